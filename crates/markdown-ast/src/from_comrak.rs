@@ -0,0 +1,258 @@
+//! Convert a [`comrak`] AST into a `markdown-ast` AST.
+//!
+//! This module is only available when the `comrak` feature is enabled.
+
+use comrak::nodes::{AstNode, ListType, NodeCodeBlock, NodeLink, NodeValue};
+
+use crate::{
+    Block, CodeBlockKind, HeadingAttributes, HeadingLevel, Inline, Inlines,
+    LineCol, LinkType, ListItem, MarkdownAstError,
+};
+
+/// Convert a [`comrak`] AST into AST [`Block`]s.
+///
+/// If `root` is a [`NodeValue::Document`], its children are converted.
+/// Otherwise `root` itself is converted into a single block.
+///
+/// Paragraphs, headings, lists, code blocks, block quotes, thematic breaks,
+/// HTML blocks, and the basic inlines (text, line breaks, emphasis, strong,
+/// strikethrough, code, links, images, and inline HTML) are supported. Any
+/// other kind of node returns a [`MarkdownAstError::Unsupported`] error, whose
+/// location is the start position of the node reported by `comrak`.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{comrak_to_ast, markdown_to_ast};
+///
+/// let arena = comrak::Arena::new();
+/// let root = comrak::parse_document(
+///     &arena,
+///     "# Title\n\nSome *text*.",
+///     &comrak::Options::default(),
+/// );
+///
+/// assert_eq!(
+///     comrak_to_ast(root),
+///     Ok(markdown_to_ast("# Title\n\nSome *text*."))
+/// );
+/// ```
+pub fn comrak_to_ast<'a>(
+    root: &'a AstNode<'a>,
+) -> Result<Vec<Block>, MarkdownAstError> {
+    if let NodeValue::Document = root.data.borrow().value {
+        return children_to_blocks(root);
+    }
+
+    Ok(vec![Block::try_from(root)?])
+}
+
+impl<'a> TryFrom<&'a AstNode<'a>> for Block {
+    type Error = MarkdownAstError;
+
+    /// Convert a [`comrak`] block node into a [`Block`].
+    ///
+    /// See [`comrak_to_ast()`] for the kinds of nodes that are supported.
+    fn try_from(node: &'a AstNode<'a>) -> Result<Block, MarkdownAstError> {
+        let block = match &node.data.borrow().value {
+            NodeValue::Paragraph => {
+                Block::Paragraph(children_to_inlines(node)?)
+            },
+            NodeValue::Heading(heading) => {
+                let level = HeadingLevel::try_from(usize::from(heading.level))
+                    .map_err(|_| unsupported(node))?;
+
+                Block::Heading(
+                    level,
+                    children_to_inlines(node)?,
+                    HeadingAttributes::default(),
+                )
+            },
+            NodeValue::List(list) => {
+                let items =
+                    node.children().map(list_item).collect::<Result<_, _>>()?;
+
+                let start = match list.list_type {
+                    ListType::Bullet => None,
+                    ListType::Ordered => Some(list.start as u64),
+                };
+
+                Block::List(items, start, list.tight)
+            },
+            NodeValue::CodeBlock(code_block) => {
+                let NodeCodeBlock {
+                    fenced,
+                    info,
+                    literal,
+                    ..
+                } = code_block;
+
+                let kind = match fenced {
+                    true => CodeBlockKind::Fenced(info.clone()),
+                    false => CodeBlockKind::Indented,
+                };
+
+                Block::CodeBlock {
+                    kind,
+                    code: literal.clone(),
+                }
+            },
+            NodeValue::BlockQuote => Block::BlockQuote {
+                kind: None,
+                blocks: children_to_blocks(node)?,
+            },
+            NodeValue::ThematicBreak => Block::Rule,
+            NodeValue::HtmlBlock(html) => Block::Html(html.literal.clone()),
+            _ => return Err(unsupported(node)),
+        };
+
+        Ok(block)
+    }
+}
+
+impl<'a> TryFrom<&'a AstNode<'a>> for Inline {
+    type Error = MarkdownAstError;
+
+    /// Convert a [`comrak`] inline node into an [`Inline`].
+    ///
+    /// See [`comrak_to_ast()`] for the kinds of nodes that are supported.
+    fn try_from(node: &'a AstNode<'a>) -> Result<Inline, MarkdownAstError> {
+        let inline = match &node.data.borrow().value {
+            NodeValue::Text(text) => Inline::Text(text.clone()),
+            NodeValue::SoftBreak => Inline::SoftBreak,
+            NodeValue::LineBreak => Inline::HardBreak,
+            NodeValue::Code(code) => Inline::Code(code.literal.clone()),
+            NodeValue::HtmlInline(html) => Inline::InlineHtml(html.clone()),
+            NodeValue::Emph => Inline::Emphasis(children_to_inlines(node)?),
+            NodeValue::Strong => Inline::Strong(children_to_inlines(node)?),
+            NodeValue::Strikethrough => {
+                Inline::Strikethrough(children_to_inlines(node)?)
+            },
+            NodeValue::Link(NodeLink { url, title }) => Inline::Link {
+                link_type: LinkType::Inline,
+                dest_url: url.clone(),
+                title: title.clone(),
+                id: String::new(),
+                content_text: children_to_inlines(node)?,
+            },
+            NodeValue::Image(NodeLink { url, title }) => Inline::Image {
+                link_type: LinkType::Inline,
+                dest_url: url.clone(),
+                title: title.clone(),
+                id: String::new(),
+                image_description: children_to_inlines(node)?,
+            },
+            _ => return Err(unsupported(node)),
+        };
+
+        Ok(inline)
+    }
+}
+
+//======================================
+// Implementation
+//======================================
+
+fn children_to_blocks<'a>(
+    node: &'a AstNode<'a>,
+) -> Result<Vec<Block>, MarkdownAstError> {
+    node.children().map(Block::try_from).collect()
+}
+
+fn children_to_inlines<'a>(
+    node: &'a AstNode<'a>,
+) -> Result<Inlines, MarkdownAstError> {
+    let mut inlines: Vec<Inline> = Vec::new();
+
+    for child in node.children() {
+        // Merge adjacent text, like markdown_to_events() does.
+        match (inlines.last_mut(), Inline::try_from(child)?) {
+            (Some(Inline::Text(previous)), Inline::Text(text)) => {
+                previous.push_str(&text)
+            },
+            (_, inline) => inlines.push(inline),
+        }
+    }
+
+    Ok(Inlines(inlines))
+}
+
+fn list_item<'a>(item: &'a AstNode<'a>) -> Result<ListItem, MarkdownAstError> {
+    let checked = match &item.data.borrow().value {
+        NodeValue::Item(_) => None,
+        NodeValue::TaskItem(symbol) => Some(symbol.is_some()),
+        _ => return Err(unsupported(item)),
+    };
+
+    Ok(ListItem(children_to_blocks(item)?, checked))
+}
+
+/// Returns a [`MarkdownAstError::Unsupported`] error for `node`.
+fn unsupported<'a>(node: &'a AstNode<'a>) -> MarkdownAstError {
+    let ast = node.data.borrow();
+
+    // Use only the name of the node kind, not its contents.
+    let value = format!("{:?}", ast.value);
+    let kind = value.split(['(', ' ']).next().unwrap_or_default();
+
+    MarkdownAstError::Unsupported {
+        construct: format!("comrak {kind} node"),
+        location: Some(LineCol {
+            line: ast.sourcepos.start.line,
+            column: ast.sourcepos.start.column,
+        }),
+    }
+}
+
+//======================================
+// Tests
+//======================================
+
+#[test]
+fn test_comrak_to_ast() {
+    use crate::markdown_to_ast;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let markdown = indoc!(
+        r#"
+        # Title
+
+        Some *emphasis*, **strong**, `code`, ![an image](photo.png), and a
+        [link](example.org "Title").
+
+        * One
+        * Two
+
+        3. Three
+
+        ```rust
+        fn main() {}
+        ```
+
+        > Quoted<br>
+
+        ---
+        "#
+    );
+
+    let arena = comrak::Arena::new();
+    let root =
+        comrak::parse_document(&arena, markdown, &comrak::Options::default());
+
+    assert_eq!(comrak_to_ast(root), Ok(markdown_to_ast(markdown)));
+
+    // Nodes that are not supported return an error.
+    let mut options = comrak::Options::default();
+    options.extension.superscript = true;
+
+    let root = comrak::parse_document(&arena, "Text\n\nx^2^", &options);
+
+    assert_eq!(
+        comrak_to_ast(root),
+        Err(MarkdownAstError::Unsupported {
+            construct: "comrak Superscript node".to_owned(),
+            location: Some(LineCol { line: 3, column: 2 }),
+        })
+    );
+}
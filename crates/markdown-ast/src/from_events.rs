@@ -5,15 +5,17 @@ use pulldown_cmark::{Event, Tag};
 use std::mem;
 
 use crate::{
-    unflatten::UnflattenedEvent, Block, CodeBlockKind, Inline, Inlines,
-    ListItem,
+    unflatten::UnflattenedEvent, Block, CodeBlockKind, HeadingAttributes,
+    Inline, Inlines, ListItem, MarkdownAstError,
 };
 
 //======================================
 // AST Builder
 //======================================
 
-pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
+pub(crate) fn ast_events_to_ast(
+    events: Vec<UnflattenedEvent>,
+) -> Result<Vec<Block>, MarkdownAstError> {
     let mut complete: Vec<Block> = vec![];
 
     let mut text_spans: Vec<Inline> = vec![];
@@ -22,12 +24,7 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
         // println!("event: {:?}", event);
 
         if !is_inline(&event) {
-            if !text_spans.is_empty() {
-                complete.push(Block::Paragraph(Inlines(mem::replace(
-                    &mut text_spans,
-                    vec![],
-                ))));
-            }
+            flush_paragraph(&mut text_spans, &mut complete);
         }
 
         match event {
@@ -43,14 +40,28 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
                 },
                 Event::SoftBreak => text_spans.push(Inline::SoftBreak),
                 Event::HardBreak => text_spans.push(Inline::HardBreak),
-                Event::Html(_) => todo!("error: unhandled inline HTML"),
-                Event::InlineHtml(_) => todo!(),
+                // pulldown-cmark only emits Event::Html inside of a
+                // Tag::HtmlBlock, but handle a stray one as an HTML block.
+                Event::Html(html) => {
+                    complete.push(Block::Html(html.to_string()))
+                },
+                Event::InlineHtml(html) => {
+                    text_spans.push(Inline::InlineHtml(html.to_string()))
+                },
                 Event::Rule => complete.push(Block::Rule),
-                Event::TaskListMarker(_) | Event::FootnoteReference(_) => {
-                    todo!("handle: {event:?}")
+                Event::FootnoteReference(label) => text_spans
+                    .push(Inline::FootnoteReference(label.to_string())),
+                // A task list marker that is not at the start of a list item,
+                // which pulldown-cmark emits for e.g. `* [ ] > quote`, is kept
+                // as plain text.
+                Event::TaskListMarker(checked) => text_spans
+                    .push(Inline::Text(task_list_marker_text(checked))),
+                Event::InlineMath(math) => {
+                    text_spans.push(Inline::InlineMath(math.to_string()))
+                },
+                Event::DisplayMath(math) => {
+                    text_spans.push(Inline::DisplayMath(math.to_string()))
                 },
-                Event::InlineMath(_) => todo!(),
-                Event::DisplayMath(_) => todo!(),
             },
             UnflattenedEvent::Nested { tag, events } => {
                 match tag {
@@ -59,15 +70,16 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
                     //
                     Tag::Emphasis => {
                         text_spans
-                            .push(Inline::Emphasis(unwrap_inlines(events)));
+                            .push(Inline::Emphasis(unwrap_inlines(events)?));
                     },
                     Tag::Strong => {
-                        text_spans.push(Inline::Strong(unwrap_inlines(events)));
+                        text_spans
+                            .push(Inline::Strong(unwrap_inlines(events)?));
                     },
                     Tag::Strikethrough => {
                         text_spans.push(Inline::Strikethrough(unwrap_inlines(
                             events,
-                        )));
+                        )?));
                     },
 
                     Tag::Link {
@@ -76,7 +88,7 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
                         title,
                         id,
                     } => {
-                        let content_text = unwrap_inlines(events);
+                        let content_text = unwrap_inlines(events)?;
 
                         text_spans.push(Inline::Link {
                             link_type,
@@ -86,52 +98,107 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
                             content_text,
                         })
                     },
+                    Tag::Image {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    } => {
+                        let image_description = unwrap_inlines(events)?;
+
+                        text_spans.push(Inline::Image {
+                            link_type,
+                            dest_url: dest_url.to_string(),
+                            title: title.to_string(),
+                            id: id.to_string(),
+                            image_description,
+                        })
+                    },
 
                     //
                     // Block content
                     //
-
-                    // TODO: Use the two Heading fields that are ignored here?
                     Tag::Heading {
                         level,
-                        id: _,
-                        classes: _,
-                        attrs: _,
+                        id,
+                        classes,
+                        attrs,
                     } => {
+                        let attributes = HeadingAttributes {
+                            id: id.map(|id| id.to_string()),
+                            classes: classes
+                                .into_iter()
+                                .map(|class| class.to_string())
+                                .collect(),
+                            attrs: attrs
+                                .into_iter()
+                                .map(|(key, value)| {
+                                    (
+                                        key.to_string(),
+                                        value.map(|value| value.to_string()),
+                                    )
+                                })
+                                .collect(),
+                        };
+
                         complete.push(Block::Heading(
                             level,
-                            unwrap_inlines(events),
+                            unwrap_inlines(events)?,
+                            attributes,
                         ));
                     },
                     // TODO(test):
                     //     Is this disappearance of the Paragraph tag correct?
-                    Tag::Paragraph => text_spans.extend(unwrap_inlines(events)),
-                    // TODO: Include the list start number in the metadata
-                    Tag::List(_start) => {
+                    Tag::Paragraph => {
+                        text_spans.extend(unwrap_inlines(events)?)
+                    },
+                    Tag::List(start) => {
                         let mut items: Vec<ListItem> = Vec::new();
 
+                        // NOTE:
+                        //  The paragraphs in the items of a tight list are NOT
+                        //  wrapped in paired Start(Tag::Paragraph) / End(_)
+                        //  events.
+                        let mut tight = true;
+
                         for event in events {
                             if let UnflattenedEvent::Nested {
                                 tag: Tag::Item,
                                 events: item_events,
                             } = event
                             {
+                                if item_events.iter().any(|event| {
+                                    matches!(
+                                        event,
+                                        UnflattenedEvent::Nested {
+                                            tag: Tag::Paragraph,
+                                            ..
+                                        }
+                                    )
+                                }) {
+                                    tight = false;
+                                }
+
+                                let (checked, item_events) =
+                                    take_task_list_marker(item_events);
                                 let item_blocks =
-                                    ast_events_to_ast(item_events);
-                                items.push(ListItem(item_blocks));
+                                    ast_events_to_ast(item_events)?;
+                                items.push(ListItem(item_blocks, checked));
                             } else {
-                                todo!("handle list element: {event:?}");
+                                return Err(unsupported(format!(
+                                    "list element: {event:?}"
+                                )));
                             }
                         }
 
-                        complete.push(Block::List(items));
+                        complete.push(Block::List(items, start, tight));
                     },
                     Tag::Item => {
-                        complete.extend(ast_events_to_ast(events));
+                        complete.extend(ast_events_to_ast(events)?);
                     },
                     Tag::CodeBlock(kind) => {
-                        let text_spans = unwrap_inlines(events);
-                        let code_text = text_to_string(&text_spans);
+                        let text_spans = unwrap_inlines(events)?;
+                        let code_text = text_to_string(&text_spans)?;
 
                         let kind = CodeBlockKind::from_pulldown_cmark(kind);
 
@@ -141,16 +208,36 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
                         })
                     },
                     Tag::BlockQuote(kind) => {
-                        let blocks = ast_events_to_ast(events);
+                        let blocks = ast_events_to_ast(events)?;
                         complete.push(Block::BlockQuote { kind, blocks })
                     },
+                    Tag::HtmlBlock => {
+                        complete.push(Block::Html(unwrap_html(events)?))
+                    },
+                    Tag::MetadataBlock(kind) => {
+                        let text_spans = unwrap_inlines(events)?;
+                        let content = text_to_string(&text_spans)?;
+
+                        complete.push(Block::Metadata { kind, content })
+                    },
+                    Tag::FootnoteDefinition(label) => {
+                        let blocks = ast_events_to_ast(events)?;
+                        complete.push(Block::FootnoteDefinition {
+                            label: label.to_string(),
+                            blocks,
+                        })
+                    },
                     Tag::Table(alignments) => {
                         let mut events = events.into_iter();
-                        let header_events = match events.next().unwrap() {
-                            UnflattenedEvent::Event(_) => panic!(),
-                            UnflattenedEvent::Nested { tag, events } => {
-                                assert!(tag == Tag::TableHead);
-                                events
+                        let header_events = match events.next() {
+                            Some(UnflattenedEvent::Nested {
+                                tag: Tag::TableHead,
+                                events,
+                            }) => events,
+                            other => {
+                                return Err(unsupported(format!(
+                                    "table without a header row: {other:?}"
+                                )))
                             },
                         };
 
@@ -158,7 +245,7 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
 
                         for table_cell in header_events {
                             let table_cell_text =
-                                unwrap_inlines(unwrap_table_cell(table_cell));
+                                unwrap_inlines(unwrap_table_cell(table_cell)?)?;
 
                             headers.push(table_cell_text);
                         }
@@ -167,10 +254,14 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
 
                         for row_events in events {
                             let row_events = match row_events {
-                                UnflattenedEvent::Event(_) => panic!(),
-                                UnflattenedEvent::Nested { tag, events } => {
-                                    assert!(tag == Tag::TableRow);
-                                    events
+                                UnflattenedEvent::Nested {
+                                    tag: Tag::TableRow,
+                                    events,
+                                } => events,
+                                other => {
+                                    return Err(unsupported(format!(
+                                        "table element: {other:?}"
+                                    )))
                                 },
                             };
 
@@ -178,8 +269,8 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
 
                             for table_cell in row_events {
                                 let table_cell_text = unwrap_inlines(
-                                    unwrap_table_cell(table_cell),
-                                );
+                                    unwrap_table_cell(table_cell)?,
+                                )?;
 
                                 row.push(table_cell_text);
                             }
@@ -193,17 +284,46 @@ pub(crate) fn ast_events_to_ast(events: Vec<UnflattenedEvent>) -> Vec<Block> {
                             rows,
                         })
                     },
-                    _ => todo!("handle: {tag:?}"),
+                    _ => {
+                        return Err(unsupported(format!(
+                            "{tag:?} in block content"
+                        )))
+                    },
                 }
             },
         }
     }
 
-    if !text_spans.is_empty() {
-        complete.push(Block::paragraph(text_spans));
+    flush_paragraph(&mut text_spans, &mut complete);
+
+    Ok(complete)
+}
+
+/// Push the pending inline content in `text_spans`, if any, as a new
+/// paragraph.
+///
+/// Leading and trailing line breaks are not content (a line break at the end
+/// of a paragraph is ignored by Markdown), so they are dropped. In particular,
+/// this means that a stray break event between two blocks does not produce a
+/// spurious paragraph.
+fn flush_paragraph(text_spans: &mut Vec<Inline>, complete: &mut Vec<Block>) {
+    let is_break = |inline: &Inline| {
+        matches!(inline, Inline::SoftBreak | Inline::HardBreak)
+    };
+
+    let mut inlines = mem::take(text_spans);
+
+    while inlines.last().is_some_and(is_break) {
+        inlines.pop();
     }
 
-    complete
+    let leading_breaks =
+        inlines.iter().take_while(|inline| is_break(inline)).count();
+    inlines.drain(..leading_breaks);
+
+    if !inlines.is_empty() {
+        complete.push(Block::paragraph(inlines));
+    }
 }
 
 /// Returns `true` if `event` contains content that can be added "inline" with text
@@ -218,14 +338,13 @@ fn is_inline(event: &UnflattenedEvent) -> bool {
             Event::Code(_) => true,
             Event::SoftBreak => true,
             Event::HardBreak => true,
-            // TODO: HTML could cause break to next block?
             Event::Html(_) => false,
-            Event::InlineHtml(_) => todo!(),
+            Event::InlineHtml(_) => true,
             Event::Rule => false,
-            Event::TaskListMarker(_) => false,
+            Event::TaskListMarker(_) => true,
             Event::FootnoteReference(_) => true,
-            Event::InlineMath(_) => todo!(),
-            Event::DisplayMath(_) => todo!(),
+            Event::InlineMath(_) => true,
+            Event::DisplayMath(_) => true,
         },
         UnflattenedEvent::Nested { tag, events: _ } => match tag {
             Tag::Emphasis | Tag::Strong | Tag::Strikethrough => true,
@@ -241,14 +360,20 @@ fn is_inline(event: &UnflattenedEvent) -> bool {
             Tag::CodeBlock(_) => false,
             Tag::BlockQuote(_kind) => false,
             Tag::Table(_) => false,
-            Tag::TableHead | Tag::TableRow => unreachable!(),
+            Tag::FootnoteDefinition(_) => false,
+            Tag::HtmlBlock => false,
             Tag::Link { .. } => true,
-            _ => todo!("handle tag: {tag:?}"),
+            Tag::Image { .. } => true,
+            // Report unexpected tags, e.g. a table row outside of a table, as
+            // unsupported blocks.
+            _ => false,
         },
     }
 }
 
-fn unwrap_inlines(events: Vec<UnflattenedEvent>) -> Inlines {
+fn unwrap_inlines(
+    events: Vec<UnflattenedEvent>,
+) -> Result<Inlines, MarkdownAstError> {
     let mut text_spans: Vec<Inline> = vec![];
 
     for event in events {
@@ -263,25 +388,33 @@ fn unwrap_inlines(events: Vec<UnflattenedEvent>) -> Inlines {
                 },
                 Event::SoftBreak => text_spans.push(Inline::SoftBreak),
                 Event::HardBreak => text_spans.push(Inline::HardBreak),
-                Event::Html(_) => todo!("error: skipping inline HTML"),
-                Event::InlineHtml(_) => todo!(),
-                Event::TaskListMarker(_)
-                | Event::Rule
-                | Event::FootnoteReference(_) => {
-                    todo!("handle: {event:?}")
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    text_spans.push(Inline::InlineHtml(html.to_string()))
+                },
+                Event::FootnoteReference(label) => text_spans
+                    .push(Inline::FootnoteReference(label.to_string())),
+                Event::TaskListMarker(checked) => text_spans
+                    .push(Inline::Text(task_list_marker_text(checked))),
+                Event::Rule => {
+                    return Err(unsupported("thematic break in inline content"))
+                },
+                Event::InlineMath(math) => {
+                    text_spans.push(Inline::InlineMath(math.to_string()))
+                },
+                Event::DisplayMath(math) => {
+                    text_spans.push(Inline::DisplayMath(math.to_string()))
                 },
-                Event::InlineMath(_) => todo!(),
-                Event::DisplayMath(_) => todo!(),
             },
             UnflattenedEvent::Nested { tag, events } => match tag {
                 Tag::Emphasis => {
-                    text_spans.push(Inline::Emphasis(unwrap_inlines(events)));
+                    text_spans.push(Inline::Emphasis(unwrap_inlines(events)?));
                 },
                 Tag::Strong => {
-                    text_spans.push(Inline::Strong(unwrap_inlines(events)));
+                    text_spans.push(Inline::Strong(unwrap_inlines(events)?));
                 },
                 Tag::Strikethrough => {
-                    text_spans.push(Inline::Strikethrough(unwrap_inlines(events)));
+                    text_spans
+                        .push(Inline::Strikethrough(unwrap_inlines(events)?));
                 },
                 Tag::Paragraph => {
                     // If this is a separate paragraph, insert two hardbreaks
@@ -293,7 +426,7 @@ fn unwrap_inlines(events: Vec<UnflattenedEvent>) -> Inlines {
                         text_spans.push(Inline::HardBreak);
                         text_spans.push(Inline::HardBreak);
                     }
-                    text_spans.extend(unwrap_inlines(events))
+                    text_spans.extend(unwrap_inlines(events)?)
                 },
                 Tag::Link {
                     link_type,
@@ -301,7 +434,7 @@ fn unwrap_inlines(events: Vec<UnflattenedEvent>) -> Inlines {
                     title,
                     id,
                 } => {
-                    let content_text = unwrap_inlines(events);
+                    let content_text = unwrap_inlines(events)?;
 
                     text_spans.push(Inline::Link {
                         link_type,
@@ -315,9 +448,9 @@ fn unwrap_inlines(events: Vec<UnflattenedEvent>) -> Inlines {
                     link_type,
                     dest_url,
                     title,
-                    id
+                    id,
                 } => {
-                    let image_description = unwrap_inlines(events);
+                    let image_description = unwrap_inlines(events)?;
 
                     text_spans.push(Inline::Image {
                         link_type,
@@ -331,7 +464,6 @@ fn unwrap_inlines(events: Vec<UnflattenedEvent>) -> Inlines {
                 //--------------------------
                 // Illegal in inline content
                 //--------------------------
-
                 Tag::Heading { .. }
                 | Tag::BlockQuote(_)
                 | Tag::CodeBlock(_)
@@ -343,27 +475,86 @@ fn unwrap_inlines(events: Vec<UnflattenedEvent>) -> Inlines {
                 | Tag::TableHead
                 | Tag::TableRow
                 | Tag::TableCell
-                | Tag::MetadataBlock(_) => panic!(
-                    "unexpected non-inline element inside inlines parse context: {tag:?}"
-                ),
+                | Tag::MetadataBlock(_) => {
+                    return Err(unsupported(format!(
+                        "{tag:?} in inline content"
+                    )))
+                },
             },
         }
     }
 
-    Inlines(text_spans)
+    Ok(Inlines(text_spans))
 }
 
-fn unwrap_table_cell(event: UnflattenedEvent) -> Vec<UnflattenedEvent> {
-    match event {
-        UnflattenedEvent::Event(_) => panic!(),
-        UnflattenedEvent::Nested { tag, events } => {
-            assert_eq!(tag, Tag::TableCell, "expected to get Tag::TableCell");
-            events
+/// Remove the task list marker from the start of the events of a list item, if
+/// present, returning the checkbox state it records.
+///
+/// In a tight list, the marker is the first event of the item. In a loose
+/// list, it is the first event of the first paragraph of the item.
+fn take_task_list_marker(
+    mut item_events: Vec<UnflattenedEvent>,
+) -> (Option<bool>, Vec<UnflattenedEvent>) {
+    let events = match item_events.first_mut() {
+        Some(UnflattenedEvent::Nested {
+            tag: Tag::Paragraph,
+            events,
+        }) => events,
+        _ => &mut item_events,
+    };
+
+    let checked = match events.first() {
+        Some(UnflattenedEvent::Event(Event::TaskListMarker(checked))) => {
+            Some(*checked)
         },
+        _ => None,
+    };
+
+    if checked.is_some() {
+        events.remove(0);
     }
+
+    (checked, item_events)
 }
 
-fn text_to_string(Inlines(text_spans): &Inlines) -> String {
+/// Returns the Markdown text of a task list marker, e.g. `[x] `.
+fn task_list_marker_text(checked: bool) -> String {
+    let marker = if checked { "[x] " } else { "[ ] " };
+
+    marker.to_owned()
+}
+
+/// Concatenate the lines of HTML in the events of a [`Tag::HtmlBlock`].
+fn unwrap_html(
+    events: Vec<UnflattenedEvent>,
+) -> Result<String, MarkdownAstError> {
+    let mut html = String::new();
+
+    for event in events {
+        match event {
+            UnflattenedEvent::Event(Event::Html(line)) => html.push_str(&line),
+            _ => return Err(unsupported(format!("{event:?} in HTML block"))),
+        }
+    }
+
+    Ok(html)
+}
+
+fn unwrap_table_cell(
+    event: UnflattenedEvent,
+) -> Result<Vec<UnflattenedEvent>, MarkdownAstError> {
+    match event {
+        UnflattenedEvent::Nested {
+            tag: Tag::TableCell,
+            events,
+        } => Ok(events),
+        _ => Err(unsupported(format!("table row element: {event:?}"))),
+    }
+}
+
+fn text_to_string(
+    Inlines(text_spans): &Inlines,
+) -> Result<String, MarkdownAstError> {
     let mut string = String::new();
 
     for span in text_spans {
@@ -377,9 +568,20 @@ fn text_to_string(Inlines(text_spans): &Inlines) -> String {
             Inline::HardBreak => {
                 string.push_str("\n");
             },
-            _ => todo!("handle span: {span:?}"),
+            _ => return Err(unsupported(format!("{span:?} in code block"))),
         }
     }
 
-    string
+    Ok(string)
+}
+
+/// Returns a [`MarkdownAstError::Unsupported`] error for `construct`.
+///
+/// The location is filled in by [`try_markdown_to_ast()`][crate::try_markdown_to_ast],
+/// which has access to the source offsets of the input.
+fn unsupported(construct: impl Into<String>) -> MarkdownAstError {
+    MarkdownAstError::Unsupported {
+        construct: construct.into(),
+        location: None,
+    }
 }
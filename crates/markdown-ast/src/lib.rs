@@ -36,15 +36,23 @@
 //!
 //! # API Overview
 //!
-//! | Function                           | Input      | Output       |
-//! |------------------------------------|------------|--------------|
-//! | [`markdown_to_ast()`]              | `&str`     | `Vec<Block>` |
-//! | [`ast_to_markdown()`]              | `&[Block]` | `String`     |
-//! | [`ast_to_events()`]                | `&[Block]` | `Vec<Event>` |
-//! | [`events_to_ast()`]                | `&[Event]` | `Vec<Block>` |
-//! | [`events_to_markdown()`]           | `&[Event]` | `String`     |
-//! | [`markdown_to_events()`]           | `&str`     | `Vec<Event>` |
-//! | [`canonicalize()`]                 | `&str`     | `String`     |
+//! | Function                           | Input      | Output               |
+//! |------------------------------------|------------|----------------------|
+//! | [`markdown_to_ast()`]              | `&str`     | `Vec<Block>`         |
+//! | [`markdown_to_ast_with_options()`] | `&str`     | `Result<Vec<Block>>` |
+//! | [`markdown_to_ast_iter()`]         | `&str`     | `Iterator<Block>`    |
+//! | [`ast_to_markdown()`]              | `&[Block]` | `String`             |
+//! | [`ast_to_markdown_with_options()`] | `&[Block]` | `String`             |
+//! | [`ast_to_events()`]                | `&[Block]` | `Vec<Event>`         |
+//! | [`ast_event_iter()`]               | `&[Block]` | `Iterator<Event>`    |
+//! | [`events_to_ast()`]                | `&[Event]` | `Vec<Block>`         |
+//! | [`try_events_to_ast()`]            | `&[Event]` | `Result<Vec<Block>>` |
+//! | [`events_to_markdown()`]           | `&[Event]` | `String`             |
+//! | [`markdown_to_events()`]           | `&str`     | `Vec<Event>`         |
+//! | [`markdown_to_event_tree()`]       | `&str`     | `Vec<EventTree>`     |
+//! | [`canonicalize()`]                 | `&str`     | `String`             |
+//! | [`canonicalize_with_options()`]    | `&str`     | `String`             |
+//! | [`ast_to_sexpr()`]                 | `&[Block]` | `String`             |
 //!
 //! ##### Terminology
 //!
@@ -87,7 +95,8 @@
 //!
 //! ```
 //! use markdown_ast::{
-//!     markdown_to_ast, Block, HeadingLevel, Inline, Inlines, ListItem
+//!     markdown_to_ast, Block, HeadingAttributes, HeadingLevel, Inline,
+//!     Inlines, ListItem
 //! };
 //! # use pretty_assertions::assert_eq;
 //!
@@ -105,7 +114,8 @@
 //!         HeadingLevel::H1,
 //!         Inlines(vec![
 //!              Inline::Text("An Example Document".to_owned())
-//!         ])
+//!         ]),
+//!         HeadingAttributes::default()
 //!     ),
 //!     Block::Paragraph(Inlines(vec![
 //!         Inline::Text("This is a paragraph that".to_owned()),
@@ -121,8 +131,8 @@
 //!             Block::Paragraph(Inlines(vec![
 //!                 Inline::Text("This is a list item".to_owned())
 //!             ]))
-//!         ])
-//!     ])
+//!         ], None)
+//!     ], None, true)
 //! ]);
 //! ```
 //!
@@ -136,7 +146,7 @@
 //! ```
 //! use markdown_ast::{
 //!     ast_to_markdown, Block, Inline, Inlines, ListItem,
-//!     HeadingLevel,
+//!     HeadingAttributes, HeadingLevel,
 //! };
 //! # use pretty_assertions::assert_eq;
 //!
@@ -147,7 +157,11 @@
 //! ];
 //!
 //! let ast = vec![
-//!     Block::Heading(HeadingLevel::H1, Inlines::plain_text("Tech Companies")),
+//!     Block::Heading(
+//!         HeadingLevel::H1,
+//!         Inlines::plain_text("Tech Companies"),
+//!         HeadingAttributes::default()
+//!     ),
 //!     Block::plain_text_paragraph("The following are major tech companies:"),
 //!     Block::List(Vec::from_iter(
 //!         tech_companies
@@ -158,10 +172,10 @@
 //!                     Block::List(vec![
 //!                         ListItem::plain_text(format!("Founded: {founded}")),
 //!                         ListItem::plain_text(format!("Employee count: {employee_count}"))
-//!                     ])
-//!                 ])
+//!                     ], None, false)
+//!                 ], None)
 //!             })
-//!     ))
+//!     ), None, false)
 //! ];
 //!
 //! let markdown: String = ast_to_markdown(&ast);
@@ -192,13 +206,13 @@
 //!
 //! ```
 //!
-//! # Known Issues
+//! # Escaping
 //!
-//! Currently `markdown-ast` does not escape Markdown content appearing in
-//! leaf inline text:
+//! Markdown characters appearing in [`Inline::Text`] are escaped when written
+//! out, so that text is never interpreted as formatting:
 //!
 //! ```
-//! use markdown_ast::{ast_to_markdown, Block};
+//! use markdown_ast::{ast_to_markdown, markdown_to_ast, Block};
 //!
 //! let ast = vec![
 //!     Block::plain_text_paragraph("In the equation a*b*c ...")
@@ -206,27 +220,26 @@
 //!
 //! let markdown = ast_to_markdown(&ast);
 //!
-//! assert_eq!(markdown, "In the equation a*b*c ...");
-//! ```
-//!
-//! which will render as:
-//!
-//! > In the equation a*b*c ...
+//! assert_eq!(markdown, r"In the equation a\*b\*c ...");
 //!
-//! with the asterisks interpreted as emphasis formatting markers, contrary to
-//! the intention of the author.
-//!
-//! Fixing this robustly will require either:
+//! assert_eq!(markdown_to_ast(&markdown), ast);
+//! ```
 //!
-//! * Adding automatic escaping of Markdown characters in [`Inline::Text`]
-//!   during rendering (not ideal)
+//! This means that an [`Inline::Text`] cannot be used to insert raw Markdown
+//! syntax into the output. Use the corresponding [`Inline`] variant instead,
+//! e.g. [`Inline::Emphasis`].
 //!
-//! * Adding pre-construction validation checks for [`Inline::Text`] that
-//!   prevent constructing an `Inline` with Markdown formatting characters that
-//!   have not been escaped correctly by the user.
+//! # Crate Features
 //!
-//! In either case, fixing this bug will be considered a **semver exempt**
-//! change in behavior to `markdown-ast`.
+//! * `serde` — Implement `serde::Serialize` and `serde::Deserialize` for the
+//!   AST types, e.g. for caching a parsed document as JSON. This enables the
+//!   `serde` feature of `pulldown-cmark`, which implements the same traits for
+//!   the `pulldown-cmark` types used in the AST, like [`HeadingLevel`].
+//! * `comrak` — Add `comrak_to_ast()`, and `TryFrom` impls for [`Block`] and
+//!   [`Inline`], for converting a [`comrak`](https://crates.io/crates/comrak)
+//!   AST into a `markdown-ast` AST, e.g. when migrating from `comrak`.
+//! * `ratatui` — Add `inlines_to_ratatui_text()`, for rendering inline content
+//!   as styled [`ratatui`](https://crates.io/crates/ratatui) terminal text.
 //!
 //! # Motivation and relation to `pulldown-cmark`
 //!
@@ -254,6 +267,18 @@ mod unflatten;
 
 mod from_events;
 mod to_events;
+mod to_markdown;
+
+mod positions;
+mod sexpr;
+mod stats;
+
+#[cfg(feature = "comrak")]
+mod from_comrak;
+#[cfg(feature = "ratatui")]
+mod to_ratatui;
+
+pub mod visit;
 
 /// Ensure that doc tests in the README.md file get run.
 ///
@@ -262,9 +287,29 @@ mod test_readme {
     #![doc = include_str!("../README.md")]
 }
 
+use std::collections::{HashMap, HashSet};
+
 use pulldown_cmark::{self as md, CowStr, Event};
 
-pub use pulldown_cmark::{HeadingLevel, LinkType};
+use crate::visit::VisitorMut;
+
+pub use pulldown_cmark::{
+    Alignment, BlockQuoteKind, HeadingLevel, LinkType, MetadataBlockKind,
+};
+
+pub use crate::{
+    positions::{
+        markdown_to_ast_with_offsets, markdown_to_ast_with_positions, LineCol,
+    },
+    sexpr::ast_to_sexpr,
+    stats::{document_stats, word_count_with, DocumentStats},
+    unflatten::{event_tree_to_events, markdown_to_event_tree, EventTree},
+};
+
+#[cfg(feature = "comrak")]
+pub use crate::from_comrak::comrak_to_ast;
+#[cfg(feature = "ratatui")]
+pub use crate::to_ratatui::inlines_to_ratatui_text;
 
 //======================================
 // AST Representation
@@ -274,14 +319,22 @@ pub use pulldown_cmark::{HeadingLevel, LinkType};
 /// (CommonMark: [blocks](https://spec.commonmark.org/0.30/#blocks),
 /// [container blocks](https://spec.commonmark.org/0.30/#container-blocks))
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Block {
     /// CommonMark: [paragraphs](https://spec.commonmark.org/0.30/#paragraphs)
     Paragraph(Inlines),
 
-    /// CommonMark: [lists](https://spec.commonmark.org/0.30/#lists)
-    List(Vec<ListItem>),
+    /// A bullet list, or an ordered list if the start number is `Some`, and
+    /// whether the list is tight.
+    ///
+    /// The items of a tight list are written without blank lines between
+    /// them, and those of a loose list with blank lines between them.
+    ///
+    /// CommonMark: [lists](https://spec.commonmark.org/0.30/#lists),
+    /// [tight and loose lists](https://spec.commonmark.org/0.30/#loose)
+    List(Vec<ListItem>, Option<u64>, bool),
     /// CommonMark: [ATX heading](https://spec.commonmark.org/0.30/#atx-heading)
-    Heading(HeadingLevel, Inlines),
+    Heading(HeadingLevel, Inlines, HeadingAttributes),
     /// An indented or fenced code block.
     ///
     /// CommonMark: [indented code blocks](https://spec.commonmark.org/0.30/#indented-code-blocks),
@@ -294,35 +347,108 @@ pub enum Block {
         ///
         /// CommonMark: [info string](https://spec.commonmark.org/0.30/#info-string)
         kind: CodeBlockKind,
+        /// The code in the block.
+        ///
+        /// Each line of parsed code ends in a newline, except possibly the
+        /// last line of an indented code block at the end of the input. When
+        /// written out, the last line is always ended with a newline, so code
+        /// with and without a trailing newline is written out identically, and
+        /// parses back with a trailing newline.
         code: String,
     },
     /// CommonMark: [block quotes](https://spec.commonmark.org/0.30/#block-quotes)
     BlockQuote {
-        // TODO: Document
+        /// The kind of GitHub-style alert this block quote is, if any.
+        ///
+        /// A block quote whose first line is an alert marker like `[!NOTE]`
+        /// is parsed with a `kind`, and written back out with that marker.
+        /// A block quote with no `kind` is written out as a plain `>` quote.
         kind: Option<md::BlockQuoteKind>,
         blocks: Vec<Block>,
     },
+    /// A GitHub Flavored Markdown table.
+    ///
+    /// Table cells cannot contain literal newlines; hard breaks in a cell are
+    /// written out as `<br>`. `<br>` tags in a cell are parsed as
+    /// [`Inline::InlineHtml`], see [`normalize_table_breaks()`].
+    ///
+    /// Pipes (`|`) in a cell, including inside an [`Inline::Code`], are
+    /// escaped as `\|` when written out.
     Table {
         alignments: Vec<md::Alignment>,
         headers: Vec<Inlines>,
         rows: Vec<Vec<Inlines>>,
     },
     /// CommonMark: [thematic breaks](https://spec.commonmark.org/0.30/#thematic-breaks)
+    ///
+    /// Always written out as `---`, preceded by a blank line so that it cannot
+    /// be read as a setext heading underline, including inside block quotes
+    /// and list items.
     Rule,
+    /// A footnote definition, e.g. `[^1]: A note.`
+    ///
+    /// Footnotes are not part of the CommonMark standard.
+    ///
+    /// GitHub Flavored Markdown: [footnotes](https://docs.github.com/en/get-started/writing-on-github/getting-started-with-writing-and-formatting-on-github/basic-writing-and-formatting-syntax#footnotes)
+    FootnoteDefinition {
+        /// The footnote label, e.g. `1` in `[^1]: A note.`
+        label: String,
+        blocks: Vec<Block>,
+    },
+    /// A block of raw HTML, e.g. `<div>...</div>`, containing the HTML source
+    /// of the block, including its trailing newline.
+    ///
+    /// CommonMark: [HTML blocks](https://spec.commonmark.org/0.30/#html-blocks)
+    Html(String),
+    /// A metadata block at the start of a document, e.g. YAML frontmatter
+    /// delimited by `---` lines, or TOML frontmatter delimited by `+++` lines.
+    ///
+    /// Metadata blocks are not part of the CommonMark standard. See
+    /// [`pulldown_cmark::Options::ENABLE_YAML_STYLE_METADATA_BLOCKS`].
+    Metadata {
+        /// The delimiters used for this metadata block.
+        kind: MetadataBlockKind,
+        /// The raw content of the block, excluding the delimiter lines, e.g.
+        /// `"title: X\n"` for `---\ntitle: X\n---`.
+        content: String,
+    },
 }
 
 /// A sequence of [`Inline`]s.
 /// (CommonMark: [inlines](https://spec.commonmark.org/0.30/#inlines))
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Inlines(pub Vec<Inline>);
 
+/// The attributes of a [`Block::Heading`], e.g. `{#my-id .class key=val}`.
+///
+/// Heading attributes are an extension to CommonMark, written at the end of
+/// the heading line. See [`pulldown_cmark::Options::ENABLE_HEADING_ATTRIBUTES`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeadingAttributes {
+    /// The id of the heading, e.g. `my-id` for `{#my-id}`.
+    pub id: Option<String>,
+    /// The classes of the heading, e.g. `class` for `{.class}`.
+    pub classes: Vec<String>,
+    /// Other attributes of the heading, e.g. `("key", Some("val"))` for
+    /// `{key=val}`, or `("key", None)` for `{key}`.
+    pub attrs: Vec<(String, Option<String>)>,
+}
+
 /// An item in a list. (CommonMark: [list items](https://spec.commonmark.org/0.30/#list-items))
+///
+/// The second field is the checkbox state of a GitHub-style task list item,
+/// e.g. `Some(true)` for `* [x] done`, or `None` if this item is not a task
+/// list item. See [`ListItem::checked()`].
 #[derive(Debug, Clone, PartialEq)]
-pub struct ListItem(pub Vec<Block>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListItem(pub Vec<Block>, pub Option<bool>);
 
 /// An inline piece of atomic Markdown content.
 /// (CommonMark: [inlines](https://spec.commonmark.org/0.30/#inlines))
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Inline {
     /// CommonMark: [textual content](https://spec.commonmark.org/0.30/#textual-content)
     ///
@@ -388,6 +514,11 @@ pub enum Inline {
     ///     Inline::Code("fn code()".to_owned())
     /// );
     /// ```
+    ///
+    /// Code spans have no language or class. Attribute syntax used by some
+    /// Markdown flavors to hint the language of a code span, e.g.
+    /// `` `code`{.rust} ``, is not supported by `pulldown-cmark`, and is parsed
+    /// as ordinary text following the code span.
     Code(String),
 
     /// CommonMark: [links](https://spec.commonmark.org/0.30/#links)
@@ -415,6 +546,10 @@ pub enum Inline {
     Link {
         link_type: md::LinkType,
         /// CommonMark: [link destination](https://spec.commonmark.org/0.30/#link-destination)
+        ///
+        /// Character references in the destination, e.g. `&amp;`, are decoded
+        /// when parsed, and re-escaped where necessary when written out.
+        /// (The destination of an autolink is stored exactly as written.)
         dest_url: String,
         /// CommonMark: [link title](https://spec.commonmark.org/0.30/#link-title)
         title: String,
@@ -459,14 +594,248 @@ pub enum Inline {
 
     /// CommonMark: [hard line breaks](https://spec.commonmark.org/0.30/#hard-line-breaks)
     HardBreak,
+
+    /// A reference to a footnote, e.g. `[^1]`, containing the footnote label.
+    ///
+    /// A footnote reference is only parsed if a matching
+    /// [`Block::FootnoteDefinition`] exists in the document.
+    ///
+    /// ```
+    /// # use markdown_ast::{markdown_to_ast, Block, Inline};
+    /// #
+    /// assert_eq!(
+    ///     markdown_to_ast("Text[^1]\n\n[^1]: A note."),
+    ///     vec![
+    ///         Block::paragraph(vec![
+    ///             Inline::plain_text("Text"),
+    ///             Inline::FootnoteReference("1".to_owned()),
+    ///         ]),
+    ///         Block::FootnoteDefinition {
+    ///             label: "1".to_owned(),
+    ///             blocks: vec![Block::plain_text_paragraph("A note.")],
+    ///         },
+    ///     ]
+    /// );
+    /// ```
+    FootnoteReference(String),
+
+    /// Inline math, e.g. `$x^2$`, containing the TeX source of the math.
+    ///
+    /// Math is not part of the CommonMark standard.
+    ///
+    /// ```
+    /// # use markdown_ast::Inline;
+    /// #
+    /// assert_eq!(
+    ///     Inline::parse("$x^2$"),
+    ///     Inline::InlineMath("x^2".to_owned())
+    /// );
+    /// ```
+    InlineMath(String),
+
+    /// Display math, e.g. `$$\int_0^1 x\,dx$$`, containing the TeX source of
+    /// the math.
+    ///
+    /// Like `pulldown-cmark`, display math is represented as inline content,
+    /// appearing inside a [`Block::Paragraph`].
+    ///
+    /// ```
+    /// # use markdown_ast::Inline;
+    /// #
+    /// assert_eq!(
+    ///     Inline::parse("$$x^2$$"),
+    ///     Inline::DisplayMath("x^2".to_owned())
+    /// );
+    /// ```
+    DisplayMath(String),
+
+    /// A piece of raw inline HTML, e.g. an opening tag like `<sub>`.
+    ///
+    /// The opening and closing tags of an HTML element are separate inlines,
+    /// with the content between them parsed as Markdown.
+    ///
+    /// ```
+    /// # use markdown_ast::{markdown_to_ast, Block, Inline};
+    /// #
+    /// assert_eq!(
+    ///     markdown_to_ast("H<sub>2</sub>O"),
+    ///     vec![Block::paragraph(vec![
+    ///         Inline::plain_text("H"),
+    ///         Inline::InlineHtml("<sub>".to_owned()),
+    ///         Inline::plain_text("2"),
+    ///         Inline::InlineHtml("</sub>".to_owned()),
+    ///         Inline::plain_text("O"),
+    ///     ])]
+    /// );
+    /// ```
+    ///
+    /// CommonMark: [raw HTML](https://spec.commonmark.org/0.30/#raw-html)
+    InlineHtml(String),
+
+    /// An inline annotated with the line and column in the source Markdown
+    /// where it starts.
+    ///
+    /// Positions are only recorded by [`markdown_to_ast_with_positions()`];
+    /// other parsing functions never produce this variant. A positioned inline
+    /// is otherwise treated the same as the inline it wraps, e.g. it is written
+    /// out as Markdown without any annotation.
+    ///
+    /// ```
+    /// # use markdown_ast::{markdown_to_ast_with_positions, Block, Inline, LineCol};
+    /// #
+    /// assert_eq!(
+    ///     markdown_to_ast_with_positions("Text"),
+    ///     vec![Block::paragraph(vec![Inline::Positioned(
+    ///         LineCol { line: 1, column: 1 },
+    ///         Box::new(Inline::plain_text("Text"))
+    ///     )])]
+    /// );
+    /// ```
+    Positioned(LineCol, Box<Inline>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CodeBlockKind {
     Fenced(String),
     Indented,
 }
 
+//======================================
+// Options
+//======================================
+
+/// Options controlling how AST [`Block`]s are written out as Markdown.
+///
+/// The [`Default`] options produce exactly the same output as
+/// [`ast_to_markdown()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownOptions {
+    /// The number of backticks used to fence a fenced code block.
+    ///
+    /// Default: `3`
+    pub code_block_token_count: usize,
+
+    /// If set, tabs in the content of code blocks are expanded to spaces,
+    /// using tab stops every this many columns.
+    ///
+    /// Default: `None` (tabs are preserved)
+    pub code_block_tab_width: Option<usize>,
+
+    /// If `true`, the link reference definitions written at the end of the
+    /// document are sorted alphabetically by label, ignoring case, instead of
+    /// being written in the order their links first appear.
+    ///
+    /// This keeps the definitions in a stable order as links are added to or
+    /// moved within a document, which makes diffs of the output smaller.
+    ///
+    /// Default: `false`
+    pub sort_reference_definitions: bool,
+
+    /// If `true`, bare `http://` and `https://` URLs appearing in
+    /// [`Inline::Text`] are written out as autolinks, e.g. `<https://example.org>`,
+    /// so that they are clickable in renderers that do not detect bare URLs.
+    ///
+    /// URLs inside the text of a link or image are left unchanged.
+    ///
+    /// Default: `false`
+    pub autolink_bare_urls: bool,
+
+    /// The marker used for the items of a bullet list.
+    ///
+    /// Default: [`ListToken::Star`]
+    pub list_token: ListToken,
+
+    /// The number of blank lines written between consecutive blocks, e.g.
+    /// between two paragraphs, or after a heading.
+    ///
+    /// Blank lines are not preserved in the AST, so any number of blank lines
+    /// between two blocks in parsed Markdown is written out as exactly this
+    /// many.
+    ///
+    /// With `0`, consecutive paragraphs are no longer separated, and are
+    /// parsed back as a single paragraph.
+    ///
+    /// Default: `1`
+    pub blank_lines_between_blocks: usize,
+
+    /// If `true`, the cells of each table are padded with spaces so that the
+    /// pipes separating its columns line up, e.g.
+    ///
+    /// ```markdown
+    /// | Name  | Value |
+    /// |-------|------:|
+    /// | a     |     1 |
+    /// ```
+    ///
+    /// Default: `false`
+    pub pad_table_columns: bool,
+}
+
+/// The marker character written at the start of each item of a bullet list.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ListToken {
+    /// `*`
+    Star,
+    /// `-`
+    Dash,
+    /// `+`
+    Plus,
+}
+
+/// Options controlling how a Markdown input string is parsed by
+/// [`markdown_to_ast_with_options()`].
+///
+/// The [`Default`] options parse exactly the same way as
+/// [`markdown_to_ast()`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    /// The maximum number of [`Event`]s that parsing the input may produce.
+    ///
+    /// If parsing the input would produce more events than this, parsing stops
+    /// and [`MarkdownAstError::EventLimitExceeded`] is returned. This can be
+    /// used to bound the amount of work done on untrusted input.
+    ///
+    /// Default: `None` (no limit)
+    pub max_events: Option<usize>,
+
+    /// The [`pulldown_cmark`] extensions to enable while parsing, e.g. as
+    /// returned by [`parse_options_from_str()`].
+    ///
+    /// Constructs enabled by an extension that cannot be represented in the
+    /// AST cause [`MarkdownAstError::Unsupported`] to be returned.
+    ///
+    /// Default: `None` (the extensions enabled by [`markdown_to_ast()`])
+    pub extensions: Option<md::Options>,
+}
+
+//======================================
+// Errors
+//======================================
+
+/// An error that occurred while processing Markdown.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum MarkdownAstError {
+    /// Parsing the input produced more [`Event`]s than the
+    /// [`ParseOptions::max_events`] limit.
+    EventLimitExceeded { max_events: usize },
+
+    /// The input contains a Markdown construct that cannot be represented in
+    /// the AST.
+    ///
+    /// `location` is the approximate position in the input of the top-level
+    /// block containing the construct, if known.
+    Unsupported {
+        construct: String,
+        location: Option<LineCol>,
+    },
+
+    /// A feature name passed to [`parse_options_from_str()`] is not the name
+    /// of a known parser extension.
+    UnknownFeature { name: String },
+}
+
 //======================================
 // Public API Functions
 //======================================
@@ -495,16 +864,273 @@ pub fn markdown_to_ast(input: &str) -> Vec<Block> {
     }
     */
 
+    return try_markdown_to_ast(input)
+        .expect("unsupported construct in Markdown input");
+}
+
+/// Parse Markdown input string into AST [`Block`]s, returning an error instead
+/// of panicking if `input` contains a construct that cannot be represented in
+/// the AST.
+///
+/// # Errors
+///
+/// Returns [`MarkdownAstError::Unsupported`] describing the unsupported
+/// construct, with the line and column of the top-level block that contains
+/// it.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{try_markdown_to_ast, Block};
+///
+/// assert_eq!(
+///     try_markdown_to_ast("Some text."),
+///     Ok(vec![Block::plain_text_paragraph("Some text.")])
+/// );
+/// ```
+pub fn try_markdown_to_ast(
+    input: &str,
+) -> Result<Vec<Block>, MarkdownAstError> {
     let events = markdown_to_events(input);
 
-    return events_to_ast(events);
+    return try_events_to_ast(events)
+        .map_err(|error| locate_unsupported(input, parser_options(), error));
+}
+
+/// Parse Markdown input string into AST [`Block`]s, according to `options`.
+///
+/// # Errors
+///
+/// Returns [`MarkdownAstError::EventLimitExceeded`] if parsing `input` would
+/// produce more events than [`ParseOptions::max_events`]. Parsing stops as
+/// soon as the limit is exceeded.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{
+///     markdown_to_ast_with_options, Block, MarkdownAstError, ParseOptions
+/// };
+///
+/// let options = ParseOptions {
+///     max_events: Some(3),
+///     ..ParseOptions::default()
+/// };
+///
+/// assert_eq!(
+///     markdown_to_ast_with_options("Some text.", &options),
+///     Ok(vec![Block::plain_text_paragraph("Some text.")])
+/// );
+///
+/// assert_eq!(
+///     markdown_to_ast_with_options("*Some* text.", &options),
+///     Err(MarkdownAstError::EventLimitExceeded { max_events: 3 })
+/// );
+/// ```
+pub fn markdown_to_ast_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<Vec<Block>, MarkdownAstError> {
+    let ParseOptions {
+        max_events,
+        extensions,
+    } = *options;
+
+    let extensions = extensions.unwrap_or_else(parser_options);
+
+    let parser = md::Parser::new_ext(input, extensions);
+
+    let mut events: Vec<Event> = Vec::new();
+
+    for event in md::TextMergeStream::new(parser) {
+        if let Some(max_events) = max_events {
+            if events.len() >= max_events {
+                return Err(MarkdownAstError::EventLimitExceeded {
+                    max_events,
+                });
+            }
+        }
+
+        events.push(event);
+    }
+
+    return try_events_to_ast(events)
+        .map_err(|error| locate_unsupported(input, extensions, error));
+}
+
+/// Parse a comma-separated list of [`pulldown_cmark`] extension names into
+/// [`pulldown_cmark::Options`], for use as [`ParseOptions::extensions`].
+///
+/// The recognized names are:
+///
+/// | Name                 | Extension                                         |
+/// |----------------------|---------------------------------------------------|
+/// | `tables`             | [`ENABLE_TABLES`][md::Options::ENABLE_TABLES]     |
+/// | `footnotes`          | [`ENABLE_FOOTNOTES`][md::Options::ENABLE_FOOTNOTES] |
+/// | `strikethrough`      | [`ENABLE_STRIKETHROUGH`][md::Options::ENABLE_STRIKETHROUGH] |
+/// | `tasklists`          | [`ENABLE_TASKLISTS`][md::Options::ENABLE_TASKLISTS] |
+/// | `smart-punctuation`  | [`ENABLE_SMART_PUNCTUATION`][md::Options::ENABLE_SMART_PUNCTUATION] |
+/// | `heading-attributes` | [`ENABLE_HEADING_ATTRIBUTES`][md::Options::ENABLE_HEADING_ATTRIBUTES] |
+/// | `math`               | [`ENABLE_MATH`][md::Options::ENABLE_MATH]         |
+/// | `gfm`                | [`ENABLE_GFM`][md::Options::ENABLE_GFM]           |
+///
+/// Names are matched case-insensitively, and whitespace around each name is
+/// ignored. An empty string enables no extensions.
+///
+/// # Errors
+///
+/// Returns [`MarkdownAstError::UnknownFeature`] if any name is not one of the
+/// names above.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{
+///     markdown_to_ast_with_options, parse_options_from_str, Block,
+///     ParseOptions,
+/// };
+///
+/// let options = ParseOptions {
+///     extensions: Some(parse_options_from_str("tables, math").unwrap()),
+///     ..ParseOptions::default()
+/// };
+///
+/// // Strikethrough is not enabled.
+/// assert_eq!(
+///     markdown_to_ast_with_options("~~text~~", &options),
+///     Ok(vec![Block::plain_text_paragraph("~~text~~")])
+/// );
+/// ```
+pub fn parse_options_from_str(
+    features: &str,
+) -> Result<md::Options, MarkdownAstError> {
+    let mut options = md::Options::empty();
+
+    for name in features.split(',').map(str::trim) {
+        if name.is_empty() {
+            continue;
+        }
+
+        let option = match name.to_ascii_lowercase().as_str() {
+            "tables" => md::Options::ENABLE_TABLES,
+            "footnotes" => md::Options::ENABLE_FOOTNOTES,
+            "strikethrough" => md::Options::ENABLE_STRIKETHROUGH,
+            "tasklists" => md::Options::ENABLE_TASKLISTS,
+            "smart-punctuation" => md::Options::ENABLE_SMART_PUNCTUATION,
+            "heading-attributes" => md::Options::ENABLE_HEADING_ATTRIBUTES,
+            "math" => md::Options::ENABLE_MATH,
+            "gfm" => md::Options::ENABLE_GFM,
+            _ => {
+                return Err(MarkdownAstError::UnknownFeature {
+                    name: name.to_owned(),
+                })
+            },
+        };
+
+        options.insert(option);
+    }
+
+    return Ok(options);
+}
+
+/// Parse Markdown input string into AST [`Block`]s, incrementally.
+///
+/// Each top-level [`Block`] is produced as soon as the events that make it up
+/// have been parsed, so the complete AST of `input` never needs to be held in
+/// memory at once. The blocks produced are the same as those returned by
+/// [`markdown_to_ast()`].
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{
+///     markdown_to_ast_iter, Block, HeadingAttributes, HeadingLevel, Inlines,
+/// };
+///
+/// let mut blocks = markdown_to_ast_iter("# Title\n\nSome text.");
+///
+/// assert_eq!(
+///     blocks.next(),
+///     Some(Block::Heading(
+///         HeadingLevel::H1,
+///         Inlines::plain_text("Title"),
+///         HeadingAttributes::default()
+///     ))
+/// );
+/// assert_eq!(blocks.next(), Some(Block::plain_text_paragraph("Some text.")));
+/// assert_eq!(blocks.next(), None);
+/// ```
+pub fn markdown_to_ast_iter(input: &str) -> impl Iterator<Item = Block> + '_ {
+    let mut events = markdown_to_events(input);
+
+    let top_level_events = std::iter::from_fn(move || {
+        let mut block_events: Vec<Event> = Vec::new();
+        let mut depth: usize = 0;
+
+        for event in events.by_ref() {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => (),
+            }
+
+            block_events.push(event);
+
+            if depth == 0 {
+                break;
+            }
+        }
+
+        if block_events.is_empty() {
+            None
+        } else {
+            Some(block_events)
+        }
+    });
+
+    return top_level_events.flat_map(events_to_ast);
 }
 
 /// Convert AST [`Block`]s into a Markdown string.
+///
+/// This is equivalent to [`ast_to_markdown_with_options()`] with the default
+/// [`MarkdownOptions`].
 pub fn ast_to_markdown(blocks: &[Block]) -> String {
+    return ast_to_markdown_with_options(blocks, &MarkdownOptions::default());
+}
+
+/// Convert AST [`Block`]s into a Markdown string, formatted according to
+/// `options`.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{
+///     ast_to_markdown_with_options, Block, CodeBlockKind, MarkdownOptions
+/// };
+///
+/// let ast = vec![Block::CodeBlock {
+///     kind: CodeBlockKind::Fenced("rust".to_owned()),
+///     code: "fn main() {}\n".to_owned(),
+/// }];
+///
+/// let options = MarkdownOptions {
+///     code_block_token_count: 4,
+///     ..MarkdownOptions::default()
+/// };
+///
+/// assert_eq!(
+///     ast_to_markdown_with_options(&ast, &options),
+///     "\n````rust\nfn main() {}\n````"
+/// );
+/// ```
+pub fn ast_to_markdown_with_options(
+    blocks: &[Block],
+    options: &MarkdownOptions,
+) -> String {
     let events = ast_to_events(blocks);
 
-    return events_to_markdown(events);
+    return events_to_markdown_with_options(events, options);
 }
 
 /// Convert [`Event`]s into a Markdown string.
@@ -515,19 +1141,25 @@ pub fn ast_to_markdown(blocks: &[Block]) -> String {
 pub fn events_to_markdown<'e, I: IntoIterator<Item = Event<'e>>>(
     events: I,
 ) -> String {
-    let mut string = String::new();
+    return events_to_markdown_with_options(
+        events,
+        &MarkdownOptions::default(),
+    );
+}
 
-    let options = default_to_markdown_options();
+fn events_to_markdown_with_options<'e, I: IntoIterator<Item = Event<'e>>>(
+    events: I,
+    options: &MarkdownOptions,
+) -> String {
+    let cmark_options = options.to_pulldown_cmark_to_cmark();
 
-    let _: pulldown_cmark_to_cmark::State =
-        pulldown_cmark_to_cmark::cmark_with_options(
-            events.into_iter(),
-            &mut string,
-            options,
-        )
-        .expect("error converting Event sequent to Markdown string");
+    let events = crate::to_markdown::prepare_events(
+        events.into_iter(),
+        options,
+        &cmark_options,
+    );
 
-    string
+    crate::to_markdown::write_events(&events, &cmark_options)
 }
 
 /// Convert AST [`Block`]s into an [`Event`] sequence.
@@ -543,29 +1175,179 @@ pub fn ast_to_events(blocks: &[Block]) -> Vec<Event> {
     events
 }
 
+/// Convert AST [`Block`]s into an [`Event`] sequence, lazily.
+///
+/// The events for each top-level [`Block`] are generated only once the
+/// iterator reaches that block, so the complete event sequence for `blocks`
+/// never needs to be held in memory at once. The events produced are the same
+/// as those returned by [`ast_to_events()`].
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{ast_event_iter, Block};
+/// use pulldown_cmark::{Event, Tag, TagEnd};
+///
+/// let ast = vec![Block::plain_text_paragraph("Hello"), Block::Rule];
+///
+/// let mut events = ast_event_iter(&ast);
+///
+/// assert_eq!(events.next(), Some(Event::Start(Tag::Paragraph)));
+/// assert_eq!(events.next(), Some(Event::Text("Hello".into())));
+/// assert_eq!(events.next(), Some(Event::End(TagEnd::Paragraph)));
+/// assert_eq!(events.next(), Some(Event::Rule));
+/// assert_eq!(events.next(), None);
+/// ```
+pub fn ast_event_iter(blocks: &[Block]) -> impl Iterator<Item = Event<'_>> {
+    return blocks.iter().flat_map(|block| {
+        let mut events: Vec<Event> = Vec::new();
+
+        crate::to_events::block_to_events(block, &mut events);
+
+        events
+    });
+}
+
 /// Parse [`Event`]s into AST [`Block`]s.
+///
+/// # Panics
+///
+/// Panics if `events` contain a construct that cannot be represented in the
+/// AST. Use [`try_events_to_ast()`] to handle unsupported input without
+/// panicking.
 pub fn events_to_ast<'i, I: IntoIterator<Item = Event<'i>>>(
     events: I,
 ) -> Vec<Block> {
+    return try_events_to_ast(events)
+        .expect("unsupported construct in Markdown events");
+}
+
+/// Parse [`Event`]s into AST [`Block`]s, returning an error instead of
+/// panicking if `events` contain a construct that cannot be represented in the
+/// AST.
+///
+/// `events` may come from a [`pulldown_cmark::Parser`] configured with any
+/// combination of extensions, not only those enabled by
+/// [`markdown_to_events()`]. `events` must be well nested, i.e. every
+/// [`Event::Start`] must be followed by a matching [`Event::End`].
+///
+/// # Errors
+///
+/// Returns [`MarkdownAstError::Unsupported`] describing the unsupported
+/// construct. Unlike [`try_markdown_to_ast()`], the error has no location,
+/// since the events have no source text.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{try_events_to_ast, Block};
+/// use pulldown_cmark::{Options, Parser, TextMergeStream};
+///
+/// // Parse without any extensions, so `~~` is plain text.
+/// let parser = Parser::new_ext("Some ~~text~~.", Options::empty());
+/// let events = TextMergeStream::new(parser);
+///
+/// assert_eq!(
+///     try_events_to_ast(events),
+///     Ok(vec![Block::plain_text_paragraph("Some ~~text~~.")])
+/// );
+/// ```
+pub fn try_events_to_ast<'i, I: IntoIterator<Item = Event<'i>>>(
+    events: I,
+) -> Result<Vec<Block>, MarkdownAstError> {
     let events =
         unflatten::parse_markdown_to_unflattened_events(events.into_iter());
 
     crate::from_events::ast_events_to_ast(events)
 }
 
-/// Parse Markdown input string into [`Event`]s.
+/// Fill in the location of an [`MarkdownAstError::Unsupported`] error that
+/// occurred while converting `input`, parsed with `extensions`.
 ///
-/// This is a thin wrapper around [`pulldown_cmark::Parser`], provided in this
-/// crate for consistency and ease of use.
-pub fn markdown_to_events<'i>(
-    input: &'i str,
-) -> impl Iterator<Item = Event<'i>> {
+/// The events of each top-level block of `input` are converted separately, and
+/// the location of the first block that fails to convert is used.
+fn locate_unsupported(
+    input: &str,
+    extensions: md::Options,
+    error: MarkdownAstError,
+) -> MarkdownAstError {
+    let MarkdownAstError::Unsupported {
+        construct,
+        location: None,
+    } = error
+    else {
+        return error;
+    };
+
+    let mut parser = md::Parser::new_ext(input, extensions).into_offset_iter();
+
+    let mut location = None;
+
+    while let Some((event, range)) = parser.next() {
+        let mut block_events = vec![event];
+        let mut depth: usize = 0;
+
+        loop {
+            match block_events.last() {
+                Some(Event::Start(_)) => depth += 1,
+                Some(Event::End(_)) => depth -= 1,
+                _ => (),
+            }
+
+            if depth == 0 {
+                break;
+            }
+
+            match parser.next() {
+                Some((event, _)) => block_events.push(event),
+                None => break,
+            }
+        }
+
+        let block_events = md::TextMergeStream::new(block_events.into_iter());
+
+        if try_events_to_ast(block_events).is_err() {
+            location = Some(LineCol::from_offset(input, range.start));
+            break;
+        }
+    }
+
+    return MarkdownAstError::Unsupported {
+        construct,
+        location,
+    };
+}
+
+/// Parse Markdown input string into [`Event`]s.
+///
+/// This is a thin wrapper around [`pulldown_cmark::Parser`], provided in this
+/// crate for consistency and ease of use.
+///
+/// Consecutive [`Event::Text`]s, e.g. those separated by a backslash escape,
+/// are merged into a single [`Event::Text`].
+pub fn markdown_to_events<'i>(
+    input: &'i str,
+) -> impl Iterator<Item = Event<'i>> {
+    md::TextMergeStream::new(md::Parser::new_ext(input, parser_options()))
+}
+
+/// The [`pulldown_cmark::Options`] used to parse Markdown input.
+fn parser_options() -> md::Options {
     // Set up options and parser. Strikethroughs are not part of the CommonMark standard
     // and we therefore must enable it explicitly.
     let mut options = md::Options::empty();
     options.insert(md::Options::ENABLE_STRIKETHROUGH);
     options.insert(md::Options::ENABLE_TABLES);
-    md::Parser::new_ext(input, options)
+    // Parse GitHub-style alert block quotes, e.g. `> [!NOTE]`.
+    options.insert(md::Options::ENABLE_GFM);
+    options.insert(md::Options::ENABLE_FOOTNOTES);
+    options.insert(md::Options::ENABLE_MATH);
+    options.insert(md::Options::ENABLE_TASKLISTS);
+    options.insert(md::Options::ENABLE_HEADING_ATTRIBUTES);
+    // Parse YAML (`---`) and TOML (`+++`) frontmatter.
+    options.insert(md::Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+    options.insert(md::Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS);
+    options
 }
 
 /// Canonicalize (or format) a Markdown input by parsing and then converting
@@ -573,9 +1355,8 @@ pub fn markdown_to_events<'i>(
 ///
 /// **⚠️ Warning ⚠️:** This function is **semver exempt**. The precise
 /// canonicalization behavior may change in MINOR or PATCH versions of
-/// markdown-ast. (Stabilizing the behavior of this function will require
-/// additional options to configure the behavior of
-/// [pulldown-cmark-to-cmark](https://crates.io/crates/pulldown-cmark-to-cmark).)
+/// markdown-ast. Use [`canonicalize_with_options()`] to pin the formatting
+/// choices that can be configured.
 ///
 /// # Examples
 ///
@@ -591,7 +1372,6 @@ pub fn markdown_to_events<'i>(
 /// "),
 /// "\
 /// * Foo
-///
 /// * Bar"
 /// )
 /// ```
@@ -618,22 +1398,561 @@ pub fn canonicalize(input: &str) -> String {
     return ast_to_markdown(&ast);
 }
 
-fn default_to_markdown_options() -> pulldown_cmark_to_cmark::Options<'static> {
-    pulldown_cmark_to_cmark::Options {
-        // newlines_after_paragraph: 2,
-        // newlines_after_headline: 0,
-        // newlines_after_codeblock: 0,
-        // newlines_after_list: 1,
-        // newlines_after_rest: 0,
-        code_block_token_count: 3,
-        ..pulldown_cmark_to_cmark::Options::default()
+/// Canonicalize (or format) a Markdown input by parsing and then converting
+/// back to a string, formatted according to `options`.
+///
+/// Unlike [`canonicalize()`], the formatting choices controlled by
+/// [`MarkdownOptions`], e.g. the bullet list marker, code fence length, and
+/// number of blank lines between blocks, are pinned by the caller, so this can
+/// be used as a stable formatter.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{canonicalize_with_options, ListToken, MarkdownOptions};
+///
+/// let options = MarkdownOptions {
+///     list_token: ListToken::Dash,
+///     ..MarkdownOptions::default()
+/// };
+///
+/// assert_eq!(
+///     canonicalize_with_options("+ Foo\n+ Bar\n", &options),
+///     "- Foo\n- Bar"
+/// );
+/// ```
+pub fn canonicalize_with_options(
+    input: &str,
+    options: &MarkdownOptions,
+) -> String {
+    let ast = markdown_to_ast(input);
+
+    return ast_to_markdown_with_options(&ast, options);
+}
+
+/// Convert every inline link in `blocks` into a reference link.
+///
+/// Links with the same destination and title share a single label. Links that
+/// are already reference links keep their label, and new labels are numbered
+/// so as not to conflict with them.
+///
+/// When written out by [`ast_to_markdown()`], the link reference definitions
+/// of all reference links are collected into a single section at the end of
+/// the document.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{ast_to_markdown, markdown_to_ast, to_reference_links};
+///
+/// let mut ast = markdown_to_ast(
+///     "See [one](a.org), [two](b.org \"B\"), and [one again](a.org).",
+/// );
+///
+/// to_reference_links(&mut ast);
+///
+/// assert_eq!(ast_to_markdown(&ast), "\
+/// See [one][1], [two][2], and [one again][1].
+///
+/// [1]: a.org
+/// [2]: b.org \"B\"");
+/// ```
+pub fn to_reference_links(blocks: &mut [Block]) {
+    // The label used for each (destination, title) pair.
+    let mut labels: HashMap<(String, String), String> = HashMap::new();
+
+    // Link labels are matched case-insensitively.
+    let mut used_labels: Vec<String> = Vec::new();
+
+    visit_inlines_mut(blocks, &mut |inline| {
+        if let Inline::Link {
+            link_type:
+                md::LinkType::Reference
+                | md::LinkType::Collapsed
+                | md::LinkType::Shortcut,
+            dest_url,
+            title,
+            id,
+            content_text: _,
+        } = inline
+        {
+            if !id.is_empty() {
+                used_labels.push(id.to_lowercase());
+
+                labels
+                    .entry((dest_url.clone(), title.clone()))
+                    .or_insert_with(|| id.clone());
+            }
+        }
+    });
+
+    let mut next_label: usize = 1;
+
+    visit_inlines_mut(blocks, &mut |inline| {
+        let Inline::Link {
+            link_type: link_type @ md::LinkType::Inline,
+            dest_url,
+            title,
+            id,
+            content_text: _,
+        } = inline
+        else {
+            return;
+        };
+
+        let label = labels
+            .entry((dest_url.clone(), title.clone()))
+            .or_insert_with(|| loop {
+                let label = next_label.to_string();
+                next_label += 1;
+
+                if !used_labels.contains(&label) {
+                    break label;
+                }
+            });
+
+        *link_type = md::LinkType::Reference;
+        *id = label.clone();
+    });
+}
+
+/// Merge each run of adjacent [`Block::List`]s of the same kind in `blocks`
+/// into a single list.
+///
+/// Two lists are of the same kind if both are ordered lists or both are
+/// bullet lists. The items of the merged list are in their original order, and
+/// an ordered list keeps the start number of the first list in the run. The
+/// merged list is tight only if every list in the run is tight.
+///
+/// Only the lists directly in `blocks` are merged, not lists nested inside
+/// list items or block quotes.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{markdown_to_ast, merge_adjacent_lists, Block};
+///
+/// let mut blocks = markdown_to_ast("* One");
+/// blocks.extend(markdown_to_ast("* Two"));
+///
+/// merge_adjacent_lists(&mut blocks);
+///
+/// assert_eq!(blocks, markdown_to_ast("* One\n* Two"));
+/// ```
+pub fn merge_adjacent_lists(blocks: &mut Vec<Block>) {
+    let mut merged: Vec<Block> = Vec::with_capacity(blocks.len());
+
+    for block in blocks.drain(..) {
+        match (merged.last_mut(), block) {
+            (
+                Some(Block::List(items, start, tight)),
+                Block::List(next_items, next_start, next_tight),
+            ) if start.is_some() == next_start.is_some() => {
+                items.extend(next_items);
+                *tight = *tight && next_tight;
+            },
+            (_, block) => merged.push(block),
+        }
+    }
+
+    *blocks = merged;
+}
+
+/// Replace every [`Inline::Link`] in `blocks` with its `content_text`, and
+/// every [`Inline::Image`] with its `image_description`, dropping their
+/// destinations and titles.
+///
+/// Other formatting, e.g. emphasis, is kept, including formatting inside the
+/// text of a link. The text of a link is merged with any adjacent
+/// [`Inline::Text`].
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{ast_to_markdown, markdown_to_ast, strip_links};
+///
+/// let mut ast = markdown_to_ast("See [the *docs*](/docs) and ![a logo](logo.png).");
+///
+/// strip_links(&mut ast);
+///
+/// assert_eq!(ast_to_markdown(&ast), "See the *docs* and a logo.");
+/// ```
+pub fn strip_links(blocks: &mut [Block]) {
+    struct StripLinks;
+
+    impl VisitorMut for StripLinks {
+        fn visit_inlines_mut(&mut self, inlines: &mut Inlines) {
+            visit::walk_inlines_mut(self, inlines);
+
+            let Inlines(inlines) = inlines;
+
+            let mut stripped: Vec<Inline> = Vec::with_capacity(inlines.len());
+
+            for inline in
+                std::mem::take(inlines).into_iter().flat_map(strip_link)
+            {
+                // Merge the text of a link with any adjacent text.
+                match (stripped.last_mut(), inline) {
+                    (Some(Inline::Text(previous)), Inline::Text(text)) => {
+                        previous.push_str(&text)
+                    },
+                    (_, inline) => stripped.push(inline),
+                }
+            }
+
+            *inlines = stripped;
+        }
+    }
+
+    fn strip_link(inline: Inline) -> Vec<Inline> {
+        match inline {
+            Inline::Link {
+                content_text: Inlines(children),
+                ..
+            }
+            | Inline::Image {
+                image_description: Inlines(children),
+                ..
+            } => children,
+            Inline::Positioned(_, inline)
+                if matches!(
+                    *inline,
+                    Inline::Link { .. } | Inline::Image { .. }
+                ) =>
+            {
+                strip_link(*inline)
+            },
+            inline => vec![inline],
+        }
+    }
+
+    visit::walk_blocks_mut(&mut StripLinks, blocks)
+}
+
+/// Remove and return the content of the first block in `blocks`, if it is a
+/// level 1 [`Block::Heading`].
+///
+/// This is useful when the first H1 heading of a document is used as its
+/// title, e.g. by a static site generator, and should not also appear in the
+/// body. Returns `None`, leaving `blocks` unchanged, if `blocks` does not start
+/// with an H1 heading. The [`HeadingAttributes`] of the heading are discarded.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{extract_title, markdown_to_ast, Inlines};
+///
+/// let mut blocks = markdown_to_ast("# My Page\n\nSome text.");
+///
+/// assert_eq!(extract_title(&mut blocks), Some(Inlines::plain_text("My Page")));
+/// assert_eq!(blocks, markdown_to_ast("Some text."));
+/// ```
+pub fn extract_title(blocks: &mut Vec<Block>) -> Option<Inlines> {
+    if !matches!(blocks.first(), Some(Block::Heading(HeadingLevel::H1, ..))) {
+        return None;
+    }
+
+    match blocks.remove(0) {
+        Block::Heading(_, title, _) => Some(title),
+        _ => unreachable!(),
+    }
+}
+
+/// Returns a GitHub-style slug for a heading with the content `heading`, for
+/// use as the id of the heading in HTML output.
+///
+/// The slug is the plain text of the heading, lowercased, with spaces replaced
+/// by `-`, and any characters other than letters, numbers, `-`, and `_`
+/// removed.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{heading_slug, markdown_to_ast, Block};
+///
+/// let ast = markdown_to_ast("## What's *New* in `v2.0`?");
+///
+/// let [Block::Heading(_, heading, _)] = ast.as_slice() else {
+///     panic!()
+/// };
+///
+/// assert_eq!(heading_slug(heading), "whats-new-in-v20");
+/// ```
+pub fn heading_slug(heading: &Inlines) -> String {
+    heading
+        .to_plain_string()
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|char| match char {
+            ' ' => Some('-'),
+            '-' | '_' => Some(char),
+            _ if char.is_alphanumeric() => Some(char),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Insert an HTML anchor, e.g. `<a id="my-heading"></a>`, at the start of the
+/// content of every [`Block::Heading`] in `blocks`, so that each heading can be
+/// linked to from HTML output.
+///
+/// The id of each anchor is the [`heading_slug()`] of the heading. Slugs that
+/// have already been used in the document, including as the explicit id of a
+/// heading, are made unique by appending `-1`, `-2`, etc.
+///
+/// Headings with an explicit id in their [`HeadingAttributes`], e.g.
+/// `# Intro {#intro}`, are left unchanged, as that id is already written out
+/// with the heading.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{ast_to_markdown, inject_heading_anchors, markdown_to_ast};
+///
+/// let mut ast = markdown_to_ast("# Intro\n\n## Intro");
+///
+/// inject_heading_anchors(&mut ast);
+///
+/// assert_eq!(
+///     ast_to_markdown(&ast),
+///     "# <a id=\"intro\"></a>Intro\n\n## <a id=\"intro-1\"></a>Intro"
+/// );
+/// ```
+pub fn inject_heading_anchors(blocks: &mut [Block]) {
+    struct ExplicitIds(HashSet<String>);
+
+    impl VisitorMut for ExplicitIds {
+        fn visit_block_mut(&mut self, block: &mut Block) {
+            visit::walk_block_mut(self, block);
+
+            if let Block::Heading(_, _, attrs) = block {
+                self.0.extend(attrs.id.clone());
+            }
+        }
+    }
+
+    struct HeadingAnchors {
+        /// The ids that have been used, explicitly or as a slug.
+        used_ids: HashSet<String>,
+        /// The number of times each slug has been used.
+        slug_counts: HashMap<String, usize>,
+    }
+
+    impl VisitorMut for HeadingAnchors {
+        fn visit_block_mut(&mut self, block: &mut Block) {
+            visit::walk_block_mut(self, block);
+
+            let Block::Heading(_, heading, attrs) = block else {
+                return;
+            };
+
+            if attrs.id.is_some() {
+                return;
+            }
+
+            let slug = heading_slug(heading);
+
+            let count = self.slug_counts.entry(slug.clone()).or_default();
+
+            let id = loop {
+                let id = match *count {
+                    0 => slug.clone(),
+                    count => format!("{slug}-{count}"),
+                };
+
+                *count += 1;
+
+                if self.used_ids.insert(id.clone()) {
+                    break id;
+                }
+            };
+
+            let id = id.replace('&', "&amp;").replace('"', "&quot;");
+
+            let anchor = Inline::InlineHtml(format!("<a id=\"{id}\"></a>"));
+
+            heading.0.insert(0, anchor);
+        }
+    }
+
+    let mut explicit_ids = ExplicitIds(HashSet::new());
+
+    visit::walk_blocks_mut(&mut explicit_ids, blocks);
+
+    let mut visitor = HeadingAnchors {
+        used_ids: explicit_ids.0,
+        slug_counts: HashMap::new(),
+    };
+
+    visit::walk_blocks_mut(&mut visitor, blocks)
+}
+
+/// Replace each `<br>` tag in the cells of every [`Block::Table`] in `blocks`
+/// with an [`Inline::HardBreak`].
+///
+/// GFM table cells cannot contain literal newlines, so authors use `<br>` to
+/// break a line within a cell. Those tags are parsed as [`Inline::InlineHtml`],
+/// so that the source round-trips unchanged. After normalizing, the common
+/// spellings `<br>`, `<br/>`, and `<br />`, in any case, are all written back
+/// out as `<br>`.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{ast_to_markdown, markdown_to_ast, normalize_table_breaks};
+///
+/// let mut ast = markdown_to_ast("|a|\n|-|\n|x<BR />y|");
+///
+/// normalize_table_breaks(&mut ast);
+///
+/// assert_eq!(ast_to_markdown(&ast), "|a|\n|-|\n|x<br>y|");
+/// ```
+pub fn normalize_table_breaks(blocks: &mut [Block]) {
+    struct TableBreaks;
+
+    impl VisitorMut for TableBreaks {
+        fn visit_block_mut(&mut self, block: &mut Block) {
+            if let Block::Table { .. } = block {
+                visit_inlines_mut(std::slice::from_mut(block), &mut |inline| {
+                    if let Inline::InlineHtml(html) = inline {
+                        if is_line_break_tag(html) {
+                            *inline = Inline::HardBreak;
+                        }
+                    }
+                });
+            } else {
+                visit::walk_block_mut(self, block);
+            }
+        }
+    }
+
+    visit::walk_blocks_mut(&mut TableBreaks, blocks)
+}
+
+/// Returns `true` if `html` is a `<br>`, `<br/>`, or `<br />` tag.
+fn is_line_break_tag(html: &str) -> bool {
+    let Some(tag) = html
+        .strip_prefix('<')
+        .and_then(|html| html.strip_suffix('>'))
+    else {
+        return false;
+    };
+
+    tag.trim_end_matches('/')
+        .trim_end()
+        .eq_ignore_ascii_case("br")
+}
+
+/// Call `f` on every [`Inline`] in `blocks`, including inlines nested inside
+/// other blocks and inlines.
+fn visit_inlines_mut(blocks: &mut [Block], f: &mut dyn FnMut(&mut Inline)) {
+    struct InlineVisitor<'f>(&'f mut dyn FnMut(&mut Inline));
+
+    impl VisitorMut for InlineVisitor<'_> {
+        fn visit_inline_mut(&mut self, inline: &mut Inline) {
+            (self.0)(inline);
+
+            visit::walk_inline_mut(self, inline)
+        }
     }
+
+    visit::walk_blocks_mut(&mut InlineVisitor(f), blocks)
 }
 
 //======================================
 // Impls
 //======================================
 
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        MarkdownOptions {
+            code_block_token_count: 3,
+            code_block_tab_width: None,
+            sort_reference_definitions: false,
+            autolink_bare_urls: false,
+            list_token: ListToken::Star,
+            blank_lines_between_blocks: 1,
+            pad_table_columns: false,
+        }
+    }
+}
+
+impl std::fmt::Display for MarkdownAstError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkdownAstError::EventLimitExceeded { max_events } => write!(
+                f,
+                "Markdown input exceeded the limit of {max_events} parser events"
+            ),
+            MarkdownAstError::Unsupported {
+                construct,
+                location: Some(LineCol { line, column }),
+            } => write!(
+                f,
+                "unsupported Markdown construct at line {line}, column {column}: {construct}"
+            ),
+            MarkdownAstError::Unsupported {
+                construct,
+                location: None,
+            } => write!(f, "unsupported Markdown construct: {construct}"),
+            MarkdownAstError::UnknownFeature { name } => {
+                write!(f, "unknown Markdown parser feature: {name:?}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for MarkdownAstError {}
+
+impl MarkdownOptions {
+    fn to_pulldown_cmark_to_cmark(
+        &self,
+    ) -> pulldown_cmark_to_cmark::Options<'static> {
+        let MarkdownOptions {
+            code_block_token_count,
+            code_block_tab_width: _,
+            sort_reference_definitions: _,
+            autolink_bare_urls: _,
+            list_token,
+            blank_lines_between_blocks,
+            pad_table_columns: _,
+        } = *self;
+
+        // The number of newlines that end a block and its trailing blank
+        // lines. The last line of an HTML block already ends in a newline.
+        let newlines_after_block = blank_lines_between_blocks + 1;
+
+        pulldown_cmark_to_cmark::Options {
+            newlines_after_headline: newlines_after_block,
+            newlines_after_paragraph: newlines_after_block,
+            newlines_after_codeblock: newlines_after_block,
+            newlines_after_htmlblock: blank_lines_between_blocks,
+            newlines_after_table: newlines_after_block,
+            newlines_after_rule: newlines_after_block,
+            newlines_after_list: newlines_after_block,
+            newlines_after_blockquote: newlines_after_block,
+            code_block_token_count,
+            list_token: list_token.as_char(),
+            // Number ordered list items consecutively from the start number
+            // of the list, instead of repeating the start number.
+            increment_ordered_list_bullets: true,
+            ..pulldown_cmark_to_cmark::Options::default()
+        }
+    }
+}
+
+impl ListToken {
+    /// Returns the marker character, e.g. `'*'` for [`ListToken::Star`].
+    pub fn as_char(self) -> char {
+        match self {
+            ListToken::Star => '*',
+            ListToken::Dash => '-',
+            ListToken::Plus => '+',
+        }
+    }
+}
+
 impl Inline {
     /// Parse a piece of simple input into an [`Inline`].
     ///
@@ -685,510 +2004,4807 @@ impl Inline {
         Inline::Text(s.into())
     }
 
-    pub fn emphasis(inline: Inline) -> Self {
-        Inline::Emphasis(Inlines(vec![inline]))
+    /// Construct an [`Inline::Emphasis`] containing `inlines`, which may be a
+    /// single [`Inline`], a `Vec<Inline>`, or an [`Inlines`].
+    pub fn emphasis<I: Into<Inlines>>(inlines: I) -> Self {
+        Inline::Emphasis(inlines.into())
     }
 
-    pub fn strong(inline: Inline) -> Self {
-        Inline::Strong(Inlines(vec![inline]))
+    /// Construct an [`Inline::Strong`] containing `inlines`, which may be a
+    /// single [`Inline`], a `Vec<Inline>`, or an [`Inlines`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{Inline, Inlines};
+    ///
+    /// assert_eq!(
+    ///     Inline::strong(vec![Inline::plain_text("bold "), Inline::code("code")]),
+    ///     Inline::parse("**bold `code`**")
+    /// );
+    ///
+    /// assert_eq!(
+    ///     Inline::strong(Inline::plain_text("bold")),
+    ///     Inline::Strong(Inlines::plain_text("bold"))
+    /// );
+    /// ```
+    pub fn strong<I: Into<Inlines>>(inlines: I) -> Self {
+        Inline::Strong(inlines.into())
     }
 
-    pub fn strikethrough(inline: Inline) -> Self {
-        Inline::Strikethrough(Inlines(vec![inline]))
+    /// Construct an [`Inline::Strikethrough`] containing `inlines`, which may
+    /// be a single [`Inline`], a `Vec<Inline>`, or an [`Inlines`].
+    pub fn strikethrough<I: Into<Inlines>>(inlines: I) -> Self {
+        Inline::Strikethrough(inlines.into())
     }
 
     pub fn code<S: Into<String>>(s: S) -> Self {
         Inline::Code(s.into())
     }
-}
 
-impl Inlines {
-    /// Construct an inlines sequence containing a single inline piece of plain
-    /// text.
-    pub fn plain_text<S: Into<String>>(inline: S) -> Self {
-        return Inlines(vec![Inline::Text(inline.into())]);
+    /// Apply `f` to the child [`Inlines`] of this inline, if it is a container
+    /// inline, and return the result.
+    ///
+    /// The container inlines are [`Inline::Emphasis`], [`Inline::Strong`],
+    /// [`Inline::Strikethrough`], [`Inline::Link`] (its `content_text`), and
+    /// [`Inline::Image`] (its `image_description`). An [`Inline::Positioned`]
+    /// applies `f` to the children of the inline it wraps. Any other inline is
+    /// returned unchanged, without calling `f`.
+    ///
+    /// `f` is applied only to the direct children of this inline; it is up to
+    /// `f` to recurse further if needed.
+    pub fn map_children<F: FnMut(Inlines) -> Inlines>(
+        self,
+        mut f: F,
+    ) -> Inline {
+        match self {
+            Inline::Emphasis(inlines) => Inline::Emphasis(f(inlines)),
+            Inline::Strong(inlines) => Inline::Strong(f(inlines)),
+            Inline::Strikethrough(inlines) => Inline::Strikethrough(f(inlines)),
+            Inline::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+                content_text,
+            } => Inline::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+                content_text: f(content_text),
+            },
+            Inline::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+                image_description,
+            } => Inline::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+                image_description: f(image_description),
+            },
+            Inline::Text(_)
+            | Inline::Code(_)
+            | Inline::SoftBreak
+            | Inline::HardBreak
+            | Inline::FootnoteReference(_)
+            | Inline::InlineMath(_)
+            | Inline::DisplayMath(_)
+            | Inline::InlineHtml(_) => self,
+            Inline::Positioned(position, inline) => {
+                Inline::Positioned(position, Box::new(inline.map_children(f)))
+            },
+        }
     }
-}
 
-impl Block {
-    /// Construct a paragraph block containing a single inline piece of plain
-    /// text.
-    pub fn plain_text_paragraph<S: Into<String>>(inline: S) -> Self {
-        return Block::Paragraph(Inlines(vec![Inline::Text(inline.into())]));
+    /// See [`Inlines::eq_ignoring_link_ids()`].
+    fn eq_ignoring_link_ids(&self, other: &Inline) -> bool {
+        match (self, other) {
+            (Inline::Emphasis(inlines), Inline::Emphasis(others))
+            | (Inline::Strong(inlines), Inline::Strong(others))
+            | (Inline::Strikethrough(inlines), Inline::Strikethrough(others)) => {
+                inlines.eq_ignoring_link_ids(others)
+            },
+            (
+                Inline::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id: _,
+                    content_text,
+                },
+                Inline::Link {
+                    link_type: other_link_type,
+                    dest_url: other_dest_url,
+                    title: other_title,
+                    id: _,
+                    content_text: other_content_text,
+                },
+            ) => {
+                link_type == other_link_type
+                    && dest_url == other_dest_url
+                    && title == other_title
+                    && content_text.eq_ignoring_link_ids(other_content_text)
+            },
+            (
+                Inline::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id: _,
+                    image_description,
+                },
+                Inline::Image {
+                    link_type: other_link_type,
+                    dest_url: other_dest_url,
+                    title: other_title,
+                    id: _,
+                    image_description: other_image_description,
+                },
+            ) => {
+                link_type == other_link_type
+                    && dest_url == other_dest_url
+                    && title == other_title
+                    && image_description
+                        .eq_ignoring_link_ids(other_image_description)
+            },
+            (
+                Inline::Positioned(position, inline),
+                Inline::Positioned(other_position, other),
+            ) => {
+                position == other_position && inline.eq_ignoring_link_ids(other)
+            },
+            (inline, other) => inline == other,
+        }
     }
 
-    pub fn paragraph(text: Vec<Inline>) -> Block {
-        Block::Paragraph(Inlines(text))
+    /// Returns the title of this [`Inline::Link`] or [`Inline::Image`], or
+    /// `None` if the title is empty or this is not a link or image.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::Inline;
+    ///
+    /// let link = Inline::parse("[my website](connorgray.com \"Connor's website\")");
+    /// assert_eq!(link.link_title(), Some("Connor's website"));
+    ///
+    /// let link = Inline::parse("[my website](connorgray.com)");
+    /// assert_eq!(link.link_title(), None);
+    ///
+    /// assert_eq!(Inline::parse("plain text").link_title(), None);
+    /// ```
+    pub fn link_title(&self) -> Option<&str> {
+        let title = match self {
+            Inline::Link { title, .. } | Inline::Image { title, .. } => title,
+            Inline::Positioned(_, inline) => return inline.link_title(),
+            _ => return None,
+        };
+
+        return (!title.is_empty()).then_some(title.as_str());
     }
+
+    /// Returns the link label of this [`Inline::Link`] or [`Inline::Image`],
+    /// or `None` if the label is empty or this is not a link or image.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block, Inline, Inlines};
+    ///
+    /// let ast = markdown_to_ast("[my website][site]\n\n[site]: connorgray.com");
+    ///
+    /// let [Block::Paragraph(Inlines(inlines))] = ast.as_slice() else {
+    ///     panic!()
+    /// };
+    ///
+    /// assert_eq!(inlines[0].link_id(), Some("site"));
+    ///
+    /// let image = Inline::parse("![cat photo](example.org/photo.png)");
+    /// assert_eq!(image.link_id(), None);
+    /// ```
+    pub fn link_id(&self) -> Option<&str> {
+        let id = match self {
+            Inline::Link { id, .. } | Inline::Image { id, .. } => id,
+            Inline::Positioned(_, inline) => return inline.link_id(),
+            _ => return None,
+        };
+
+        return (!id.is_empty()).then_some(id.as_str());
+    }
+}
+
+impl Inlines {
+    /// Construct an empty inlines sequence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{Inline, Inlines};
+    ///
+    /// let mut inlines = Inlines::new();
+    ///
+    /// inlines.push(Inline::plain_text("Some "));
+    /// inlines.push(Inline::strong(Inline::plain_text("bold")));
+    /// inlines.extend([Inline::SoftBreak, Inline::plain_text("text")]);
+    ///
+    /// assert_eq!(
+    ///     inlines,
+    ///     Inlines(vec![
+    ///         Inline::plain_text("Some "),
+    ///         Inline::strong(Inline::plain_text("bold")),
+    ///         Inline::SoftBreak,
+    ///         Inline::plain_text("text"),
+    ///     ])
+    /// );
+    /// ```
+    pub fn new() -> Self {
+        return Inlines(Vec::new());
+    }
+
+    /// Append `inline` to the end of this sequence.
+    pub fn push(&mut self, inline: Inline) {
+        let Inlines(inlines) = self;
+
+        inlines.push(inline);
+    }
+
+    /// Construct an inlines sequence containing a single inline piece of plain
+    /// text.
+    pub fn plain_text<S: Into<String>>(inline: S) -> Self {
+        return Inlines(vec![Inline::Text(inline.into())]);
+    }
+
+    /// Returns `true` if any of these inlines is something other than plain
+    /// text or a line break.
+    ///
+    /// Renderers can use this to take a fast path for content that is
+    /// entirely plain text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{Inline, Inlines};
+    ///
+    /// assert!(!Inlines::plain_text("plain").contains_formatting());
+    ///
+    /// assert!(Inlines(vec![
+    ///     Inline::plain_text("some "),
+    ///     Inline::strong(Inline::plain_text("bold")),
+    /// ])
+    /// .contains_formatting());
+    /// ```
+    pub fn contains_formatting(&self) -> bool {
+        fn is_formatting(inline: &Inline) -> bool {
+            match inline {
+                Inline::Text(_) | Inline::SoftBreak | Inline::HardBreak => {
+                    false
+                },
+                Inline::Emphasis(_)
+                | Inline::Strong(_)
+                | Inline::Strikethrough(_)
+                | Inline::Code(_)
+                | Inline::Link { .. }
+                | Inline::Image { .. }
+                | Inline::FootnoteReference(_)
+                | Inline::InlineMath(_)
+                | Inline::DisplayMath(_)
+                | Inline::InlineHtml(_) => true,
+                Inline::Positioned(_, inline) => is_formatting(inline),
+            }
+        }
+
+        let Inlines(inlines) = self;
+
+        return inlines.iter().any(is_formatting);
+    }
+
+    /// Apply `f` to each top-level [`Inline`] in this sequence.
+    ///
+    /// Combine with [`Inline::map_children()`] to rewrite nested inlines.
+    ///
+    /// # Examples
+    ///
+    /// Replace every code span with plain text, at any depth:
+    ///
+    /// ```
+    /// use markdown_ast::{Inline, Inlines};
+    ///
+    /// fn uncode(inline: Inline) -> Inline {
+    ///     match inline {
+    ///         Inline::Code(code) => Inline::Text(code),
+    ///         other => other.map_children(|inlines| inlines.map(uncode)),
+    ///     }
+    /// }
+    ///
+    /// let inlines = Inlines(vec![
+    ///     Inline::code("a"),
+    ///     Inline::strong(vec![Inline::plain_text("b "), Inline::code("c")]),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     inlines.map(uncode),
+    ///     Inlines(vec![
+    ///         Inline::plain_text("a"),
+    ///         Inline::strong(vec![Inline::plain_text("b "), Inline::plain_text("c")]),
+    ///     ])
+    /// );
+    /// ```
+    pub fn map<F: FnMut(Inline) -> Inline>(self, f: F) -> Inlines {
+        let Inlines(inlines) = self;
+
+        return Inlines(inlines.into_iter().map(f).collect());
+    }
+
+    /// Returns the first non-empty piece of text or code in these inlines,
+    /// descending into formatting, links, and images.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{Inline, Inlines};
+    ///
+    /// let inlines = Inlines(vec![
+    ///     Inline::strong(Inline::plain_text("Bold")),
+    ///     Inline::plain_text(" start"),
+    /// ]);
+    ///
+    /// assert_eq!(inlines.first_text(), Some("Bold"));
+    /// assert_eq!(inlines.last_text(), Some(" start"));
+    ///
+    /// assert_eq!(Inlines(vec![Inline::SoftBreak]).first_text(), None);
+    /// ```
+    pub fn first_text(&self) -> Option<&str> {
+        let Inlines(inlines) = self;
+
+        return inlines.iter().find_map(|inline| {
+            Inlines::inline_text(inline, Inlines::first_text)
+        });
+    }
+
+    /// Returns the last non-empty piece of text or code in these inlines,
+    /// descending into formatting, links, and images.
+    ///
+    /// See [`Inlines::first_text()`].
+    pub fn last_text(&self) -> Option<&str> {
+        let Inlines(inlines) = self;
+
+        return inlines.iter().rev().find_map(|inline| {
+            Inlines::inline_text(inline, Inlines::last_text)
+        });
+    }
+
+    /// Returns the visible text of these inlines, without any formatting.
+    ///
+    /// The text of [`Inline::Text`] and [`Inline::Code`] is concatenated,
+    /// descending into formatting, links, and images. A
+    /// [`Inline::SoftBreak`] is written as a space, and an
+    /// [`Inline::HardBreak`] as a newline. HTML, math, and footnote
+    /// references are omitted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let ast = markdown_to_ast("Some **bold** [*linked*](example.org)\ntext");
+    ///
+    /// let [Block::Paragraph(inlines)] = ast.as_slice() else {
+    ///     panic!()
+    /// };
+    ///
+    /// assert_eq!(inlines.to_plain_string(), "Some bold linked text");
+    /// ```
+    pub fn to_plain_string(&self) -> String {
+        fn push_plain_text(string: &mut String, Inlines(inlines): &Inlines) {
+            for inline in inlines {
+                push_inline_plain_text(string, inline);
+            }
+        }
+
+        fn push_inline_plain_text(string: &mut String, inline: &Inline) {
+            match inline {
+                Inline::Text(text) | Inline::Code(text) => {
+                    string.push_str(text)
+                },
+                Inline::SoftBreak => string.push(' '),
+                Inline::HardBreak => string.push('\n'),
+                Inline::Emphasis(children)
+                | Inline::Strong(children)
+                | Inline::Strikethrough(children)
+                | Inline::Link {
+                    content_text: children,
+                    ..
+                }
+                | Inline::Image {
+                    image_description: children,
+                    ..
+                } => push_plain_text(string, children),
+                Inline::FootnoteReference(_)
+                | Inline::InlineMath(_)
+                | Inline::DisplayMath(_)
+                | Inline::InlineHtml(_) => (),
+                Inline::Positioned(_, inline) => {
+                    push_inline_plain_text(string, inline)
+                },
+            }
+        }
+
+        let mut string = String::new();
+
+        push_plain_text(&mut string, self);
+
+        return string;
+    }
+
+    /// Returns the text of `inline`, if it is a text or code inline, or the
+    /// result of `child_text` on its children, if it contains other inlines.
+    fn inline_text(
+        inline: &Inline,
+        child_text: fn(&Inlines) -> Option<&str>,
+    ) -> Option<&str> {
+        match inline {
+            Inline::Text(text) | Inline::Code(text) => {
+                Some(text.as_str()).filter(|text| !text.is_empty())
+            },
+            Inline::Emphasis(children)
+            | Inline::Strong(children)
+            | Inline::Strikethrough(children)
+            | Inline::Link {
+                content_text: children,
+                ..
+            }
+            | Inline::Image {
+                image_description: children,
+                ..
+            } => child_text(children),
+            Inline::SoftBreak
+            | Inline::HardBreak
+            | Inline::FootnoteReference(_)
+            | Inline::InlineMath(_)
+            | Inline::DisplayMath(_)
+            | Inline::InlineHtml(_) => None,
+            Inline::Positioned(_, inline) => {
+                Inlines::inline_text(inline, child_text)
+            },
+        }
+    }
+
+    /// Returns `true` if `self` and `other` are equal, ignoring any differences
+    /// in the `id` of (possibly nested) links and images.
+    ///
+    /// Reference links whose labels differ only in case, e.g. `[text][Foo]`
+    /// and `[text][foo]`, refer to the same link reference definition and are
+    /// rendered identically, but are parsed with different `id`s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let parse = |markdown| match markdown_to_ast(markdown).as_slice() {
+    ///     [Block::Paragraph(inlines)] => inlines.clone(),
+    ///     _ => panic!(),
+    /// };
+    ///
+    /// let a = parse("[link][Foo]\n\n[foo]: example.org");
+    /// let b = parse("[link][foo]\n\n[foo]: example.org");
+    ///
+    /// assert_ne!(a, b);
+    /// assert!(a.eq_ignoring_link_ids(&b));
+    /// ```
+    pub fn eq_ignoring_link_ids(&self, other: &Inlines) -> bool {
+        let (Inlines(inlines), Inlines(others)) = (self, other);
+
+        return inlines.len() == others.len()
+            && inlines
+                .iter()
+                .zip(others)
+                .all(|(inline, other)| inline.eq_ignoring_link_ids(other));
+    }
+}
+
+impl Block {
+    /// Construct a paragraph block containing a single inline piece of plain
+    /// text.
+    pub fn plain_text_paragraph<S: Into<String>>(inline: S) -> Self {
+        return Block::Paragraph(Inlines(vec![Inline::Text(inline.into())]));
+    }
+
+    pub fn paragraph(text: Vec<Inline>) -> Block {
+        Block::Paragraph(Inlines(text))
+    }
+
+    /// Construct a [`Block::Table`] whose header and body cells each contain a
+    /// single inline piece of plain text.
+    ///
+    /// Every column has no alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{ast_to_markdown, markdown_to_ast, Block};
+    ///
+    /// let table = Block::table_from_rows(
+    ///     vec!["Name", "Website"],
+    ///     vec![vec!["Foo", "foo.org"], vec!["Bar", "bar.org"]],
+    /// );
+    ///
+    /// assert_eq!(
+    ///     ast_to_markdown(&[table.clone()]),
+    ///     "|Name|Website|\n|----|-------|\n|Foo|foo.org|\n|Bar|bar.org|"
+    /// );
+    ///
+    /// assert_eq!(
+    ///     markdown_to_ast("| Name | Website |\n|---|---|\n| Foo | foo.org |\n| Bar | bar.org |"),
+    ///     vec![table]
+    /// );
+    /// ```
+    pub fn table_from_rows(headers: Vec<&str>, rows: Vec<Vec<&str>>) -> Block {
+        let alignments = vec![md::Alignment::None; headers.len()];
+
+        let headers = headers.into_iter().map(Inlines::plain_text).collect();
+
+        let rows = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(Inlines::plain_text).collect())
+            .collect();
+
+        return Block::Table {
+            alignments,
+            headers,
+            rows,
+        };
+    }
+
+    /// Construct a fenced [`Block::CodeBlock`] with the given language and
+    /// code.
+    ///
+    /// If `lang` is `None`, the code block has an empty info string.
+    ///
+    /// `code` is used as-is, and should usually end in a newline, like the code
+    /// of a parsed code block.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let block = Block::from_code(Some("rust"), "fn main() {}\n");
+    ///
+    /// assert_eq!(markdown_to_ast("```rust\nfn main() {}\n```"), vec![block]);
+    ///
+    /// assert_eq!(
+    ///     markdown_to_ast("```\n$ ls\n```"),
+    ///     vec![Block::from_code(None, "$ ls\n")]
+    /// );
+    /// ```
+    pub fn from_code(lang: Option<&str>, code: &str) -> Block {
+        return Block::CodeBlock {
+            kind: CodeBlockKind::Fenced(lang.unwrap_or_default().to_owned()),
+            code: code.to_owned(),
+        };
+    }
+
+    /// If this is a [`Block::CodeBlock`], returns its language and code.
+    ///
+    /// The language is the first word of the info string of a fenced code
+    /// block, or `None` if the info string is empty or this is an indented code
+    /// block.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let ast = markdown_to_ast("```rust ignore\nfn main() {}\n```\n\n    plain\n");
+    ///
+    /// assert_eq!(ast[0].as_code(), Some((Some("rust"), "fn main() {}\n")));
+    /// assert_eq!(ast[1].as_code(), Some((None, "plain\n")));
+    ///
+    /// assert_eq!(Block::plain_text_paragraph("text").as_code(), None);
+    /// ```
+    pub fn as_code(&self) -> Option<(Option<&str>, &str)> {
+        let Block::CodeBlock { kind, code } = self else {
+            return None;
+        };
+
+        let lang = kind
+            .info_string()
+            .and_then(|info_string| info_string.split_whitespace().next());
+
+        return Some((lang, code.as_str()));
+    }
+
+    /// Convert a [`Block::CodeBlock`] into a fenced code block with the info
+    /// string `info`, e.g. to add a language to an indented code block.
+    ///
+    /// The code is unchanged. If this is already a fenced code block, its info
+    /// string is replaced. Blocks other than code blocks are returned as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let [block] = markdown_to_ast("    fn main() {}\n").try_into().unwrap();
+    ///
+    /// assert_eq!(
+    ///     block.into_fenced("rust".to_owned()),
+    ///     Block::from_code(Some("rust"), "fn main() {}\n")
+    /// );
+    /// ```
+    pub fn into_fenced(self, info: String) -> Block {
+        let Block::CodeBlock { kind: _, code } = self else {
+            return self;
+        };
+
+        return Block::CodeBlock {
+            kind: CodeBlockKind::Fenced(info),
+            code,
+        };
+    }
+
+    /// Returns the plain text of a [`Block::Paragraph`] or [`Block::Heading`],
+    /// without any formatting, or `None` for other blocks.
+    ///
+    /// See [`Inlines::to_plain_string()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let [heading, rule] = markdown_to_ast("# A *Title*\n\n---")
+    ///     .try_into()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(heading.plain_text_contents().as_deref(), Some("A Title"));
+    /// assert_eq!(rule.plain_text_contents(), None);
+    /// ```
+    pub fn plain_text_contents(&self) -> Option<String> {
+        match self {
+            Block::Paragraph(inlines) | Block::Heading(_, inlines, _) => {
+                Some(inlines.to_plain_string())
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the number of words of text in this block, including the text
+    /// of any blocks nested inside it.
+    ///
+    /// Code, URLs, and HTML are not counted. See [`DocumentStats::words`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let [list] = markdown_to_ast("* Run `cargo build`\n* Then *relax*")
+    ///     .try_into()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(list.word_count(), 3);
+    /// ```
+    pub fn word_count(&self) -> usize {
+        document_stats(std::slice::from_ref(self)).words
+    }
+}
+
+impl ListItem {
+    /// Construct a list item containing a single inline piece of plain text.
+    pub fn plain_text<S: Into<String>>(inline: S) -> Self {
+        return ListItem(
+            vec![Block::Paragraph(Inlines(vec![Inline::Text(inline.into())]))],
+            None,
+        );
+    }
+
+    /// Returns the checkbox state of this item, if it is a task list item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{markdown_to_ast, Block};
+    ///
+    /// let ast = markdown_to_ast("* [x] done\n* [ ] todo\n* plain");
+    ///
+    /// let [Block::List(items, _, _)] = &ast[..] else { panic!() };
+    ///
+    /// let checked: Vec<_> = items.iter().map(|item| item.checked()).collect();
+    ///
+    /// assert_eq!(checked, [Some(true), Some(false), None]);
+    /// ```
+    pub fn checked(&self) -> Option<bool> {
+        let ListItem(_, checked) = *self;
+
+        return checked;
+    }
+
+    /// Set the checkbox state of this item.
+    ///
+    /// `None` turns this item into a plain list item, without a checkbox.
+    pub fn set_checked(&mut self, checked: Option<bool>) {
+        self.1 = checked;
+    }
+}
+
+impl CodeBlockKind {
+    pub fn info_string(&self) -> Option<&str> {
+        match self {
+            CodeBlockKind::Fenced(info_string) => Some(info_string.as_str()),
+            CodeBlockKind::Indented => None,
+        }
+    }
+
+    /// Parse the attributes at the end of the info string of a fenced code
+    /// block, e.g. `{.no-eval}` in `` ```python {.no-eval} ``.
+    ///
+    /// Attributes use the same syntax as [`HeadingAttributes`]: `#id` sets the
+    /// id, `.class` adds a class, and `key=val` or `key` adds an attribute.
+    /// Returns `None` if this is an indented code block, or if its info string
+    /// does not end with an attribute block.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::{CodeBlockKind, HeadingAttributes};
+    ///
+    /// let kind = CodeBlockKind::Fenced("python {#setup .no-eval cell=false}".into());
+    ///
+    /// assert_eq!(
+    ///     kind.parse_attrs(),
+    ///     Some(HeadingAttributes {
+    ///         id: Some("setup".into()),
+    ///         classes: vec!["no-eval".into()],
+    ///         attrs: vec![("cell".into(), Some("false".into()))],
+    ///     })
+    /// );
+    ///
+    /// assert_eq!(CodeBlockKind::Fenced("python".into()).parse_attrs(), None);
+    /// ```
+    pub fn parse_attrs(&self) -> Option<HeadingAttributes> {
+        let info_string = self.info_string()?.trim_end();
+
+        let (_, attrs) = info_string.strip_suffix('}')?.rsplit_once('{')?;
+
+        let mut attributes = HeadingAttributes::default();
+
+        for attr in attrs.split_whitespace() {
+            if let Some(id) = attr.strip_prefix('#') {
+                attributes.id = Some(id.to_owned());
+            } else if let Some(class) = attr.strip_prefix('.') {
+                attributes.classes.push(class.to_owned());
+            } else if let Some((key, value)) = attr.split_once('=') {
+                attributes
+                    .attrs
+                    .push((key.to_owned(), Some(value.to_owned())));
+            } else {
+                attributes.attrs.push((attr.to_owned(), None));
+            }
+        }
+
+        return Some(attributes);
+    }
+
+    /// Guess the language of a piece of `code` from its shebang line, if it
+    /// has one.
+    ///
+    /// This is intended as a fallback for code blocks that have no info
+    /// string. The returned name is the name of the interpreter program, e.g.
+    /// `python3` for `#!/usr/bin/env python3`, or `bash` for `#!/bin/bash`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ast::CodeBlockKind;
+    ///
+    /// assert_eq!(
+    ///     CodeBlockKind::sniff_language("#!/usr/bin/env python\nprint(1)\n"),
+    ///     Some("python")
+    /// );
+    ///
+    /// assert_eq!(CodeBlockKind::sniff_language("#!/bin/sh\necho 1\n"), Some("sh"));
+    ///
+    /// assert_eq!(CodeBlockKind::sniff_language("print(1)\n"), None);
+    /// ```
+    pub fn sniff_language(code: &str) -> Option<&str> {
+        let interpreter_line = code.lines().next()?.strip_prefix("#!")?;
+
+        let mut words = interpreter_line.split_whitespace();
+
+        let mut name = words.next()?.rsplit('/').next()?;
+
+        if name == "env" {
+            // Skip any options and environment variables passed to `env`,
+            // e.g. `#!/usr/bin/env -S FOO=1 python`.
+            let program = words
+                .find(|word| !word.starts_with('-') && !word.contains('='))?;
+
+            name = program.rsplit('/').next()?;
+        }
+
+        return (!name.is_empty()).then_some(name);
+    }
+
+    pub(crate) fn from_pulldown_cmark(kind: md::CodeBlockKind) -> Self {
+        match kind {
+            md::CodeBlockKind::Indented => CodeBlockKind::Indented,
+            md::CodeBlockKind::Fenced(info_string) => {
+                CodeBlockKind::Fenced(info_string.to_string())
+            },
+        }
+    }
+
+    pub(crate) fn to_pulldown_cmark<'s>(&'s self) -> md::CodeBlockKind<'s> {
+        match self {
+            CodeBlockKind::Fenced(info) => {
+                md::CodeBlockKind::Fenced(CowStr::from(info.as_str()))
+            },
+            CodeBlockKind::Indented => md::CodeBlockKind::Indented,
+        }
+    }
+}
+
+impl From<Inline> for Inlines {
+    fn from(inline: Inline) -> Self {
+        Inlines(vec![inline])
+    }
+}
+
+impl From<Vec<Inline>> for Inlines {
+    fn from(inlines: Vec<Inline>) -> Self {
+        Inlines(inlines)
+    }
+}
+
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{Inline, Inlines};
+///
+/// let words = ["one", "two", "three"];
+///
+/// let inlines: Inlines = words
+///     .iter()
+///     .enumerate()
+///     .flat_map(|(index, word)| {
+///         let separator = (index > 0).then_some(Inline::SoftBreak);
+///
+///         separator.into_iter().chain([Inline::plain_text(*word)])
+///     })
+///     .collect();
+///
+/// assert_eq!(
+///     inlines,
+///     Inlines(vec![
+///         Inline::plain_text("one"),
+///         Inline::SoftBreak,
+///         Inline::plain_text("two"),
+///         Inline::SoftBreak,
+///         Inline::plain_text("three"),
+///     ])
+/// );
+/// ```
+impl FromIterator<Inline> for Inlines {
+    fn from_iter<I: IntoIterator<Item = Inline>>(iter: I) -> Self {
+        Inlines(Vec::from_iter(iter))
+    }
+}
+
+impl Extend<Inline> for Inlines {
+    fn extend<I: IntoIterator<Item = Inline>>(&mut self, iter: I) {
+        let Inlines(inlines) = self;
+
+        inlines.extend(iter)
+    }
+}
+
+impl IntoIterator for Inlines {
+    type Item = Inline;
+    type IntoIter = std::vec::IntoIter<Inline>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let Inlines(vec) = self;
+        vec.into_iter()
+    }
+}
+
+//======================================
+// Tests: Markdown to AST parsing
+//======================================
+
+#[test]
+fn test_markdown_to_ast() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        markdown_to_ast("hello"),
+        vec![Block::paragraph(vec![Inline::Text("hello".into())])]
+    );
+
+    //--------------
+    // Styled text
+    //--------------
+
+    assert_eq!(
+        markdown_to_ast("*hello*"),
+        vec![Block::paragraph(vec![Inline::emphasis(Inline::Text(
+            "hello".into()
+        ))])]
+    );
+
+    assert_eq!(
+        markdown_to_ast("**hello**"),
+        vec![Block::paragraph(vec![Inline::strong(Inline::Text(
+            "hello".into()
+        ))])]
+    );
+
+    assert_eq!(
+        markdown_to_ast("~~hello~~"),
+        vec![Block::paragraph(vec![Inline::strikethrough(Inline::Text(
+            "hello".into()
+        ))])]
+    );
+
+    assert_eq!(
+        markdown_to_ast("**`strong code`**"),
+        vec![Block::paragraph(vec![Inline::strong(Inline::Code(
+            "strong code".into()
+        ))])]
+    );
+
+    assert_eq!(
+        markdown_to_ast("~~`foo`~~"),
+        vec![Block::paragraph(vec![Inline::strikethrough(Inline::Code(
+            "foo".into()
+        ))])]
+    );
+
+    assert_eq!(
+        markdown_to_ast("**[example](example.com)**"),
+        vec![Block::paragraph(vec![Inline::strong(Inline::Link {
+            link_type: md::LinkType::Inline,
+            dest_url: "example.com".into(),
+            title: String::new(),
+            id: String::new(),
+            content_text: Inlines(vec![Inline::Text("example".into())]),
+        })])]
+    );
+
+    // Test composition of emphasis, strong, strikethrough and code
+    assert_eq!(
+        markdown_to_ast("_~~**`foo`**~~_"),
+        vec![Block::paragraph(vec![Inline::emphasis(
+            Inline::strikethrough(Inline::strong(Inline::Code("foo".into())))
+        )])]
+    );
+
+    //--------------
+    // Lists
+    //--------------
+
+    assert_eq!(
+        markdown_to_ast("* hello"),
+        vec![Block::List(
+            vec![ListItem(
+                vec![Block::paragraph(vec![Inline::Text("hello".into())])],
+                None,
+            )],
+            None,
+            true
+        )]
+    );
+
+    // List items with styled text
+
+    assert_eq!(
+        markdown_to_ast("* *hello*"),
+        vec![Block::List(
+            vec![ListItem(
+                vec![Block::paragraph(vec![Inline::emphasis(Inline::Text(
+                    "hello".into()
+                ))])],
+                None,
+            )],
+            None,
+            true
+        )]
+    );
+
+    assert_eq!(
+        markdown_to_ast("* **hello**"),
+        vec![Block::List(
+            vec![ListItem(
+                vec![Block::paragraph(vec![Inline::strong(Inline::Text(
+                    "hello".into()
+                ))])],
+                None,
+            )],
+            None,
+            true
+        )]
+    );
+
+    assert_eq!(
+        markdown_to_ast("* ~~hello~~"),
+        vec![Block::List(
+            vec![ListItem(
+                vec![Block::paragraph(vec![Inline::strikethrough(
+                    Inline::Text("hello".into()),
+                )])],
+                None,
+            )],
+            None,
+            true
+        )]
+    );
+
+    //----------------------------------
+
+    let input = "\
+* And **bold** text.
+  
+  * With nested list items.
+    
+    * `md2nb` supports nested lists up to three levels deep.
+";
+
+    let ast = vec![Block::List(
+        vec![ListItem(
+            vec![
+                Block::paragraph(vec![
+                    Inline::plain_text("And "),
+                    Inline::strong(Inline::plain_text("bold")),
+                    Inline::plain_text(" text."),
+                ]),
+                Block::List(
+                    vec![ListItem(
+                        vec![
+                            Block::paragraph(vec![Inline::plain_text(
+                                "With nested list items.",
+                            )]),
+                            Block::List(
+                                vec![ListItem(
+                                    vec![Block::paragraph(vec![
+                    Inline::code("md2nb"),
+                    Inline::plain_text(
+                        " supports nested lists up to three levels deep.",
+                    ),
+                ])],
+                                    None,
+                                )],
+                                None,
+                                true,
+                            ),
+                        ],
+                        None,
+                    )],
+                    None,
+                    false,
+                ),
+            ],
+            None,
+        )],
+        None,
+        false,
+    )];
+
+    assert_eq!(markdown_to_ast(input), ast);
+
+    // Sanity check conversion to event stream.
+    assert_eq!(
+        markdown_to_events(input).collect::<Vec<_>>(),
+        ast_to_events(&ast)
+    );
+
+    //----------------------------------
+    // Test structures
+    //----------------------------------
+
+    assert_eq!(
+        markdown_to_ast(indoc!(
+            "
+            * hello
+
+              world
+            "
+        )),
+        vec![Block::List(
+            vec![ListItem(
+                vec![
+                    Block::paragraph(vec![Inline::Text("hello".into())]),
+                    Block::paragraph(vec![Inline::Text("world".into())])
+                ],
+                None,
+            )],
+            None,
+            false
+        )]
+    );
+
+    #[rustfmt::skip]
+    assert_eq!(
+        markdown_to_ast(indoc!(
+            "
+            # Example
+
+            * A
+              - A.A
+
+                hello world
+
+                * *A.A.A*
+            "
+        )),
+        vec![
+            Block::Heading(
+                HeadingLevel::H1,
+                Inlines(vec![Inline::Text("Example".into())]),
+                HeadingAttributes::default(),
+            ),
+            Block::List(vec![
+                ListItem(vec![
+                    Block::paragraph(vec![Inline::Text("A".into())]),
+                    Block::List(vec![
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.A".into())]),
+                            Block::paragraph(vec![Inline::Text("hello world".into())]),
+                            Block::List(vec![
+                                ListItem(vec![
+                                    Block::paragraph(vec![
+                                        Inline::emphasis(
+                                            Inline::Text(
+                                            "A.A.A".into()),
+                                        )
+                                    ])
+                                ], None)
+                            ], None, true)
+                        ], None)
+                    ], None, false)
+                ], None)
+            ], None, true)
+        ]
+    );
+
+    #[rustfmt::skip]
+    assert_eq!(
+        markdown_to_ast(indoc!(
+            "
+            * A
+              - A.A
+                * A.A.A
+              - A.B
+              - A.C
+            "
+        )),
+        vec![
+            Block::List(vec![
+                ListItem(vec![
+                    Block::paragraph(vec![Inline::Text("A".into())]),
+                    Block::List(vec![
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.A".into())]),
+                            Block::List(vec![ListItem(vec![
+                                Block::paragraph(vec![Inline::Text("A.A.A".into())]),
+                            ], None)], None, true)
+                        ], None),
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.B".into())]),
+                        ], None),
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.C".into())]),
+                        ], None)
+                    ], None, true)
+                ], None)
+            ], None, true)
+        ]
+    );
+
+    #[rustfmt::skip]
+    assert_eq!(
+        markdown_to_ast(indoc!(
+            "
+            # Example
+
+            * A
+              - A.A
+              - A.B
+              * A.C
+            "
+        )),
+        vec![
+            Block::Heading(
+                HeadingLevel::H1,
+                Inlines(vec![Inline::Text("Example".into())]),
+                HeadingAttributes::default(),
+            ),
+            Block::List(vec![
+                ListItem(vec![
+                    Block::paragraph(vec![Inline::Text("A".into())]),
+                    Block::List(vec![
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.A".into())]),
+                        ], None),
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.B".into())]),
+                        ], None),
+                    ], None, true),
+                    Block::List(vec![
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.C".into())])
+                        ], None)
+                    ], None, true),
+                ], None),
+            ], None, true)
+        ]
+    );
+
+    #[rustfmt::skip]
+    assert_eq!(
+        markdown_to_ast(indoc!(
+            "
+            * A
+              - A.A
+              - A.B
+
+                separate paragraph
+
+              - A.C
+            "
+        )),
+        vec![
+            Block::List(vec![
+                ListItem(vec![
+                    Block::paragraph(vec![Inline::Text("A".into())]),
+                    Block::List(vec![
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.A".into())]),
+                        ], None),
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.B".into())]),
+                            Block::paragraph(vec![Inline::Text("separate paragraph".into())]),
+                        ], None),
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.C".into())]),
+                        ], None)
+                    ], None, false)
+                ], None)
+            ], None, true)
+        ]
+    );
+
+    #[rustfmt::skip]
+    assert_eq!(
+        markdown_to_ast(indoc!(
+            "
+            # Example
+
+            * A
+              - A.A
+                * A.A.A
+                  **soft break**
+
+              - A.B
+
+                separate paragraph
+
+              - A.C
+            "
+        )),
+        vec![
+            Block::Heading(
+                HeadingLevel::H1,
+                Inlines(vec![Inline::Text("Example".into())]),
+                HeadingAttributes::default(),
+            ),
+            Block::List(vec![
+                ListItem(vec![
+                    Block::paragraph(vec![Inline::Text("A".into())]),
+                    Block::List(vec![
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.A".into())]),
+                            Block::List(vec![
+                                ListItem(vec![
+                                    Block::paragraph(vec![
+                                        Inline::Text("A.A.A".into()),
+                                        Inline::SoftBreak,
+                                        Inline::strong(
+                                            Inline::Text("soft break".into()),
+                                        )
+                                    ]),
+                                ], None)
+                            ], None, true),
+                        ], None),
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.B".into())]),
+                            Block::paragraph(vec![Inline::Text("separate paragraph".into())]),
+                        ], None),
+                        ListItem(vec![
+                            Block::paragraph(vec![Inline::Text("A.C".into())]),
+                        ], None),
+                    ], None, false)
+                ], None)
+            ], None, true)
+        ]
+    );
+}
+
+/// Tests that break events between blocks, outside of any inline content, do
+/// not produce spurious paragraphs.
+#[test]
+fn test_events_to_ast_stray_breaks() {
+    use md::{HeadingLevel, Tag, TagEnd};
+    use pretty_assertions::assert_eq;
+
+    let heading = |level| Tag::Heading {
+        level,
+        id: None,
+        classes: vec![],
+        attrs: vec![],
+    };
+
+    let events = vec![
+        Event::SoftBreak,
+        Event::Start(heading(HeadingLevel::H1)),
+        Event::Text(CowStr::from("One")),
+        Event::End(TagEnd::Heading(HeadingLevel::H1)),
+        Event::SoftBreak,
+        Event::HardBreak,
+        Event::Start(heading(HeadingLevel::H2)),
+        Event::Text(CowStr::from("Two")),
+        Event::End(TagEnd::Heading(HeadingLevel::H2)),
+        Event::HardBreak,
+    ];
+
+    assert_eq!(
+        events_to_ast(events),
+        vec![
+            Block::Heading(
+                HeadingLevel::H1,
+                Inlines::plain_text("One"),
+                HeadingAttributes::default(),
+            ),
+            Block::Heading(
+                HeadingLevel::H2,
+                Inlines::plain_text("Two"),
+                HeadingAttributes::default(),
+            ),
+        ]
+    );
+}
+
+/// Tests that a thematic break between two lists ends the first list, and is
+/// not swallowed into either of the lists.
+#[test]
+fn test_events_to_ast_rule_between_lists() {
+    use pretty_assertions::assert_eq;
+
+    let list = |text: &str| {
+        Block::List(
+            vec![ListItem(vec![Block::plain_text_paragraph(text)], None)],
+            None,
+            true,
+        )
+    };
+
+    let markdown = "* a\n\n---\n\n* b";
+
+    assert_eq!(
+        events_to_ast(markdown_to_events(markdown)),
+        vec![list("a"), Block::Rule, list("b")]
+    );
+
+    assert_roundtrip(markdown);
+}
+
+/// Tests that parsing stops with an error once the `max_events` limit is
+/// exceeded.
+#[test]
+fn test_markdown_to_ast_max_events() {
+    use pretty_assertions::assert_eq;
+
+    // Each emphasized word produces several events.
+    let markdown = "*word* ".repeat(1000);
+
+    let options = ParseOptions {
+        max_events: Some(100),
+        ..ParseOptions::default()
+    };
+
+    assert_eq!(
+        markdown_to_ast_with_options(&markdown, &options),
+        Err(MarkdownAstError::EventLimitExceeded { max_events: 100 })
+    );
+
+    assert_eq!(
+        markdown_to_ast_with_options(&markdown, &ParseOptions::default()),
+        Ok(markdown_to_ast(&markdown))
+    );
+
+    // An input that produces exactly `max_events` events is parsed normally.
+    let options = ParseOptions {
+        max_events: Some(markdown_to_events(&markdown).count()),
+        ..ParseOptions::default()
+    };
+
+    assert_eq!(
+        markdown_to_ast_with_options(&markdown, &options),
+        Ok(markdown_to_ast(&markdown))
+    );
+}
+
+#[test]
+fn test_parse_options_from_str() {
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        parse_options_from_str("tables,footnotes,math"),
+        Ok(md::Options::ENABLE_TABLES
+            | md::Options::ENABLE_FOOTNOTES
+            | md::Options::ENABLE_MATH)
+    );
+    assert_eq!(
+        parse_options_from_str(" Strikethrough , tasklists, "),
+        Ok(md::Options::ENABLE_STRIKETHROUGH | md::Options::ENABLE_TASKLISTS)
+    );
+    assert_eq!(parse_options_from_str(""), Ok(md::Options::empty()));
+    assert_eq!(
+        parse_options_from_str("tables,emoji"),
+        Err(MarkdownAstError::UnknownFeature {
+            name: "emoji".to_owned()
+        })
+    );
+
+    // Only the requested extensions are enabled.
+    let options = ParseOptions {
+        extensions: Some(parse_options_from_str("math").unwrap()),
+        ..ParseOptions::default()
+    };
+
+    assert_eq!(
+        markdown_to_ast_with_options("$x$ and ~~y~~", &options),
+        Ok(vec![Block::paragraph(vec![
+            Inline::InlineMath("x".to_owned()),
+            Inline::plain_text(" and ~~y~~"),
+        ])])
+    );
+
+    // Without the metadata block extensions, frontmatter is not recognized.
+    let options = ParseOptions {
+        extensions: Some(md::Options::empty()),
+        ..ParseOptions::default()
+    };
+
+    assert_eq!(
+        markdown_to_ast_with_options("---\ntitle: x\n---\n\nText.", &options),
+        Ok(vec![
+            Block::Rule,
+            Block::Heading(
+                HeadingLevel::H2,
+                Inlines::plain_text("title: x"),
+                HeadingAttributes::default(),
+            ),
+            Block::plain_text_paragraph("Text."),
+        ])
+    );
+}
+
+/// Tests that unsupported input is reported as an error, instead of panicking.
+#[test]
+fn test_try_markdown_to_ast_unsupported() {
+    use pretty_assertions::assert_eq;
+
+    // Events that cannot be represented in the AST are reported without a
+    // location, since they have no source text.
+    let result = try_events_to_ast(vec![
+        Event::Start(md::Tag::TableCell),
+        Event::End(md::TagEnd::TableCell),
+    ]);
+
+    let Err(error) = result else {
+        panic!("expected an error, got: {result:?}");
+    };
+
+    assert!(
+        matches!(error, MarkdownAstError::Unsupported { location: None, .. }),
+        "{error:?}"
+    );
+    assert!(
+        error
+            .to_string()
+            .starts_with("unsupported Markdown construct: "),
+        "{error}"
+    );
+
+    let error = MarkdownAstError::Unsupported {
+        construct: "TableCell in block content".to_owned(),
+        location: Some(LineCol { line: 5, column: 1 }),
+    };
+
+    assert_eq!(
+        error.to_string(),
+        "unsupported Markdown construct at line 5, column 1: \
+         TableCell in block content"
+    );
+
+    // Supported input is parsed the same way as by markdown_to_ast().
+    assert_eq!(
+        try_markdown_to_ast("Some *text*."),
+        Ok(markdown_to_ast("Some *text*."))
+    );
+}
+
+/// Tests that events from a parser configured with any combination of
+/// extensions are parsed without panicking.
+#[test]
+fn test_try_events_to_ast_extensions() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let markdown = indoc!(
+        "
+        Text with a footnote[^note] and ~~struck~~ text.
+
+        [^note]: The *footnote*.
+
+        | a | b |
+        |---|---|
+        | c | d |
+        "
+    );
+
+    let parse = |options: md::Options| {
+        let events = md::Parser::new_ext(markdown, options);
+        try_events_to_ast(md::TextMergeStream::new(events))
+    };
+
+    assert_eq!(
+        parse(md::Options::ENABLE_FOOTNOTES),
+        Ok(vec![
+            Block::paragraph(vec![
+                Inline::plain_text("Text with a footnote"),
+                Inline::FootnoteReference("note".to_owned()),
+                Inline::plain_text(" and ~~struck~~ text."),
+            ]),
+            Block::FootnoteDefinition {
+                label: "note".to_owned(),
+                blocks: vec![Block::paragraph(vec![
+                    Inline::plain_text("The "),
+                    Inline::emphasis(Inline::plain_text("footnote")),
+                    Inline::plain_text("."),
+                ])],
+            },
+            Block::paragraph(vec![
+                Inline::plain_text("| a | b |"),
+                Inline::SoftBreak,
+                Inline::plain_text("|---|---|"),
+                Inline::SoftBreak,
+                Inline::plain_text("| c | d |"),
+            ]),
+        ])
+    );
+
+    let all = md::Options::all();
+    let flags: Vec<md::Options> = all.iter().collect();
+
+    for mask in 0..(1u32 << flags.len()) {
+        let options = flags
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| mask & (1 << index) != 0)
+            .fold(md::Options::empty(), |options, (_, flag)| options | *flag);
+
+        assert!(parse(options).is_ok(), "{options:?}");
+    }
+
+    // A stray table row is reported as unsupported.
+    let result = try_events_to_ast(vec![
+        Event::Start(md::Tag::TableRow),
+        Event::End(md::TagEnd::TableRow),
+    ]);
+
+    assert!(
+        matches!(
+            result,
+            Err(MarkdownAstError::Unsupported { location: None, .. })
+        ),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn test_sniff_language() {
+    use pretty_assertions::assert_eq;
+
+    let sniff = CodeBlockKind::sniff_language;
+
+    assert_eq!(sniff("#!/usr/bin/env python3\nprint(1)"), Some("python3"));
+    assert_eq!(sniff("#!/usr/bin/env node"), Some("node"));
+    assert_eq!(sniff("#! /bin/bash -e\necho 1"), Some("bash"));
+    assert_eq!(
+        sniff("#!/usr/bin/env -S FOO=1 julia --quiet"),
+        Some("julia")
+    );
+    assert_eq!(sniff("#!/usr/bin/env /usr/local/bin/R"), Some("R"));
+
+    assert_eq!(sniff(""), None);
+    assert_eq!(sniff("#!"), None);
+    assert_eq!(sniff("#!/usr/bin/env"), None);
+    assert_eq!(sniff("#!/bin/"), None);
+    assert_eq!(sniff("print(1)\n#!/usr/bin/env python"), None);
+    assert_eq!(sniff("# A comment"), None);
+}
+
+#[test]
+fn test_code_block_parse_attrs() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        ```python {.no-eval cell=false}
+        print(1)
+        ```
+
+        ```{#example}
+        code
+        ```
+
+            indented
+        "
+    ));
+
+    let attrs: Vec<Option<HeadingAttributes>> = ast
+        .iter()
+        .map(|block| match block {
+            Block::CodeBlock { kind, .. } => kind.parse_attrs(),
+            _ => panic!("expected a code block: {block:?}"),
+        })
+        .collect();
+
+    assert_eq!(
+        attrs,
+        vec![
+            Some(HeadingAttributes {
+                id: None,
+                classes: vec!["no-eval".to_owned()],
+                attrs: vec![("cell".to_owned(), Some("false".to_owned()))],
+            }),
+            Some(HeadingAttributes {
+                id: Some("example".to_owned()),
+                ..HeadingAttributes::default()
+            }),
+            None,
+        ]
+    );
+
+    // An info string without a closing brace has no attributes.
+    assert_eq!(CodeBlockKind::Fenced("rust {".into()).parse_attrs(), None);
+    assert_eq!(
+        CodeBlockKind::Fenced("rust {}".into()).parse_attrs(),
+        Some(HeadingAttributes::default())
+    );
+}
+
+//======================================
+// Tests: AST to Markdown string
+//======================================
+
+#[test]
+fn test_ast_to_markdown() {
+    use indoc::indoc;
+    // use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        ast_to_markdown(&[Block::paragraph(vec![Inline::Text(
+            "hello".into()
+        )])]),
+        "hello"
+    );
+
+    // NOTE:
+    //  Consecutive paragraphs in an item cannot be written in a tight list, so
+    //  this list is written loose.
+    assert_eq!(
+        ast_to_markdown(&[Block::List(
+            vec![ListItem(
+                vec![
+                    Block::paragraph(vec![Inline::Text("hello".into())]),
+                    Block::paragraph(vec![Inline::Text("world".into())])
+                ],
+                None,
+            )],
+            None,
+            true
+        )]),
+        indoc!(
+            "
+            * hello
+              
+              world"
+        ),
+    )
+}
+
+/// Tests that nested emphasis and strong elements are written with alternating
+/// delimiters, so that they don't merge into a single delimiter run or close
+/// each other when parsed back.
+#[test]
+fn test_nested_emphasis_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::paragraph(vec![Inline::emphasis(Inline::emphasis(
+        Inline::plain_text("x"),
+    ))])];
+
+    assert_eq!(ast_to_markdown(&ast), "*_x_*");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    let ast = vec![Block::paragraph(vec![Inline::strong(Inline::emphasis(
+        Inline::emphasis(Inline::plain_text("x")),
+    ))])];
+
+    assert_eq!(ast_to_markdown(&ast), "**_*x*_**");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    assert_roundtrip("*_x_* and **_y_** and *a **b** c*");
+
+    // Emphasis within a word must use `*`, as `_` cannot open or close there,
+    // so the enclosing emphasis uses `_`.
+    for (inlines, expected) in [
+        (
+            vec![
+                Inline::plain_text("a"),
+                Inline::emphasis(Inline::plain_text("x")),
+            ],
+            "_a*x*_",
+        ),
+        (
+            vec![
+                Inline::emphasis(Inline::plain_text("x")),
+                Inline::plain_text("a"),
+            ],
+            "_*x*a_",
+        ),
+    ] {
+        let ast =
+            vec![Block::paragraph(vec![Inline::Emphasis(Inlines(inlines))])];
+
+        assert_eq!(ast_to_markdown(&ast), expected);
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+    }
+
+    // Emphasis nested anywhere within emphasis alternates delimiters.
+    assert_eq!(canonicalize("*a *b* c*"), "*a _b_ c*");
+
+    assert_roundtrip("x*y*z and **a *b* c** and *a **b**c*");
+
+    // Adjacent emphasis elements alternate delimiters.
+    let ast = vec![Block::paragraph(vec![
+        Inline::emphasis(Inline::plain_text("a")),
+        Inline::emphasis(Inline::plain_text("b")),
+    ])];
+
+    assert_eq!(ast_to_markdown(&ast), "*a*_b_");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    assert_roundtrip("**a**__b__ and x *a* *b* y");
+
+    //==================================
+    // Nested emphasis within a word
+    //==================================
+
+    // Nested elements within a word share a single run of `*`s.
+    for (inline, expected) in [
+        (
+            Inline::emphasis(Inline::strong(Inline::plain_text("b"))),
+            "a***b***c",
+        ),
+        (
+            Inline::strong(Inline::strong(Inline::plain_text("b"))),
+            "a****b****c",
+        ),
+    ] {
+        let ast = vec![Block::paragraph(vec![
+            Inline::plain_text("a"),
+            inline,
+            Inline::plain_text("c"),
+        ])];
+
+        assert_eq!(ast_to_markdown(&ast), expected);
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+    }
+
+    // Emphasis directly within emphasis cannot be written within a word, so
+    // the inner emphasis is dropped.
+    let ast = vec![Block::paragraph(vec![
+        Inline::plain_text("a"),
+        Inline::emphasis(Inline::emphasis(Inline::plain_text("b"))),
+        Inline::plain_text("c"),
+    ])];
+
+    assert_eq!(ast_to_markdown(&ast), "a*b*c");
+    assert_eq!(
+        markdown_to_ast(&ast_to_markdown(&ast)),
+        vec![Block::paragraph(vec![
+            Inline::plain_text("a"),
+            Inline::emphasis(Inline::plain_text("b")),
+            Inline::plain_text("c"),
+        ])]
+    );
+}
+
+/// Tests that trailing whitespace on the lines of a code block is preserved.
+#[test]
+fn test_code_block_trailing_whitespace_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = "Some code:\n\n```\nfoo  \n  bar \n\t\n```";
+
+    assert_eq!(
+        markdown_to_ast(markdown)[1],
+        Block::CodeBlock {
+            kind: CodeBlockKind::Fenced(String::new()),
+            code: "foo  \n  bar \n\t\n".into(),
+        }
+    );
+
+    assert_roundtrip(markdown);
+}
+
+/// Tests that code blocks whose code does or does not end with a newline are
+/// written out identically, and parse back with a trailing newline.
+#[test]
+fn test_code_block_trailing_newline_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    for kind in [
+        CodeBlockKind::Fenced("rust".to_owned()),
+        CodeBlockKind::Indented,
+    ] {
+        let code_block = |code: &str| Block::CodeBlock {
+            kind: kind.clone(),
+            code: code.to_owned(),
+        };
+
+        // At the end of the document, and followed by another block.
+        for after in [vec![], vec![Block::plain_text_paragraph("after")]] {
+            let document = |code: &str| -> Vec<Block> {
+                std::iter::once(code_block(code))
+                    .chain(after.clone())
+                    .collect()
+            };
+
+            for (without, with) in [("code", "code\n"), ("a\nb", "a\nb\n")] {
+                let markdown = ast_to_markdown(&document(with));
+
+                assert_eq!(ast_to_markdown(&document(without)), markdown);
+                assert_eq!(markdown_to_ast(&markdown), document(with));
+
+                assert_roundtrip(&markdown);
+            }
+        }
+    }
+
+    // An indented code block at the end of the input, without a final newline,
+    // is parsed without a trailing newline.
+    assert_eq!(
+        markdown_to_ast("    code"),
+        vec![Block::CodeBlock {
+            kind: CodeBlockKind::Indented,
+            code: "code".to_owned(),
+        }]
+    );
+}
+
+#[test]
+fn test_metadata_block_roundtrip() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let markdown = indoc!(
+        "
+        ---
+        title: X
+        tags: [a, *b*]
+        ---
+
+        # Heading"
+    );
+
+    assert_eq!(
+        markdown_to_ast(markdown),
+        vec![
+            Block::Metadata {
+                kind: MetadataBlockKind::YamlStyle,
+                content: "title: X\ntags: [a, *b*]\n".to_owned(),
+            },
+            Block::Heading(
+                HeadingLevel::H1,
+                Inlines::plain_text("Heading"),
+                HeadingAttributes::default(),
+            ),
+        ]
+    );
+
+    assert_roundtrip(markdown);
+
+    assert_roundtrip("+++\ntitle = \"X\"\n+++\n\nText.");
+
+    // Content without a trailing newline is still followed by the closing
+    // delimiter on its own line.
+    let ast = vec![Block::Metadata {
+        kind: MetadataBlockKind::PlusesStyle,
+        content: "title = \"X\"".to_owned(),
+    }];
+
+    assert_eq!(ast_to_markdown(&ast), "+++\ntitle = \"X\"\n+++\n");
+}
+
+/// Tests that a fenced code block with no content is written out as an empty
+/// code block.
+#[test]
+fn test_empty_code_block_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = "a\n\n```rust\n```\n\nb";
+
+    let ast = vec![
+        Block::plain_text_paragraph("a"),
+        Block::CodeBlock {
+            kind: CodeBlockKind::Fenced("rust".to_owned()),
+            code: String::new(),
+        },
+        Block::plain_text_paragraph("b"),
+    ];
+
+    assert_eq!(markdown_to_ast(markdown), ast);
+    assert_eq!(ast_to_markdown(&ast), markdown);
+
+    assert_roundtrip(markdown);
+}
+
+/// Tests that indented code blocks are written out indented, not fenced.
+#[test]
+fn test_indented_code_block_roundtrip() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let markdown = indoc!(
+        "
+        Some code:
+
+            fn main() {
+
+                println!(\"Hello\");
+            }
+
+        After the code."
+    );
+
+    assert_roundtrip(markdown);
+
+    // Indented code blocks nested inside list items and block quotes are
+    // indented relative to their container.
+    let ast = markdown_to_ast(indoc!(
+        "
+        * In a list item:
+
+              indented code
+
+        > In a block quote:
+        >
+        >     indented code
+        "
+    ));
+
+    let markdown = ast_to_markdown(&ast);
+
+    assert!(!markdown.contains("```"), "{markdown}");
+    assert_eq!(markdown_to_ast(&markdown), ast);
+
+    // An indented code block with no code is written as a fenced code block.
+    let ast = vec![Block::CodeBlock {
+        kind: CodeBlockKind::Indented,
+        code: String::new(),
+    }];
+
+    assert_eq!(
+        markdown_to_ast(&ast_to_markdown(&ast)),
+        vec![Block::CodeBlock {
+            kind: CodeBlockKind::Fenced(String::new()),
+            code: String::new(),
+        }]
+    );
+}
+
+/// Tests that an indented code block converted with [`Block::into_fenced()`]
+/// is written out as a fenced code block with the new info string.
+#[test]
+fn test_code_block_into_fenced() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast: Vec<Block> = markdown_to_ast(indoc!(
+        "
+        Some code:
+
+            fn main() {
+                println!(\"Hello\");
+            }
+        "
+    ))
+    .into_iter()
+    .map(|block| block.into_fenced("rust".to_owned()))
+    .collect();
+
+    let markdown = indoc!(
+        "
+        Some code:
+
+        ```rust
+        fn main() {
+            println!(\"Hello\");
+        }
+        ```"
+    );
+
+    assert_eq!(ast_to_markdown(&ast), markdown);
+    assert_eq!(markdown_to_ast(markdown), ast);
+
+    // Non-code blocks are unchanged.
+    assert_eq!(ast[0], Block::plain_text_paragraph("Some code:"));
+}
+
+/// Tests that link and image titles are preserved exactly, including any
+/// leading, trailing, or repeated internal whitespace.
+#[test]
+fn test_link_title_whitespace_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = r#"[my website](example.org "  Connor  Gray's   website ")"#;
+
+    assert_eq!(
+        markdown_to_ast(markdown),
+        vec![Block::paragraph(vec![Inline::Link {
+            link_type: LinkType::Inline,
+            dest_url: "example.org".into(),
+            title: "  Connor  Gray's   website ".into(),
+            id: String::new(),
+            content_text: Inlines::plain_text("my website"),
+        }])]
+    );
+
+    assert_roundtrip(markdown);
+
+    let markdown = r#"![cat photo](example.org/photo.png "a  cat ")"#;
+
+    assert_eq!(
+        markdown_to_ast(markdown),
+        vec![Block::paragraph(vec![Inline::Image {
+            link_type: LinkType::Inline,
+            dest_url: "example.org/photo.png".into(),
+            title: "a  cat ".into(),
+            id: String::new(),
+            image_description: Inlines::plain_text("cat photo"),
+        }])]
+    );
+
+    assert_roundtrip(markdown);
+}
+
+/// Tests that the titles of links and images of every link type are preserved
+/// when converting to events and back to Markdown.
+#[test]
+fn test_link_title_roundtrip() {
+    use indoc::indoc;
+    use md::Tag;
+    use pretty_assertions::assert_eq;
+
+    fn titles(markdown: &str) -> Vec<(LinkType, String)> {
+        ast_to_events(&markdown_to_ast(markdown))
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Start(
+                    Tag::Link {
+                        link_type, title, ..
+                    }
+                    | Tag::Image {
+                        link_type, title, ..
+                    },
+                ) => Some((link_type, title.to_string())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    let markdown = indoc!(
+        r#"
+        An [inline](https://example.org "My Title") link.
+
+        A [full][ref], [collapsed][], and [shortcut] reference link.
+
+        An image: ![alt](photo.png "Photo Title")
+
+        [ref]: https://example.org/ref "Ref Title"
+        [collapsed]: https://example.org/collapsed "Collapsed Title"
+        [shortcut]: https://example.org/shortcut "Shortcut Title""#
+    );
+
+    assert_eq!(
+        titles(markdown),
+        vec![
+            (LinkType::Inline, "My Title".to_owned()),
+            (LinkType::Reference, "Ref Title".to_owned()),
+            (LinkType::Collapsed, "Collapsed Title".to_owned()),
+            (LinkType::Shortcut, "Shortcut Title".to_owned()),
+            (LinkType::Inline, "Photo Title".to_owned()),
+        ]
+    );
+
+    assert_roundtrip(markdown);
+
+    assert_roundtrip(r#"[text](url "My Title")"#);
+    assert_roundtrip(r#"![alt](photo.png "Photo Title")"#);
+}
+
+/// Tests that character references in link destinations and titles are
+/// decoded consistently, and written out so that they decode the same way.
+#[test]
+fn test_link_character_references_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let link = |dest_url: &str, title: &str| {
+        vec![Block::paragraph(vec![Inline::Link {
+            link_type: LinkType::Inline,
+            dest_url: dest_url.to_owned(),
+            title: title.to_owned(),
+            id: String::new(),
+            content_text: Inlines::plain_text("x"),
+        }])]
+    };
+
+    // An escaped ampersand that does not begin a character reference is
+    // written out unescaped.
+    let ast = markdown_to_ast("[x](https://x.com/?a=1&amp;b=2)");
+    assert_eq!(ast, link("https://x.com/?a=1&b=2", ""));
+    assert_eq!(ast_to_markdown(&ast), "[x](https://x.com/?a=1&b=2)");
+
+    // An escaped ampersand that would otherwise begin a character reference
+    // stays escaped.
+    let markdown = "[x](https://x.com/?q=&amp;copy;&amp;#169; \"&amp;lt;\")";
+    let ast = markdown_to_ast(markdown);
+    assert_eq!(ast, link("https://x.com/?q=&copy;&#169;", "&lt;"));
+    assert_eq!(ast_to_markdown(&ast), markdown);
+    assert_roundtrip(markdown);
+
+    let markdown = "![x](photo.png?a&amp;#x41;)";
+    assert_eq!(ast_to_markdown(&markdown_to_ast(markdown)), markdown);
+    assert_roundtrip(markdown);
+
+    // Character references in autolinks are not decoded.
+    let markdown = "<https://x.com/?a&amp;b>";
+    assert_eq!(ast_to_markdown(&markdown_to_ast(markdown)), markdown);
+    assert_roundtrip(markdown);
+}
+
+/// Tests that a tight list item that starts with an image, e.g. in a list of
+/// badges, is parsed as a paragraph containing the image.
+#[test]
+fn test_list_item_image_roundtrip() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        try_markdown_to_ast("* ![alt](x.png)"),
+        Ok(vec![Block::List(
+            vec![ListItem(
+                vec![Block::paragraph(vec![Inline::Image {
+                    link_type: LinkType::Inline,
+                    dest_url: "x.png".to_owned(),
+                    title: String::new(),
+                    id: String::new(),
+                    image_description: Inlines::plain_text("alt"),
+                }])],
+                None,
+            )],
+            None,
+            true,
+        )])
+    );
+
+    assert_roundtrip("* ![alt](x.png)");
+
+    assert_roundtrip(indoc!(
+        r#"
+        * ![build](build.svg "Build") and more text
+
+        * [![docs](docs.svg)](https://example.org)
+
+        1. ![first](1.png)"#
+    ));
+}
+
+/// Tests that an image nested inside a link, as commonly used for badges, is
+/// preserved.
+#[test]
+fn test_linked_image_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = "[![alt](i.png)](https://x.com)";
+
+    assert_eq!(
+        markdown_to_ast(markdown),
+        vec![Block::paragraph(vec![Inline::Link {
+            link_type: LinkType::Inline,
+            dest_url: "https://x.com".to_owned(),
+            title: String::new(),
+            id: String::new(),
+            content_text: Inlines(vec![Inline::Image {
+                link_type: LinkType::Inline,
+                dest_url: "i.png".to_owned(),
+                title: String::new(),
+                id: String::new(),
+                image_description: Inlines::plain_text("alt"),
+            }]),
+        }])]
+    );
+
+    assert_roundtrip(markdown);
+}
+
+/// Tests that links with no text, as used for anchors, are written as `[](url)`.
+#[test]
+fn test_empty_link_text_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let link = |link_type, id: &str| Inline::Link {
+        link_type,
+        dest_url: "https://example.org".to_owned(),
+        title: String::new(),
+        id: id.to_owned(),
+        content_text: Inlines::new(),
+    };
+
+    let ast = vec![Block::paragraph(vec![
+        Inline::plain_text("An anchor: "),
+        link(LinkType::Inline, ""),
+    ])];
+
+    assert_eq!(ast_to_markdown(&ast), "An anchor: [](https://example.org)");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    assert_roundtrip("[](https://example.org) and [](#top \"Top\")");
+    assert_roundtrip("[][ref]\n\n[ref]: https://example.org");
+
+    // Links that can't be written without text are written in a form that
+    // can.
+    assert_eq!(
+        ast_to_markdown(&[Block::paragraph(vec![link(
+            LinkType::Autolink,
+            ""
+        )])]),
+        "[](https://example.org)"
+    );
+    assert_eq!(
+        ast_to_markdown(&[Block::paragraph(vec![link(
+            LinkType::Shortcut,
+            "ref"
+        )])]),
+        "[][ref]\n\n[ref]: https://example.org"
+    );
+}
+
+#[test]
+fn test_reference_links_roundtrip() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    // Reference links are written with their definitions collected at the
+    // end of the document.
+    let markdown = indoc!(
+        r#"
+        See [a][x], [b][], and [c].
+
+        * [d][X]
+
+        [x]: https://example.org "Title \"quoted\""
+        [b]: <with space>
+        [c]: c.org"#
+    );
+
+    assert_roundtrip(markdown);
+
+    assert_roundtrip("[text][ref]\n\n[ref]: https://example.org");
+
+    // Reference images share link reference definitions with reference links.
+    let markdown = indoc!(
+        r#"
+        ![full][img], ![collapsed][], ![shortcut], and [a link][img].
+
+        [img]: photo.png "Photo"
+        [collapsed]: collapsed.png
+        [shortcut]: shortcut.png"#
+    );
+
+    assert_roundtrip(markdown);
+
+    assert_eq!(
+        canonicalize("[c]\n\n[c]: c.org\n\nText after."),
+        "[c]\n\nText after.\n\n[c]: c.org"
+    );
+}
+
+/// Tests that a reference link whose link reference definition appears before
+/// the link is resolved, and that both survive being written back out.
+#[test]
+fn test_reference_link_definition_before_use() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = "[ref]: https://example.org \"Title\"\n\nSee [text][ref].";
+
+    let ast = markdown_to_ast(markdown);
+
+    assert_eq!(
+        ast,
+        vec![Block::paragraph(vec![
+            Inline::plain_text("See "),
+            Inline::Link {
+                link_type: LinkType::Reference,
+                dest_url: "https://example.org".to_owned(),
+                title: "Title".to_owned(),
+                id: "ref".to_owned(),
+                content_text: Inlines::plain_text("text"),
+            },
+            Inline::plain_text("."),
+        ])]
+    );
+
+    // The definition is written after the link, at the end of the document.
+    assert_eq!(
+        ast_to_markdown(&ast),
+        "See [text][ref].\n\n[ref]: https://example.org \"Title\""
+    );
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+}
+
+#[test]
+fn test_to_reference_links() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let mut ast = markdown_to_ast(indoc!(
+        "
+        # [Heading link](a.org)
+
+        Existing [reference][1] and [new](b.org \"B\") links.
+
+        > [Same](a.org) destination.
+        >
+        > * *[nested](c.org)*
+
+        | Table |
+        |-------|
+        | [cell](b.org \"B\") |
+
+        <https://autolink.org> and ![image](photo.png) are unchanged.
+
+        [1]: one.org
+        "
+    ));
+
+    to_reference_links(&mut ast);
+
+    assert_eq!(
+        ast_to_markdown(&ast),
+        indoc!(
+            r#"
+            # [Heading link][2]
+
+            Existing [reference][1] and [new][3] links.
+
+             > 
+             > [Same][2] destination.
+             > 
+             > * *[nested][4]*
+
+            |Table|
+            |-----|
+            |[cell][3]|
+
+            <https://autolink.org> and ![image](photo.png) are unchanged.
+
+            [2]: a.org
+            [1]: one.org
+            [3]: b.org "B"
+            [4]: c.org"#
+        )
+        .trim_start()
+    );
+
+    // The reference links parse back to the same links.
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+}
+
+#[test]
+fn test_strip_links() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let mut ast = markdown_to_ast(indoc!(
+        "
+        # A [heading link](a.org)
+
+        Some [**bold** link](b.org \"B\"), a [reference][ref] link, and
+        [![an image](c.png)](d.org) inside a link.
+
+        * An <https://example.org> autolink.
+
+        | [Header](e.org) |
+        |-----------------|
+        | ![cell](f.png)  |
+
+        [ref]: g.org
+        "
+    ));
+
+    strip_links(&mut ast);
+
+    assert_eq!(
+        ast,
+        markdown_to_ast(indoc!(
+            "
+            # A heading link
+
+            Some **bold** link, a reference link, and
+            an image inside a link.
+
+            * An https://example.org autolink.
+
+            | Header |
+            |--------|
+            | cell   |
+            "
+        ))
+    );
+
+    // Positioned links are stripped too.
+    let mut ast = markdown_to_ast_with_positions("[a](b.org)");
+
+    strip_links(&mut ast);
+
+    assert_eq!(
+        ast,
+        vec![Block::paragraph(vec![Inline::Positioned(
+            LineCol { line: 1, column: 2 },
+            Box::new(Inline::plain_text("a")),
+        )])]
+    );
+}
+
+#[test]
+fn test_merge_adjacent_lists() {
+    use pretty_assertions::assert_eq;
+
+    let item =
+        |text: &str| ListItem(vec![Block::plain_text_paragraph(text)], None);
+
+    let mut blocks = vec![
+        Block::List(vec![item("a")], None, true),
+        Block::List(vec![item("b"), item("c")], None, true),
+        Block::List(vec![item("one")], Some(1), true),
+        Block::List(vec![item("two")], Some(5), true),
+        Block::plain_text_paragraph("Text."),
+        Block::List(vec![item("d")], None, true),
+        Block::List(vec![item("e")], None, true),
+    ];
+
+    merge_adjacent_lists(&mut blocks);
+
+    assert_eq!(
+        blocks,
+        vec![
+            Block::List(vec![item("a"), item("b"), item("c")], None, true),
+            Block::List(vec![item("one"), item("two")], Some(1), true),
+            Block::plain_text_paragraph("Text."),
+            Block::List(vec![item("d"), item("e")], None, true),
+        ]
+    );
+}
+
+#[test]
+fn test_to_plain_string() {
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast("**bold** and `code`");
+
+    assert_eq!(
+        ast[0].plain_text_contents().as_deref(),
+        Some("bold and code")
+    );
+
+    let [Block::Paragraph(inlines)] = ast.as_slice() else {
+        panic!()
+    };
+
+    assert_eq!(inlines.to_plain_string(), "bold and code");
+
+    let inlines = Inlines(vec![
+        Inline::strikethrough(Inline::plain_text("struck")),
+        Inline::SoftBreak,
+        Inline::emphasis(Inline::plain_text("soft")),
+        Inline::HardBreak,
+        Inline::Link {
+            link_type: LinkType::Inline,
+            dest_url: "example.org".to_owned(),
+            title: "Title".to_owned(),
+            id: String::new(),
+            content_text: Inlines::plain_text("hard"),
+        },
+        Inline::InlineHtml("<br>".to_owned()),
+    ]);
+
+    assert_eq!(inlines.to_plain_string(), "struck soft\nhard");
+
+    let block = Block::from_code(Some("rust"), "fn main() {}");
+
+    assert_eq!(block.plain_text_contents(), None);
+}
+
+#[test]
+fn test_canonicalize_with_options() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let input = indoc!(
+        "
+        + One
+        + Two
+
+        ```
+        code
+        ```
+        "
+    );
+
+    let compact = MarkdownOptions {
+        list_token: ListToken::Star,
+        blank_lines_between_blocks: 1,
+        ..MarkdownOptions::default()
+    };
+
+    let spacious = MarkdownOptions {
+        list_token: ListToken::Dash,
+        blank_lines_between_blocks: 2,
+        code_block_token_count: 4,
+        ..MarkdownOptions::default()
+    };
+
+    let compact_output = canonicalize_with_options(input, &compact);
+    let spacious_output = canonicalize_with_options(input, &spacious);
+
+    assert_eq!(compact_output, "* One\n* Two\n\n```\ncode\n```");
+    assert_eq!(spacious_output, "- One\n- Two\n\n\n````\ncode\n````");
+
+    // Both outputs are valid Markdown for the same document, and are already
+    // in canonical form.
+    assert_eq!(markdown_to_ast(&compact_output), markdown_to_ast(input));
+    assert_eq!(markdown_to_ast(&spacious_output), markdown_to_ast(input));
+    assert_eq!(
+        canonicalize_with_options(&spacious_output, &spacious),
+        spacious_output
+    );
+}
+
+/// Tests that any number of blank lines between paragraphs is normalized to
+/// [`MarkdownOptions::blank_lines_between_blocks`].
+#[test]
+fn test_blank_lines_between_paragraphs() {
+    use pretty_assertions::assert_eq;
+
+    for input_blank_lines in 1..=3 {
+        let separator = "\n".repeat(input_blank_lines + 1);
+        let input = format!("First paragraph.{separator}Second paragraph.");
+
+        let ast = markdown_to_ast(&input);
+
+        assert_eq!(
+            ast,
+            vec![
+                Block::plain_text_paragraph("First paragraph."),
+                Block::plain_text_paragraph("Second paragraph."),
+            ]
+        );
+
+        assert_eq!(
+            ast_to_markdown(&ast),
+            "First paragraph.\n\nSecond paragraph."
+        );
+
+        for blank_lines_between_blocks in 1..=3 {
+            let options = MarkdownOptions {
+                blank_lines_between_blocks,
+                ..MarkdownOptions::default()
+            };
+
+            let output = ast_to_markdown_with_options(&ast, &options);
+
+            let separator = "\n".repeat(blank_lines_between_blocks + 1);
+
+            assert_eq!(
+                output,
+                format!("First paragraph.{separator}Second paragraph.")
+            );
+
+            // The output is stable when parsed and written again.
+            assert_eq!(canonicalize_with_options(&output, &options), output);
+        }
+    }
+}
+
+#[test]
+fn test_extract_title() {
+    use pretty_assertions::assert_eq;
+
+    let mut blocks = markdown_to_ast("# The *Title*\n\nText.\n\n# Another H1");
+
+    assert_eq!(
+        extract_title(&mut blocks),
+        Some(Inlines(vec![
+            Inline::plain_text("The "),
+            Inline::emphasis(Inline::plain_text("Title")),
+        ]))
+    );
+    assert_eq!(blocks, markdown_to_ast("Text.\n\n# Another H1"));
+
+    // Only an H1 heading that is the first block is extracted.
+    for markdown in ["## Subtitle\n\nText.", "Text.\n\n# Title", ""] {
+        let mut blocks = markdown_to_ast(markdown);
+
+        assert_eq!(extract_title(&mut blocks), None);
+        assert_eq!(blocks, markdown_to_ast(markdown));
+    }
+}
+
+#[test]
+fn test_inject_heading_anchors() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let mut ast = markdown_to_ast(indoc!(
+        "
+        # Getting *Started*
+
+        ## Setup {#custom-id}
+
+        > ## Getting Started
+        "
+    ));
+
+    inject_heading_anchors(&mut ast);
+
+    assert_eq!(
+        ast[0],
+        Block::Heading(
+            HeadingLevel::H1,
+            Inlines(vec![
+                Inline::InlineHtml(
+                    r#"<a id="getting-started"></a>"#.to_owned()
+                ),
+                Inline::plain_text("Getting "),
+                Inline::emphasis(Inline::plain_text("Started")),
+            ]),
+            HeadingAttributes::default(),
+        )
+    );
+
+    // Headings with an explicit id are left unchanged, and repeated slugs are
+    // made unique.
+    assert_eq!(ast_to_markdown(&ast[1..2]), "## Setup { #custom-id }");
+
+    let Block::BlockQuote { kind: _, blocks } = &ast[2] else {
+        panic!()
+    };
+
+    assert_eq!(
+        ast_to_markdown(blocks),
+        r#"## <a id="getting-started-1"></a>Getting Started"#
+    );
+
+    //==================================
+    // Slugs colliding with explicit ids
+    //==================================
+
+    for markdown in [
+        "# Intro {#intro}\n\n# Intro",
+        "# Intro\n\n## Other {#intro}",
+    ] {
+        let mut ast = markdown_to_ast(markdown);
+
+        inject_heading_anchors(&mut ast);
+
+        let markdown = ast_to_markdown(&ast);
+
+        assert_eq!(markdown.matches(r#"id="intro""#).count(), 0, "{markdown}");
+        assert_eq!(markdown.matches("#intro").count(), 1, "{markdown}");
+        assert!(markdown.contains(r#"<a id="intro-1"></a>Intro"#));
+    }
+
+    // A slug with a numeric suffix is skipped if it is already in use.
+    let mut ast = markdown_to_ast("# A\n\n# A-1 {#a-1}\n\n# A\n\n# A-1");
+
+    inject_heading_anchors(&mut ast);
+
+    assert_eq!(
+        ast_to_markdown(&ast),
+        indoc!(
+            r#"
+            # <a id="a"></a>A
+
+            # A-1 { #a-1 }
+
+            # <a id="a-2"></a>A
+
+            # <a id="a-1-1"></a>A-1"#
+        )
+        .trim_start()
+    );
+}
+
+#[test]
+fn test_inlines_eq_ignoring_link_ids() {
+    let link = |id: &str| Inline::Link {
+        link_type: LinkType::Reference,
+        dest_url: "example.org".to_owned(),
+        title: String::new(),
+        id: id.to_owned(),
+        content_text: Inlines(vec![Inline::emphasis(Inline::plain_text("x"))]),
+    };
+
+    // Links nested inside other inlines are compared ignoring their ids too.
+    let a = Inlines(vec![
+        Inline::plain_text("See "),
+        Inline::Strong(Inlines(vec![link("Foo")])),
+    ]);
+    let b = Inlines(vec![
+        Inline::plain_text("See "),
+        Inline::Strong(Inlines(vec![link("FOO")])),
+    ]);
+
+    assert_ne!(a, b);
+    assert!(a.eq_ignoring_link_ids(&b));
+
+    // Differences other than the id are not ignored.
+    let c = Inlines(vec![
+        Inline::plain_text("See "),
+        Inline::Emphasis(Inlines(vec![link("Foo")])),
+    ]);
+
+    assert!(!a.eq_ignoring_link_ids(&c));
+    assert!(!a.eq_ignoring_link_ids(&Inlines(vec![Inline::plain_text("See ")])));
+}
+
+/// Tests that a paragraph starting with `#` that follows a heading is not
+/// written out as another heading.
+#[test]
+fn test_heading_followed_by_hash_text_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![
+        Block::Heading(
+            HeadingLevel::H1,
+            Inlines::plain_text("Title"),
+            HeadingAttributes::default(),
+        ),
+        Block::plain_text_paragraph("#something"),
+        Block::plain_text_paragraph("# Not a heading"),
+    ];
+
+    let markdown = ast_to_markdown(&ast);
+
+    assert_eq!(markdown, "# Title\n\n\\#something\n\n\\# Not a heading");
+    assert_eq!(markdown_to_ast(&markdown), ast);
+
+    assert_roundtrip(&markdown);
+}
+
+/// Tests that headings are written out with exactly one space after the
+/// opening `#` characters.
+#[test]
+fn test_heading_spacing() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::Heading(
+        HeadingLevel::H2,
+        Inlines::plain_text("Heading"),
+        HeadingAttributes::default(),
+    )];
+
+    assert_eq!(ast_to_markdown(&ast), "## Heading");
+
+    assert_eq!(canonicalize("##    Heading   "), "## Heading");
+    assert_eq!(canonicalize("  ## Heading ##"), "## Heading");
+    assert_eq!(canonicalize("##\tHeading"), "## Heading");
+
+    // Without a space after the `#` characters, this is a paragraph, not a
+    // heading. (CommonMark: [ATX headings](https://spec.commonmark.org/0.30/#atx-headings))
+    let markdown = "##Heading";
+
+    assert_eq!(
+        markdown_to_ast(markdown),
+        vec![Block::plain_text_paragraph("##Heading")]
+    );
+    assert_eq!(canonicalize(markdown), "\\##Heading");
+
+    assert_roundtrip(&canonicalize(markdown));
+}
+
+#[test]
+fn test_heading_attributes_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast("# Title {#my-id .class key=val}");
+
+    assert_eq!(
+        ast,
+        vec![Block::Heading(
+            HeadingLevel::H1,
+            Inlines::plain_text("Title"),
+            HeadingAttributes {
+                id: Some("my-id".to_owned()),
+                classes: vec!["class".to_owned()],
+                attrs: vec![("key".to_owned(), Some("val".to_owned()))],
+            },
+        )]
+    );
+
+    assert_eq!(ast_to_markdown(&ast), "# Title { #my-id .class key=val }");
+
+    assert_roundtrip("# Title { #my-id .class key=val }");
+
+    assert_roundtrip("## Title { #a .b .c d=e f=g }");
+
+    // Headings without attributes are written without braces.
+    assert_roundtrip("# Title");
+}
+
+/// Tests that newlines in programmatically constructed [`Inline::Text`] are
+/// written out as soft breaks.
+#[test]
+fn test_text_newlines_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::BlockQuote {
+        kind: None,
+        blocks: vec![Block::plain_text_paragraph("line1\nline2\n\n# line3")],
+    }];
+
+    let markdown = ast_to_markdown(&ast);
+
+    assert_eq!(markdown, "\n > \n > line1\n > line2\n > \\# line3");
+
+    assert_eq!(
+        markdown_to_ast(&markdown),
+        vec![Block::BlockQuote {
+            kind: None,
+            blocks: vec![Block::paragraph(vec![
+                Inline::plain_text("line1"),
+                Inline::SoftBreak,
+                Inline::plain_text("line2"),
+                Inline::SoftBreak,
+                Inline::plain_text("# line3"),
+            ])],
+        }]
+    );
+
+    assert_roundtrip(&markdown);
+}
+
+#[test]
+fn test_inline_map_children() {
+    use pretty_assertions::assert_eq;
+
+    let upper = |inlines: Inlines| {
+        inlines.map(|inline| match inline {
+            Inline::Text(text) => Inline::Text(text.to_uppercase()),
+            other => other,
+        })
+    };
+
+    assert_eq!(
+        Inline::parse("[*a* b](example.org)").map_children(upper),
+        Inline::parse("[*a* B](example.org)")
+    );
+
+    assert_eq!(
+        Inline::parse("![cat photo](photo.png)").map_children(upper),
+        Inline::parse("![CAT PHOTO](photo.png)")
+    );
+
+    assert_eq!(
+        Inline::parse("~~x~~").map_children(upper),
+        Inline::parse("~~X~~")
+    );
+
+    // Leaf inlines are returned unchanged, without calling `f`.
+    for leaf in [
+        Inline::plain_text("text"),
+        Inline::code("code"),
+        Inline::SoftBreak,
+        Inline::HardBreak,
+    ] {
+        assert_eq!(
+            leaf.clone()
+                .map_children(|_| panic!("called on a leaf inline")),
+            leaf
+        );
+    }
+}
+
+#[test]
+fn test_inlines_first_last_text() {
+    use pretty_assertions::assert_eq;
+
+    let [Block::Paragraph(inlines)] = &markdown_to_ast("**Bold** start")[..]
+    else {
+        panic!("expected a single paragraph")
+    };
+
+    assert_eq!(inlines.first_text(), Some("Bold"));
+    assert_eq!(inlines.last_text(), Some(" start"));
+
+    let [Block::Paragraph(inlines)] =
+        &markdown_to_ast("[*`code`*](example.org) middle ***end***")[..]
+    else {
+        panic!("expected a single paragraph")
+    };
+
+    assert_eq!(inlines.first_text(), Some("code"));
+    assert_eq!(inlines.last_text(), Some("end"));
+
+    assert_eq!(Inlines(vec![]).first_text(), None);
+    assert_eq!(
+        Inlines(vec![Inline::emphasis(Inlines(vec![])), Inline::HardBreak])
+            .last_text(),
+        None,
+    );
+}
+
+#[test]
+fn test_inlines_contains_formatting() {
+    let parse = |markdown| match markdown_to_ast(markdown).as_slice() {
+        [Block::Paragraph(inlines)] => inlines.clone(),
+        ast => panic!("expected a single paragraph, got: {ast:?}"),
+    };
+
+    assert!(!Inlines(vec![]).contains_formatting());
+    assert!(!parse("plain text").contains_formatting());
+    assert!(!parse("line one\nline two  \nline three").contains_formatting());
+
+    assert!(parse("some *emphasis*").contains_formatting());
+    assert!(parse("some **strong**").contains_formatting());
+    assert!(parse("some ~~strikethrough~~").contains_formatting());
+    assert!(parse("some `code`").contains_formatting());
+    assert!(parse("a [link](example.org)").contains_formatting());
+    assert!(parse("an ![image](photo.png)").contains_formatting());
+}
+
+#[test]
+fn test_block_quote_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::BlockQuote {
+        kind: None,
+        blocks: vec![
+            Block::plain_text_paragraph("one"),
+            Block::plain_text_paragraph("two"),
+        ],
+    }];
+
+    let markdown = ast_to_markdown(&ast);
+
+    assert_eq!(markdown, "\n > \n > one\n > \n > two");
+    assert_eq!(markdown_to_ast(&markdown), ast);
+
+    assert_roundtrip(&markdown);
+}
+
+/// Tests that GitHub-style alert block quotes are written out with their
+/// marker on the first line only.
+#[test]
+fn test_alert_block_quote_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::BlockQuote {
+        kind: Some(md::BlockQuoteKind::Warning),
+        blocks: vec![
+            Block::plain_text_paragraph("one"),
+            Block::plain_text_paragraph("two"),
+        ],
+    }];
+
+    let markdown = ast_to_markdown(&ast);
+
+    assert_eq!(markdown, "\n > [!WARNING]\n > one\n > \n > two");
+    assert_eq!(markdown_to_ast(&markdown), ast);
+
+    assert_roundtrip(&markdown);
+    assert_roundtrip(
+        "\n > [!NOTE]\n > A note.\n > \n >  > [!TIP]\n >  > A nested tip.",
+    );
+
+    // The kind of a parsed alert survives being written out and parsed back.
+    let ast = markdown_to_ast("> [!WARNING]\n> text");
+
+    assert_eq!(
+        ast,
+        vec![Block::BlockQuote {
+            kind: Some(md::BlockQuoteKind::Warning),
+            blocks: vec![Block::plain_text_paragraph("text")],
+        }]
+    );
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+}
+
+/// Tests that alert block quotes nested inside alerts of a different kind are
+/// written out with the marker of each kind at its own nesting level.
+#[test]
+fn test_nested_alert_block_quote_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let alert = |kind, blocks| Block::BlockQuote {
+        kind: Some(kind),
+        blocks,
+    };
+
+    let warning = alert(
+        md::BlockQuoteKind::Warning,
+        vec![Block::plain_text_paragraph("Inner warning.")],
+    );
+
+    let ast = vec![alert(
+        md::BlockQuoteKind::Note,
+        vec![
+            Block::plain_text_paragraph("Outer note."),
+            warning.clone(),
+            Block::plain_text_paragraph("After."),
+        ],
+    )];
+
+    let markdown = ast_to_markdown(&ast);
+
+    assert_eq!(
+        markdown,
+        concat!(
+            "\n > [!NOTE]\n > Outer note.\n > \n",
+            " >  > [!WARNING]\n >  > Inner warning.\n > \n",
+            " > After."
+        )
+    );
+    assert_eq!(markdown_to_ast(&markdown), ast);
+
+    assert_roundtrip(&markdown);
+
+    // An alert that is the first block of another alert.
+    let ast = vec![alert(
+        md::BlockQuoteKind::Caution,
+        vec![alert(md::BlockQuoteKind::Important, vec![warning])],
+    )];
+
+    assert_eq!(
+        ast_to_markdown(&ast),
+        concat!(
+            "\n > [!CAUTION]\n >  > [!IMPORTANT]\n",
+            " >  >  > [!WARNING]\n >  >  > Inner warning."
+        )
+    );
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+}
+
+/// Tests that rules inside block quotes and list items are written out in a
+/// form that is parsed back as a rule.
+#[test]
+fn test_rule_in_container_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![
+        Block::BlockQuote {
+            kind: None,
+            blocks: vec![
+                Block::plain_text_paragraph("a"),
+                Block::Rule,
+                Block::plain_text_paragraph("b"),
+            ],
+        },
+        Block::List(
+            vec![
+                ListItem(vec![Block::Rule], None),
+                ListItem(
+                    vec![Block::plain_text_paragraph("c"), Block::Rule],
+                    None,
+                ),
+            ],
+            None,
+            false,
+        ),
+    ];
+
+    let markdown = ast_to_markdown(&ast);
+
+    assert_eq!(
+        markdown,
+        "\n > \n > a\n > \n > ---\n > \n > b\n\n* ---\n\n* c\n  \n  ---"
+    );
+    assert_eq!(markdown_to_ast(&markdown), ast);
+
+    assert_roundtrip(&markdown);
+}
+
+#[test]
+fn test_table_cell_line_breaks() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::Table {
+        alignments: vec![md::Alignment::None, md::Alignment::None],
+        headers: vec![Inlines::plain_text("a"), Inlines::plain_text("b")],
+        rows: vec![vec![
+            Inlines(vec![
+                Inline::plain_text("x"),
+                Inline::HardBreak,
+                Inline::plain_text("y"),
+            ]),
+            Inlines(vec![
+                Inline::plain_text("z"),
+                Inline::HardBreak,
+                Inline::plain_text("w"),
+            ]),
+        ]],
+    }];
+
+    assert_eq!(ast_to_markdown(&ast), "|a|b|\n|-|-|\n|x<br>y|z<br>w|");
+
+    //==================================
+    // Without normalization
+    //==================================
+
+    // By default, <br> tags are kept as inline HTML, so the source round-trips
+    // unchanged.
+    let markdown = "|a|b|\n|-|-|\n|x<br>y|z<BR />w|";
+
+    assert_eq!(
+        markdown_to_ast(markdown)[0],
+        Block::Table {
+            alignments: vec![md::Alignment::None, md::Alignment::None],
+            headers: vec![Inlines::plain_text("a"), Inlines::plain_text("b")],
+            rows: vec![vec![
+                Inlines(vec![
+                    Inline::plain_text("x"),
+                    Inline::InlineHtml("<br>".to_owned()),
+                    Inline::plain_text("y"),
+                ]),
+                Inlines(vec![
+                    Inline::plain_text("z"),
+                    Inline::InlineHtml("<BR />".to_owned()),
+                    Inline::plain_text("w"),
+                ]),
+            ]],
+        }
+    );
+
+    assert_roundtrip(markdown);
+    assert_roundtrip("|a|b|\n|-|-|\n|x<br/>y|z<br>w|");
+
+    //==================================
+    // With normalization
+    //==================================
+
+    // All of the common spellings of the <br> tag are normalized.
+    for markdown in [
+        "|a|b|\n|-|-|\n|x<br>y|z<BR />w|",
+        "|a|b|\n|-|-|\n|x<br/>y|z<br>w|",
+    ] {
+        let mut normalized = markdown_to_ast(markdown);
+
+        normalize_table_breaks(&mut normalized);
+
+        assert_eq!(normalized, ast);
+    }
+
+    // <br> tags outside of tables are left unchanged.
+    let mut html = markdown_to_ast("x<br>y");
+
+    normalize_table_breaks(&mut html);
+
+    assert_eq!(html, markdown_to_ast("x<br>y"));
+}
+
+/// Tests that a newline in the text of a table cell is written as a space,
+/// instead of ending the row.
+#[test]
+fn test_table_cell_newline_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let table = |cell: Inlines| {
+        vec![Block::Table {
+            alignments: vec![md::Alignment::None],
+            headers: vec![Inlines::plain_text("h")],
+            rows: vec![vec![cell]],
+        }]
+    };
+
+    for cell in [
+        Inlines::plain_text("a\nb"),
+        Inlines(vec![
+            Inline::plain_text("a"),
+            Inline::SoftBreak,
+            Inline::plain_text("b"),
+        ]),
+    ] {
+        let markdown = ast_to_markdown(&table(cell));
+
+        assert_eq!(markdown, "|h|\n|-|\n|a b|");
+        assert_eq!(
+            markdown_to_ast(&markdown),
+            table(Inlines::plain_text("a b"))
+        );
+    }
+
+    assert_roundtrip("|h|\n|-|\n|a b|");
+}
+
+#[test]
+fn test_footnote_roundtrip() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        markdown_to_ast("Text[^1]\n\n[^1]: note"),
+        vec![
+            Block::paragraph(vec![
+                Inline::plain_text("Text"),
+                Inline::FootnoteReference("1".to_owned()),
+            ]),
+            Block::FootnoteDefinition {
+                label: "1".to_owned(),
+                blocks: vec![Block::plain_text_paragraph("note")],
+            },
+        ]
+    );
+
+    assert_roundtrip("Text[^1]\n\n[^1]: note");
+
+    let documents = [
+        indoc!(
+            "
+            A[^a] b[^b].
+
+            [^a]: First
+                line
+
+                Second *para*.
+
+            [^b]: > quote
+
+            * item[^a]
+            "
+        ),
+        "> q[^x]\n>\n> [^x]: in quote",
+    ];
+
+    for document in documents {
+        let ast = markdown_to_ast(document);
+
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+    }
+
+    // A footnote reference without a matching definition is plain text.
+    assert_eq!(
+        markdown_to_ast("Text[^1]"),
+        vec![Block::plain_text_paragraph("Text[^1]")]
+    );
+}
+
+#[test]
+fn test_html_roundtrip() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        <div align=\"center\">
+          <img src=\"logo.png\">
+        </div>
+
+        H<sub>2</sub>O
+        "
+    ));
+
+    assert_eq!(
+        ast,
+        vec![
+            Block::Html(
+                "<div align=\"center\">\n  <img src=\"logo.png\">\n</div>\n"
+                    .to_owned()
+            ),
+            Block::paragraph(vec![
+                Inline::plain_text("H"),
+                Inline::InlineHtml("<sub>".to_owned()),
+                Inline::plain_text("2"),
+                Inline::InlineHtml("</sub>".to_owned()),
+                Inline::plain_text("O"),
+            ]),
+        ]
+    );
+
+    assert_roundtrip("<div>\n  *not emphasis*\n</div>\n\nH<sub>2</sub>O");
+
+    for document in [
+        "Text\n\n<!-- comment -->\n\nMore text",
+        "# Title\n<br>\ntext",
+        "* <div>a</div>\n* b <span>c</span>",
+        "> <details>\n> <summary>More</summary>\n>\n> Hidden\n> </details>",
+    ] {
+        let ast = markdown_to_ast(document);
+
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast, "{document}");
+    }
+
+    // An HTML block whose last line does not end with a newline is not joined
+    // to the following block.
+    let ast = vec![
+        Block::Html("<div>x</div>".to_owned()),
+        Block::plain_text_paragraph("after"),
+    ];
+
+    assert_eq!(ast_to_markdown(&ast), "<div>x</div>\n\nafter");
+    assert_eq!(
+        markdown_to_ast(&ast_to_markdown(&ast)),
+        vec![
+            Block::Html("<div>x</div>\n".to_owned()),
+            Block::plain_text_paragraph("after"),
+        ]
+    );
+
+    let ast = vec![Block::BlockQuote {
+        kind: None,
+        blocks: vec![
+            Block::Html("<div>x</div>".to_owned()),
+            Block::plain_text_paragraph("after"),
+        ],
+    }];
+
+    assert_eq!(
+        markdown_to_ast(&ast_to_markdown(&ast)),
+        vec![Block::BlockQuote {
+            kind: None,
+            blocks: vec![
+                Block::Html("<div>x</div>\n".to_owned()),
+                Block::plain_text_paragraph("after"),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn test_math_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        markdown_to_ast(r"Inline $x^2$ and display $$\int_0^1 x$$ math."),
+        vec![Block::paragraph(vec![
+            Inline::plain_text("Inline "),
+            Inline::InlineMath("x^2".to_owned()),
+            Inline::plain_text(" and display "),
+            Inline::DisplayMath(r"\int_0^1 x".to_owned()),
+            Inline::plain_text(" math."),
+        ])]
+    );
+
+    assert_roundtrip(r"Inline $x^2$ and display $$\int_0^1 x$$ math.");
+    assert_roundtrip("$$\nx = 1\n$$");
+
+    // Escaped dollar signs are preserved in the math source.
+    assert_eq!(
+        Inline::parse(r"$\$5 + \$3$"),
+        Inline::InlineMath(r"\$5 + \$3".to_owned())
+    );
+    assert_roundtrip(r"$\$5 + \$3$");
+    assert_roundtrip(r"$$\text{cost} = \$5$$");
+
+    // Unescaped dollar signs in constructed math are escaped when written out.
+    let ast = vec![Block::paragraph(vec![
+        Inline::InlineMath("a$b".to_owned()),
+        Inline::plain_text(" and "),
+        Inline::DisplayMath(r"\$x$".to_owned()),
+    ])];
+
+    assert_eq!(ast_to_markdown(&ast), r"$a\$b$ and $$\$x\$$$");
+    assert_eq!(
+        markdown_to_ast(&ast_to_markdown(&ast)),
+        vec![Block::paragraph(vec![
+            Inline::InlineMath(r"a\$b".to_owned()),
+            Inline::plain_text(" and "),
+            Inline::DisplayMath(r"\$x\$".to_owned()),
+        ])]
+    );
+
+    // Math at the start of a paragraph is separated from the preceding block.
+    assert_roundtrip("$$x$$\n\n$y$");
+
+    // Dollar signs in text are escaped, so they are not parsed as math.
+    let ast = vec![Block::plain_text_paragraph("It costs $5, or $10 for two.")];
+
+    assert_eq!(ast_to_markdown(&ast), r"It costs \$5, or \$10 for two.");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+}
+
+/// Tests that code spans consisting entirely of spaces, which are exempt from
+/// the CommonMark rule that strips one leading and trailing space, round-trip
+/// unchanged.
+///
+/// CommonMark: [code spans](https://spec.commonmark.org/0.30/#code-spans)
+#[test]
+fn test_whitespace_code_span_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    for code in [" ", "   ", "  x  "] {
+        let ast = vec![Block::paragraph(vec![
+            Inline::plain_text("a "),
+            Inline::code(code),
+            Inline::plain_text(" b"),
+        ])];
+
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+    }
+
+    assert_roundtrip("a `   ` b");
+    assert_roundtrip("` `");
+    assert_roundtrip("`   x   `");
+}
+
+/// Tests that non-breaking spaces and other Unicode whitespace in
+/// [`Inline::Text`] are written out literally, and not normalized to regular
+/// spaces or trimmed, including at the start and end of a block.
+///
+/// CommonMark only strips leading and trailing spaces and tabs, so this
+/// whitespace is significant.
+#[test]
+fn test_special_whitespace_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    for text in [
+        // NO-BREAK SPACE
+        "a\u{A0}b",
+        "\u{A0}\u{A0}leading",
+        "trailing\u{A0}",
+        // EM SPACE, THIN SPACE, NARROW NO-BREAK SPACE, IDEOGRAPHIC SPACE
+        "a\u{2003}b\u{2009}c\u{202F}d\u{3000}e",
+        // ZERO WIDTH SPACE
+        "a\u{200B}b",
+    ] {
+        let ast = vec![Block::paragraph(vec![Inline::plain_text(text)])];
+
+        assert_eq!(ast_to_markdown(&ast), text);
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+    }
+
+    assert_roundtrip("# Title\u{A0}");
+    assert_roundtrip("* item\u{A0}");
+    assert_roundtrip("[\u{A0}link\u{A0}](example.org)");
+    assert_roundtrip("`code\u{A0}`");
+    assert_roundtrip("|\u{A0}a\u{A0}|b|\n|---|-|\n|c|\u{2003}d|");
+    assert_roundtrip("end\u{A0}\u{A0}\nnext");
+}
+
+/// Tests that an empty document is written out as an empty string, and that
+/// empty or blank input is parsed as an empty document.
+#[test]
+fn test_empty_document_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(ast_to_markdown(&[]), "");
+    assert_eq!(
+        ast_to_markdown_with_options(&[], &MarkdownOptions::default()),
+        ""
+    );
+
+    assert_eq!(markdown_to_ast(""), vec![]);
+    assert_eq!(markdown_to_ast("\n \n\t\n"), vec![]);
+
+    assert_roundtrip("");
+}
+
+/// Tests that a code span followed by a class attribute, e.g.
+/// `` `code`{.rust} ``, which is not supported, is parsed as a plain code span
+/// followed by text, and is written back out unchanged.
+#[test]
+fn test_code_span_class_attribute_fallback() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = "Use `let x = 1;`{.rust} here.";
+
+    assert_eq!(
+        markdown_to_ast(markdown),
+        vec![Block::paragraph(vec![
+            Inline::plain_text("Use "),
+            Inline::code("let x = 1;"),
+            Inline::plain_text("{.rust} here."),
+        ])]
+    );
+
+    assert_roundtrip(markdown);
+}
+
+/// Tests that Markdown characters in [`Inline::Text`] are escaped when written
+/// out, so that the text parses back unchanged.
+#[test]
+fn test_text_escaping_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::paragraph(vec![Inline::plain_text("a*b*c")])];
+
+    assert_eq!(ast_to_markdown(&ast), r"a\*b\*c");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    for text in [
+        "a*b*c",
+        "snake_case_name",
+        "a `tick` b",
+        "[not a link](example.org)",
+        "![not an image](photo.png)",
+        r"back\slash",
+        "a <span> b",
+        "~~not struck~~",
+        "&copy; &#65;",
+        "# not a heading",
+        "> not a quote",
+        "- not a list",
+        "+ not a list",
+        "text\n- not a list",
+        "text\n  > not a quote",
+        "a - b + c > d # e",
+    ] {
+        let ast = vec![Block::plain_text_paragraph(text)];
+        let markdown = ast_to_markdown(&ast);
+
+        let expected = match text.split_once('\n') {
+            Some((first, second)) => Block::paragraph(vec![
+                Inline::plain_text(first),
+                Inline::SoftBreak,
+                Inline::plain_text(second),
+            ]),
+            None => Block::plain_text_paragraph(text),
+        };
+
+        assert_eq!(markdown_to_ast(&markdown), vec![expected], "{markdown}");
+    }
+
+    // `<` is escaped only where it could start inline HTML or an autolink.
+    let ast = vec![Block::plain_text_paragraph("compare a <b and c")];
+
+    assert_eq!(ast_to_markdown(&ast), r"compare a \<b and c");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    for (text, expected) in [
+        ("a < b", "a < b"),
+        ("1 <2", "1 <2"),
+        ("</p> <!-- x -->", r"\</p> \<!-- x -->"),
+        ("ends with <", r"ends with \<"),
+    ] {
+        let ast = vec![Block::plain_text_paragraph(text)];
+
+        assert_eq!(ast_to_markdown(&ast), expected);
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+    }
+
+    // `#` characters in headings are escaped, so that they are not read as a
+    // closing sequence.
+    let ast = vec![Block::Heading(
+        HeadingLevel::H2,
+        Inlines::plain_text("Issue #"),
+        HeadingAttributes::default(),
+    )];
+
+    assert_eq!(ast_to_markdown(&ast), r"## Issue \#");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    // Code spans and autolinks are written out verbatim.
+    let ast = vec![Block::paragraph(vec![
+        Inline::code("a*b*c"),
+        Inline::plain_text(" "),
+        Inline::Link {
+            link_type: md::LinkType::Autolink,
+            dest_url: "https://example.org/a_b".to_owned(),
+            title: String::new(),
+            id: String::new(),
+            content_text: Inlines::plain_text("https://example.org/a_b"),
+        },
+    ])];
+
+    assert_eq!(ast_to_markdown(&ast), "`a*b*c` <https://example.org/a_b>");
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+
+    assert_roundtrip(r"a\*b\*c \_d\_ \[e\] \<f> \~g\~ &amp;copy;");
+}
+
+/// Tests that text which would be parsed back as an ordered list item, setext
+/// heading underline, indented code block, or image is escaped when written
+/// out.
+#[test]
+fn test_line_start_escaping_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let link = Inline::Link {
+        link_type: md::LinkType::Inline,
+        dest_url: "u".to_owned(),
+        title: String::new(),
+        id: String::new(),
+        content_text: Inlines::plain_text("x"),
+    };
+
+    for (ast, expected) in [
+        (vec![Inline::plain_text("1. not a list")], r"1\. not a list"),
+        (vec![Inline::plain_text("3) x")], r"3\) x"),
+        (vec![Inline::plain_text("1.5 apples")], "1.5 apples"),
+        (
+            vec![
+                Inline::plain_text("a"),
+                Inline::SoftBreak,
+                Inline::plain_text("==="),
+            ],
+            "a\n\\===",
+        ),
+        (
+            vec![Inline::plain_text("Wow!"), link.clone()],
+            r"Wow\![x](u)",
+        ),
+        (vec![Inline::plain_text("!"), link], r"\![x](u)"),
+        (vec![Inline::plain_text("    x")], "&#32;   x"),
+        (
+            vec![
+                Inline::plain_text("a"),
+                Inline::SoftBreak,
+                Inline::plain_text("\tb"),
+            ],
+            "a\n&#9;b",
+        ),
+    ] {
+        let ast = vec![Block::paragraph(ast)];
+
+        assert_eq!(ast_to_markdown(&ast), expected);
+        assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
+    }
+
+    // Escapes in the input are kept when it is canonicalized.
+    for markdown in
+        [r"1\. not list", r"2\) not list", "a\n\\===", r"Wow\![x](u)"]
+    {
+        assert_eq!(canonicalize(markdown), markdown);
+    }
+}
+
+/// Tests that unterminated emphasis, strong, and code span delimiters, which
+/// are parsed as literal text, are escaped when written out so that they
+/// round-trip as text.
+#[test]
+fn test_unterminated_formatting_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    for (markdown, canonical) in [
+        ("**bold", r"\*\*bold"),
+        ("*emphasis", r"\*emphasis"),
+        ("__strong", r"\_\_strong"),
+        ("~~struck", r"\~\~struck"),
+        ("`code", r"\`code"),
+        ("``code`", r"\`\`code\`"),
+        ("text **bold", r"text \*\*bold"),
+    ] {
+        let ast = markdown_to_ast(markdown);
+
+        assert_eq!(ast, vec![Block::plain_text_paragraph(markdown)]);
+
+        assert_eq!(ast_to_markdown(&ast), canonical);
+
+        assert_eq!(markdown_to_ast(canonical), ast);
+    }
+}
+
+#[test]
+fn test_table_cell_pipes_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = r"|a|b|
+|-|-|
+|`x\|y`|z\|w|";
+
+    let ast = vec![Block::Table {
+        alignments: vec![md::Alignment::None, md::Alignment::None],
+        headers: vec![Inlines::plain_text("a"), Inlines::plain_text("b")],
+        rows: vec![vec![
+            Inlines(vec![Inline::code("x|y")]),
+            Inlines::plain_text("z|w"),
+        ]],
+    }];
+
+    assert_eq!(markdown_to_ast(markdown), ast);
+    assert_eq!(ast_to_markdown(&ast), markdown);
+
+    assert_roundtrip(markdown);
+
+    // Pipes in programmatically constructed text are escaped too.
+    let ast = vec![Block::Table {
+        alignments: vec![md::Alignment::None],
+        headers: vec![Inlines::plain_text("a|b")],
+        rows: vec![],
+    }];
+
+    assert_eq!(ast_to_markdown(&ast), "|a\\|b|\n|---|");
+}
+
+/// Tests that formatting in table header cells is preserved.
+#[test]
+fn test_table_header_formatting_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = r"|**Name**|*Role*|`id`|
+|----|----|----|
+|Ada|Engineer|1|";
+
+    let ast = markdown_to_ast(markdown);
+
+    let [Block::Table { headers, .. }] = ast.as_slice() else {
+        panic!("expected a table, got: {ast:?}")
+    };
+
+    assert_eq!(
+        headers,
+        &[
+            Inlines(vec![Inline::strong(Inline::plain_text("Name"))]),
+            Inlines(vec![Inline::emphasis(Inline::plain_text("Role"))]),
+            Inlines(vec![Inline::code("id")]),
+        ]
+    );
+
+    assert_eq!(ast_to_markdown(&ast), markdown);
+
+    assert_roundtrip(markdown);
+}
+
+#[test]
+fn test_table_cell_image_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = r"|Badge|Status|
+|-----|------|
+|![badge](x.svg)|[![build](build.svg)](https://example.org)|";
+
+    let ast = markdown_to_ast(markdown);
+
+    let [Block::Table { rows, .. }] = ast.as_slice() else {
+        panic!("expected a table, got: {ast:?}")
+    };
+
+    assert_eq!(
+        rows[0][0],
+        Inlines(vec![Inline::Image {
+            link_type: md::LinkType::Inline,
+            dest_url: "x.svg".to_owned(),
+            title: String::new(),
+            id: String::new(),
+            image_description: Inlines::plain_text("badge"),
+        }])
+    );
+
+    assert_eq!(ast_to_markdown(&ast), markdown);
+
+    assert_roundtrip(markdown);
+
+    // Pipes in image descriptions, destinations, and titles are escaped.
+    assert_roundtrip(
+        r#"|Badge|
+|-----|
+|![a\|b](x\|y.svg "c&#124;d")|"#,
+    );
+}
+
+#[test]
+fn test_table_alignment_roundtrip() {
+    use indoc::indoc;
+    use md::Alignment;
+    use pretty_assertions::assert_eq;
+
+    let table = |headers: [&str; 4]| Block::Table {
+        alignments: vec![
+            Alignment::None,
+            Alignment::Left,
+            Alignment::Center,
+            Alignment::Right,
+        ],
+        headers: headers.into_iter().map(Inlines::plain_text).collect(),
+        rows: vec![vec![
+            Inlines::plain_text("1"),
+            Inlines::plain_text("2"),
+            Inlines::plain_text("3"),
+            Inlines::plain_text("4"),
+        ]],
+    };
+
+    let markdown = indoc!(
+        "
+        |None|Left|Center|Right|
+        |----|:---|:----:|----:|
+        |1|2|3|4|"
+    );
+
+    let ast = vec![table(["None", "Left", "Center", "Right"])];
+
+    assert_eq!(markdown_to_ast(markdown), ast);
+    assert_eq!(ast_to_markdown(&ast), markdown);
+    assert_roundtrip(markdown);
+
+    // Headers too short to hold the alignment colons get widened delimiters.
+    let markdown = "|a|b|c|d|\n|-|:-|:-:|-:|\n|1|2|3|4|";
+
+    let ast = vec![table(["a", "b", "c", "d"])];
+
+    assert_eq!(markdown_to_ast(markdown), ast);
+    assert_eq!(ast_to_markdown(&ast), markdown);
+    assert_roundtrip(markdown);
+}
+
+/// Tests that the content of a list item is always emitted aligned with the
+/// content of its first line ("marker + space"), regardless of how far it was
+/// indented in the input.
+#[test]
+fn test_list_item_content_indentation() {
+    use pretty_assertions::assert_eq;
+
+    // Note: The lines containing only whitespace below are written with
+    //       trailing spaces matching the current list item indentation.
+    let canonical = "* foo\n  \n  bar\n  \n  * baz\n    \n    qux";
+
+    assert_eq!(
+        canonicalize("*   foo\n\n    bar\n\n    * baz\n\n      qux"),
+        canonical
+    );
+    assert_eq!(
+        canonicalize("* foo\n\n  bar\n\n  * baz\n\n    qux"),
+        canonical
+    );
+
+    assert_eq!(
+        markdown_to_ast(canonical),
+        vec![Block::List(
+            vec![ListItem(
+                vec![
+                    Block::plain_text_paragraph("foo"),
+                    Block::plain_text_paragraph("bar"),
+                    Block::List(
+                        vec![ListItem(
+                            vec![
+                                Block::plain_text_paragraph("baz"),
+                                Block::plain_text_paragraph("qux"),
+                            ],
+                            None,
+                        )],
+                        None,
+                        false
+                    ),
+                ],
+                None,
+            )],
+            None,
+            false
+        )]
+    );
+
+    assert_roundtrip(canonical);
+}
+
+/// Tests that a list whose only blank line is inside its first item is
+/// treated as loose in its entirety.
+#[test]
+fn test_list_leading_loose_item_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    // Note: The line containing only whitespace below is written with
+    //       trailing spaces matching the list item indentation.
+    let canonical = "* a\n  \n  b\n\n* c";
+
+    assert_eq!(canonicalize("* a\n\n  b\n* c"), canonical);
+
+    assert_eq!(
+        markdown_to_ast(canonical),
+        vec![Block::List(
+            vec![
+                ListItem(
+                    vec![
+                        Block::plain_text_paragraph("a"),
+                        Block::plain_text_paragraph("b"),
+                    ],
+                    None,
+                ),
+                ListItem::plain_text("c"),
+            ],
+            None,
+            false
+        )]
+    );
+
+    // Every item, including the last, is emitted as part of a loose list.
+    assert_eq!(
+        ast_to_events(&markdown_to_ast(canonical))
+            .iter()
+            .filter(|event| **event == Event::Start(md::Tag::Paragraph))
+            .count(),
+        3
+    );
+
+    assert_roundtrip(canonical);
 }
 
-impl ListItem {
-    /// Construct a list item containing a single inline piece of plain text.
-    pub fn plain_text<S: Into<String>>(inline: S) -> Self {
-        return ListItem(vec![Block::Paragraph(Inlines(vec![Inline::Text(
-            inline.into(),
-        )]))]);
+/// Tests that whether a list is tight or loose is preserved, including for
+/// items whose last block is not a paragraph.
+#[test]
+fn test_tight_list_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        markdown_to_ast("* a\n* b"),
+        vec![Block::List(
+            vec![ListItem::plain_text("a"), ListItem::plain_text("b")],
+            None,
+            true
+        )]
+    );
+    assert_eq!(
+        markdown_to_ast("* a\n\n* b"),
+        vec![Block::List(
+            vec![ListItem::plain_text("a"), ListItem::plain_text("b")],
+            None,
+            false
+        )]
+    );
+
+    for markdown in [
+        "* a\n* b",
+        "* a\n\n* b",
+        "* a\n  ```\n  code\n  ```\n* b",
+        "* # Heading\n* <div>html</div>\n* b",
+        "* a\n  |x|\n  |-|\n* b",
+        // A tight list nested in an item of a loose list, followed by another
+        // block in the item.
+        "* a\n  \n  * b\n  * c\n  \n  d\n\n* e",
+    ] {
+        assert_eq!(canonicalize(markdown), markdown);
+        assert_roundtrip(markdown);
     }
+
+    let ast = markdown_to_ast("* > quote\n* b");
+
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
 }
 
-impl CodeBlockKind {
-    pub fn info_string(&self) -> Option<&str> {
-        match self {
-            CodeBlockKind::Fenced(info_string) => Some(info_string.as_str()),
-            CodeBlockKind::Indented => None,
-        }
-    }
+/// Tests that lists nested inside list items round-trip, with each level of
+/// nesting preserved.
+#[test]
+fn test_nested_list_roundtrip() {
+    use pretty_assertions::assert_eq;
 
-    pub(crate) fn from_pulldown_cmark(kind: md::CodeBlockKind) -> Self {
-        match kind {
-            md::CodeBlockKind::Indented => CodeBlockKind::Indented,
-            md::CodeBlockKind::Fenced(info_string) => {
-                CodeBlockKind::Fenced(info_string.to_string())
-            },
-        }
-    }
+    // Note: The lines containing only whitespace below are written with
+    //       trailing spaces matching the current list item indentation.
+    let canonical = "* a\n  \n  * b\n    \n    * c\n  * d\n\n* e";
 
-    pub(crate) fn to_pulldown_cmark<'s>(&'s self) -> md::CodeBlockKind<'s> {
-        match self {
-            CodeBlockKind::Fenced(info) => {
-                md::CodeBlockKind::Fenced(CowStr::from(info.as_str()))
-            },
-            CodeBlockKind::Indented => md::CodeBlockKind::Indented,
-        }
-    }
+    assert_eq!(
+        markdown_to_ast(canonical),
+        vec![Block::List(
+            vec![
+                ListItem(
+                    vec![
+                        Block::plain_text_paragraph("a"),
+                        Block::List(
+                            vec![
+                                ListItem(
+                                    vec![
+                                        Block::plain_text_paragraph("b"),
+                                        Block::List(
+                                            vec![ListItem::plain_text("c")],
+                                            None,
+                                            true
+                                        ),
+                                    ],
+                                    None,
+                                ),
+                                ListItem::plain_text("d"),
+                            ],
+                            None,
+                            false
+                        ),
+                    ],
+                    None,
+                ),
+                ListItem::plain_text("e"),
+            ],
+            None,
+            false
+        )]
+    );
+
+    // Tight nested lists are written tight.
+    let tight = "* a\n  * b\n    * c\n  * d\n* e";
+
+    assert_eq!(canonicalize(tight), tight);
+    assert_roundtrip(tight);
+
+    assert_roundtrip(canonical);
+
+    //==================================
+    // Mixed ordered and bullet lists
+    //==================================
+
+    assert_roundtrip("* a\n  \n  1. b\n  \n  2. c\n\n* d");
+
+    assert_roundtrip("1. a\n   \n   * b\n2. c");
 }
 
-impl IntoIterator for Inlines {
-    type Item = Inline;
-    type IntoIter = std::vec::IntoIter<Inline>;
+/// Tests that lists indented with tabs are parsed with tabs expanded to the
+/// next multiple of 4 columns, per CommonMark, and written back out indented
+/// with spaces.
+#[test]
+fn test_tab_indented_list_roundtrip() {
+    use pretty_assertions::assert_eq;
 
-    fn into_iter(self) -> Self::IntoIter {
-        let Inlines(vec) = self;
-        vec.into_iter()
-    }
+    let tabs = "* a\n\t* b\n\t\t* c\n\t* d\n* e";
+
+    assert_eq!(
+        markdown_to_ast(tabs),
+        markdown_to_ast("* a\n  * b\n    * c\n  * d\n* e")
+    );
+    assert_eq!(canonicalize(tabs), "* a\n  * b\n    * c\n  * d\n* e");
+
+    // A tab after a list marker, and tab-indented continuation paragraphs.
+    let tabs = "1.\tOne\n\n\tcontinued\n\n\t-\tNested\n\n\t\tmore";
+
+    assert_eq!(
+        markdown_to_ast(tabs),
+        vec![Block::List(
+            vec![ListItem(
+                vec![
+                    Block::plain_text_paragraph("One"),
+                    Block::plain_text_paragraph("continued"),
+                    Block::List(
+                        vec![ListItem(
+                            vec![
+                                Block::plain_text_paragraph("Nested"),
+                                Block::plain_text_paragraph("more"),
+                            ],
+                            None,
+                        )],
+                        None,
+                        false,
+                    ),
+                ],
+                None,
+            )],
+            Some(1),
+            false,
+        )]
+    );
+    assert_eq!(
+        canonicalize(tabs),
+        "1. One\n   \n   continued\n   \n   * Nested\n     \n     more"
+    );
+
+    // The columns of a tab that are not needed to reach the content of a list
+    // item are part of the content, here the indentation of a code block.
+    let ast = markdown_to_ast("- foo\n\n\t\tbar\n");
+
+    assert_eq!(
+        ast,
+        vec![Block::List(
+            vec![ListItem(
+                vec![
+                    Block::plain_text_paragraph("foo"),
+                    Block::CodeBlock {
+                        kind: CodeBlockKind::Indented,
+                        code: "  bar\n".to_owned(),
+                    },
+                ],
+                None,
+            )],
+            None,
+            false,
+        )]
+    );
+    assert_eq!(markdown_to_ast(&ast_to_markdown(&ast)), ast);
 }
 
-//======================================
-// Tests: Markdown to AST parsing
-//======================================
+#[test]
+fn test_ordered_list_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    assert_eq!(
+        markdown_to_ast("3. foo\n4. bar"),
+        vec![Block::List(
+            vec![ListItem::plain_text("foo"), ListItem::plain_text("bar")],
+            Some(3),
+            true
+        )]
+    );
+
+    assert_eq!(canonicalize("3. foo\n4. bar"), "3. foo\n4. bar");
+
+    assert_roundtrip("3. foo\n4. bar");
+
+    assert_roundtrip("1. foo");
+
+    // A list starting at 0 is still an ordered list.
+    assert_eq!(
+        markdown_to_ast("0. foo"),
+        vec![Block::List(
+            vec![ListItem::plain_text("foo")],
+            Some(0),
+            true
+        )]
+    );
+}
 
 #[test]
-fn test_markdown_to_ast() {
+fn test_task_list_roundtrip() {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
+    let mut ast = markdown_to_ast("- [x] done\n- [ ] todo");
+
     assert_eq!(
-        markdown_to_ast("hello"),
-        vec![Block::paragraph(vec![Inline::Text("hello".into())])]
+        ast,
+        vec![Block::List(
+            vec![
+                ListItem(vec![Block::plain_text_paragraph("done")], Some(true)),
+                ListItem(
+                    vec![Block::plain_text_paragraph("todo")],
+                    Some(false)
+                ),
+            ],
+            None,
+            true
+        )]
     );
 
-    //--------------
-    // Styled text
-    //--------------
+    assert_roundtrip("* [x] done\n* [ ] todo");
+    assert_roundtrip("* [x] done\n\n* [ ] todo");
+
+    // Loose list items, and items whose first block is not a paragraph.
+    assert_roundtrip("* [ ] a\n  \n  b\n\n* [x] c");
+
+    //==================================
+    // Toggle the checkbox of each item
+    //==================================
+
+    let Block::List(items, _, _) = &mut ast[0] else {
+        panic!()
+    };
+
+    items[0].set_checked(Some(false));
+    items[1].set_checked(None);
 
+    assert_eq!(ast_to_markdown(&ast), "* [ ] done\n* todo");
+
+    // A task list marker that is not at the start of a list item is text.
     assert_eq!(
-        markdown_to_ast("*hello*"),
-        vec![Block::paragraph(vec![Inline::emphasis(Inline::Text(
-            "hello".into()
-        ))])]
+        ast_to_sexpr(&markdown_to_ast("* [ ] > quote")),
+        indoc!(
+            r#"
+            (list
+              (item
+                (block-quote
+                  (paragraph "[ ] " "quote"))))
+            "#
+        )
+        .trim_start()
+    );
+}
+
+#[test]
+fn test_sort_reference_definitions() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let markdown = indoc!(
+        "
+        See [zeta], [Beta], [alpha], and [zeta] again.
+
+        [zeta]: z.org
+        [Beta]: b.org \"Title\"
+        [alpha]: a.org"
+    );
+
+    let ast = markdown_to_ast(markdown);
+
+    let options = MarkdownOptions {
+        sort_reference_definitions: true,
+        ..MarkdownOptions::default()
+    };
+
+    let sorted = ast_to_markdown_with_options(&ast, &options);
+
+    assert_eq!(
+        sorted,
+        indoc!(
+            "
+            See [zeta], [Beta], [alpha], and [zeta] again.
+
+            [alpha]: a.org
+            [Beta]: b.org \"Title\"
+            [zeta]: z.org"
+        )
     );
 
+    // Sorting doesn't change the parsed document, and is stable.
+    assert_eq!(markdown_to_ast(&sorted), ast);
     assert_eq!(
-        markdown_to_ast("**hello**"),
-        vec![Block::paragraph(vec![Inline::strong(Inline::Text(
-            "hello".into()
-        ))])]
+        ast_to_markdown_with_options(&markdown_to_ast(&sorted), &options),
+        sorted
     );
 
+    // By default, definitions are written in the order of first use.
+    assert_eq!(ast_to_markdown(&markdown_to_ast(&sorted)), markdown);
+}
+
+/// Tests that some of the larger Markdown documents in this repository
+/// all round-trip when processed:
+#[test]
+fn test_md_documents_roundtrip() {
+    let kitchen_sink_md =
+        include_str!("../../md2nb/docs/examples/kitchen-sink.md");
+
+    assert_roundtrip(kitchen_sink_md);
+
+    //==================================
+    // README.md
+    //==================================
+
+    let readme = include_str!("../../../README.md");
+
+    assert_roundtrip(readme);
+}
+
+#[test]
+fn test_markdown_to_ast_iter() {
+    use pretty_assertions::assert_eq;
+
+    let documents = [
+        include_str!("../../md2nb/docs/examples/kitchen-sink.md"),
+        include_str!("../../../README.md"),
+        include_str!("../README.md"),
+    ];
+
+    for document in documents {
+        assert_eq!(
+            markdown_to_ast_iter(document).collect::<Vec<_>>(),
+            markdown_to_ast(document)
+        );
+    }
+}
+
+#[test]
+fn test_ast_event_iter() {
+    use pretty_assertions::assert_eq;
+
+    let documents = [
+        include_str!("../../md2nb/docs/examples/kitchen-sink.md"),
+        include_str!("../../../README.md"),
+        include_str!("../README.md"),
+    ];
+
+    for document in documents {
+        let ast = markdown_to_ast(document);
+
+        assert_eq!(
+            ast_event_iter(&ast).collect::<Vec<_>>(),
+            ast_to_events(&ast)
+        );
+    }
+}
+
+/// Tests that the default [`MarkdownOptions`] reproduce the output of
+/// [`ast_to_markdown()`] exactly.
+#[test]
+fn test_default_markdown_options() {
+    use pretty_assertions::assert_eq;
+
+    let documents = [
+        include_str!("../../md2nb/docs/examples/kitchen-sink.md"),
+        include_str!("../../../README.md"),
+        include_str!("../README.md"),
+    ];
+
+    for document in documents {
+        let ast = markdown_to_ast(document);
+
+        assert_eq!(
+            ast_to_markdown_with_options(&ast, &MarkdownOptions::default()),
+            ast_to_markdown(&ast)
+        );
+    }
+}
+
+#[test]
+fn test_code_block_tab_width_option() {
+    use pretty_assertions::assert_eq;
+
+    let ast = vec![Block::CodeBlock {
+        kind: CodeBlockKind::Fenced("go".to_owned()),
+        code: "\tfoo()\n  \tbar()\nx\t= 1\n".to_owned(),
+    }];
+
+    let options = MarkdownOptions {
+        code_block_tab_width: Some(4),
+        ..MarkdownOptions::default()
+    };
+
     assert_eq!(
-        markdown_to_ast("~~hello~~"),
-        vec![Block::paragraph(vec![Inline::strikethrough(Inline::Text(
-            "hello".into()
-        ))])]
+        ast_to_markdown_with_options(&ast, &options),
+        "\n```go\n    foo()\n    bar()\nx   = 1\n```"
     );
 
+    // Tabs are preserved by default.
     assert_eq!(
-        markdown_to_ast("**`strong code`**"),
-        vec![Block::paragraph(vec![Inline::strong(Inline::Code(
-            "strong code".into()
-        ))])]
+        ast_to_markdown(&ast),
+        "\n```go\n\tfoo()\n  \tbar()\nx\t= 1\n```"
     );
+}
+
+#[test]
+fn test_markdown_options_pad_table_columns() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        | Name | Value | Centered |
+        |:--|--:|:-:|
+        | alpha *x* | 1 | a |
+        | b | `a\\|b` | |"
+    ));
+
+    let options = MarkdownOptions {
+        pad_table_columns: true,
+        ..MarkdownOptions::default()
+    };
+
+    let markdown = ast_to_markdown_with_options(&ast, &options);
 
     assert_eq!(
-        markdown_to_ast("~~`foo`~~"),
-        vec![Block::paragraph(vec![Inline::strikethrough(Inline::Code(
-            "foo".into()
-        ))])]
+        markdown,
+        indoc!(
+            r"
+            | Name      |  Value | Centered |
+            |:----------|-------:|:--------:|
+            | alpha *x* |      1 |    a     |
+            | b         | `a\|b` |          |"
+        )
     );
+    assert_eq!(markdown_to_ast(&markdown), ast);
 
+    // Tables are not padded by default.
     assert_eq!(
-        markdown_to_ast("**[example](example.com)**"),
-        vec![Block::paragraph(vec![Inline::strong(Inline::Link {
-            link_type: md::LinkType::Inline,
-            dest_url: "example.com".into(),
-            title: String::new(),
-            id: String::new(),
-            content_text: Inlines(vec![Inline::Text("example".into())]),
-        })])]
+        ast_to_markdown(&ast),
+        ast_to_markdown_with_options(&ast, &MarkdownOptions::default())
+    );
+    assert!(ast_to_markdown(&ast).starts_with("|Name|Value|Centered|"));
+}
+
+#[test]
+fn test_markdown_options_formatting() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        # Title
+
+        * One
+        * Two
+
+        ```rust
+        fn main() {}
+        ```
+
+        The end."
+    ));
+
+    let options = MarkdownOptions {
+        code_block_token_count: 4,
+        list_token: ListToken::Dash,
+        blank_lines_between_blocks: 2,
+        ..MarkdownOptions::default()
+    };
+
+    let markdown = ast_to_markdown_with_options(&ast, &options);
+
+    assert_eq!(
+        markdown,
+        indoc!(
+            "
+            # Title
+
+
+            - One
+            - Two
+
+
+            ````rust
+            fn main() {}
+            ````
+
+
+            The end."
+        )
     );
 
-    // Test composition of emphasis, strong, strikethrough and code
-    assert_eq!(
-        markdown_to_ast("_~~**`foo`**~~_"),
-        vec![Block::paragraph(vec![Inline::emphasis(
-            Inline::strikethrough(Inline::strong(Inline::Code("foo".into())))
-        )])]
-    );
+    assert_eq!(markdown_to_ast(&markdown), ast);
+}
+
+/// Tests that nested bullet lists written with a `-` list marker are indented
+/// the same way as with the default `*` marker.
+#[test]
+fn test_list_token_option_nested() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        * One
+          * Nested
+            * Deeper
+        * Two
+
+          1. Ordered
+        "
+    ));
+
+    for list_token in [ListToken::Dash, ListToken::Plus] {
+        let options = MarkdownOptions {
+            list_token,
+            ..MarkdownOptions::default()
+        };
+
+        let markdown = ast_to_markdown_with_options(&ast, &options);
+
+        assert_eq!(
+            markdown,
+            indoc!(
+                "
+                * One
+                  
+                  * Nested
+                    * Deeper
+                * Two
+                  
+                  1. Ordered"
+            )
+            .replace('*', &list_token.as_char().to_string())
+        );
+
+        assert_eq!(markdown_to_ast(&markdown), ast);
+    }
+}
+
+#[test]
+fn test_autolink_bare_urls_option() {
+    use pretty_assertions::assert_eq;
+
+    let options = MarkdownOptions {
+        autolink_bare_urls: true,
+        ..MarkdownOptions::default()
+    };
 
-    //--------------
-    // Lists
-    //--------------
+    let write = |markdown: &str| {
+        ast_to_markdown_with_options(&markdown_to_ast(markdown), &options)
+    };
 
     assert_eq!(
-        markdown_to_ast("* hello"),
-        vec![Block::List(vec![ListItem(vec![Block::paragraph(vec![
-            Inline::Text("hello".into())
-        ])])])]
+        write("See https://example.org/a_b?c=1&d=2 for details."),
+        "See <https://example.org/a_b?c=1&d=2> for details."
     );
 
-    // List items with styled text
-
+    // Trailing punctuation and unmatched parentheses are not part of the URL.
     assert_eq!(
-        markdown_to_ast("* *hello*"),
-        vec![Block::List(vec![ListItem(vec![Block::paragraph(vec![
-            Inline::emphasis(Inline::Text("hello".into()))
-        ])])])]
+        write(
+            "(see http://example.org/wiki/Foo_(bar)), or https://example.org."
+        ),
+        "(see <http://example.org/wiki/Foo_(bar)>), or <https://example.org>."
     );
 
+    // Existing links, autolinks, and code spans are unchanged.
+    for markdown in [
+        "[https://example.org](https://example.org)",
+        "<https://example.org>",
+        "`https://example.org`",
+        "notaurl://https",
+    ] {
+        assert_eq!(write(markdown), markdown);
+    }
+
+    // The autolinks parse back as links.
+    let ast = markdown_to_ast(&write("Visit https://example.org today"));
+
     assert_eq!(
-        markdown_to_ast("* **hello**"),
-        vec![Block::List(vec![ListItem(vec![Block::paragraph(vec![
-            Inline::strong(Inline::Text("hello".into()))
-        ])])])]
+        ast,
+        vec![Block::paragraph(vec![
+            Inline::plain_text("Visit "),
+            Inline::Link {
+                link_type: md::LinkType::Autolink,
+                dest_url: "https://example.org".to_owned(),
+                title: String::new(),
+                id: String::new(),
+                content_text: Inlines::plain_text("https://example.org"),
+            },
+            Inline::plain_text(" today"),
+        ])]
     );
 
+    // Bare URLs are left as-is by default.
     assert_eq!(
-        markdown_to_ast("* ~~hello~~"),
-        vec![Block::List(vec![ListItem(vec![Block::paragraph(vec![
-            Inline::strikethrough(Inline::Text("hello".into()),)
-        ])])])]
+        ast_to_markdown(&markdown_to_ast("See https://example.org")),
+        "See https://example.org"
     );
+}
 
-    //----------------------------------
-
-    let input = "\
-* And **bold** text.
-  
-  * With nested list items.
-    
-    * `md2nb` supports nested lists up to three levels deep.
-";
-
-    let ast = vec![Block::List(vec![ListItem(vec![
-        Block::paragraph(vec![
-            Inline::plain_text("And "),
-            Inline::strong(Inline::plain_text("bold")),
-            Inline::plain_text(" text."),
-        ]),
-        Block::List(vec![ListItem(vec![
-            Block::paragraph(vec![Inline::plain_text(
-                "With nested list items.",
-            )]),
-            Block::List(vec![ListItem(vec![Block::paragraph(vec![
-                Inline::code("md2nb"),
-                Inline::plain_text(
-                    " supports nested lists up to three levels deep.",
-                ),
-            ])])]),
-        ])]),
-    ])])];
+#[test]
+fn test_autolink_roundtrip() {
+    use pretty_assertions::assert_eq;
 
-    assert_eq!(markdown_to_ast(input), ast);
+    let link = |link_type, dest_url: &str, text: &str| Inline::Link {
+        link_type,
+        dest_url: dest_url.to_owned(),
+        title: String::new(),
+        id: String::new(),
+        content_text: Inlines::plain_text(text),
+    };
 
-    // Sanity check conversion to event stream.
     assert_eq!(
-        markdown_to_events(input).collect::<Vec<_>>(),
-        ast_to_events(&ast)
+        markdown_to_ast("<https://example.org/a_b> or <foo@example.org>"),
+        vec![Block::paragraph(vec![
+            link(
+                md::LinkType::Autolink,
+                "https://example.org/a_b",
+                "https://example.org/a_b",
+            ),
+            Inline::plain_text(" or "),
+            link(md::LinkType::Email, "foo@example.org", "foo@example.org"),
+        ])]
     );
 
-    //----------------------------------
-    // Test structures
-    //----------------------------------
+    assert_roundtrip("<https://example.org/a_b> or <foo@example.org>");
+    assert_roundtrip("* <mailto:foo.bar+baz@example.org>");
 
+    // Bare URLs are parsed as text, and written back unchanged, without
+    // escaping underscores that cannot begin or end emphasis.
     assert_eq!(
-        markdown_to_ast(indoc!(
-            "
-            * hello
-
-              world
-            "
-        )),
-        vec![Block::List(vec![ListItem(vec![
-            Block::paragraph(vec![Inline::Text("hello".into())]),
-            Block::paragraph(vec![Inline::Text("world".into())])
-        ])])]
+        markdown_to_ast("See https://example.org/a_b_c."),
+        vec![Block::plain_text_paragraph(
+            "See https://example.org/a_b_c."
+        )]
     );
 
-    #[rustfmt::skip]
-    assert_eq!(
-        markdown_to_ast(indoc!(
-            "
-            # Example
+    assert_roundtrip("See https://example.org/a_b_c.");
+    assert_roundtrip("(https://example.org/wiki/Foo_bar)");
 
-            * A
-              - A.A
+    // Underscores outside of URLs are still escaped.
+    assert_eq!(
+        ast_to_markdown(&markdown_to_ast(r"snake\_case and http://x.org/a\_")),
+        r"snake\_case and http://x.org/a\_"
+    );
+}
 
-                hello world
+/// Tests that `tests/fixtures/every-variant.md` contains at least one example
+/// of every [`Block`] and [`Inline`] variant.
+///
+/// Downstream crates use this fixture to check that they can handle every
+/// kind of Markdown content. When adding a new variant, the `match`es below
+/// will fail to compile until the variant is handled here, and this test will
+/// fail until an example of the new variant is added to the fixture.
+#[test]
+fn test_every_variant_fixture() {
+    use std::collections::BTreeSet;
 
-                * *A.A.A*
-            "
-        )),
-        vec![
-            Block::Heading(
-                HeadingLevel::H1,
-                Inlines(vec![Inline::Text("Example".into())])
-            ),
-            Block::List(vec![
-                ListItem(vec![
-                    Block::paragraph(vec![Inline::Text("A".into())]),
-                    Block::List(vec![
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.A".into())]),
-                            Block::paragraph(vec![Inline::Text("hello world".into())]),
-                            Block::List(vec![
-                                ListItem(vec![
-                                    Block::paragraph(vec![
-                                        Inline::emphasis(
-                                            Inline::Text(
-                                            "A.A.A".into()),
-                                        )
-                                    ])
-                                ])
-                            ])
-                        ])
-                    ])
-                ])
-            ])
-        ]
-    );
+    fn visit_block(block: &Block, seen: &mut BTreeSet<&'static str>) {
+        let name = match block {
+            Block::Paragraph(inlines) => {
+                visit_inlines(inlines, seen);
+                "Paragraph"
+            },
+            Block::List(items, start, _) => {
+                if start.is_some() {
+                    seen.insert("List(ordered)");
+                }
 
-    #[rustfmt::skip]
-    assert_eq!(
-        markdown_to_ast(indoc!(
-            "
-            * A
-              - A.A
-                * A.A.A
-              - A.B
-              - A.C
-            "
-        )),
-        vec![
-            Block::List(vec![
-                ListItem(vec![
-                    Block::paragraph(vec![Inline::Text("A".into())]),
-                    Block::List(vec![
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.A".into())]),
-                            Block::List(vec![ListItem(vec![
-                                Block::paragraph(vec![Inline::Text("A.A.A".into())]),
-                            ])])
-                        ]),
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.B".into())]),
-                        ]),
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.C".into())]),
-                        ])
-                    ])
-                ])
-            ])
-        ]
-    );
+                for ListItem(blocks, checked) in items {
+                    if checked.is_some() {
+                        seen.insert("ListItem(checked)");
+                    }
 
-    #[rustfmt::skip]
-    assert_eq!(
-        markdown_to_ast(indoc!(
-            "
-            # Example
+                    blocks.iter().for_each(|block| visit_block(block, seen));
+                }
+                "List"
+            },
+            Block::Heading(_, inlines, attributes) => {
+                if *attributes != HeadingAttributes::default() {
+                    seen.insert("Heading(attributes)");
+                }
 
-            * A
-              - A.A
-              - A.B
-              * A.C
-            "
-        )),
-        vec![
-            Block::Heading(
-                HeadingLevel::H1,
-                Inlines(vec![Inline::Text("Example".into())])
-            ),
-            Block::List(vec![
-                ListItem(vec![
-                    Block::paragraph(vec![Inline::Text("A".into())]),
-                    Block::List(vec![
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.A".into())]),
-                        ]),
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.B".into())]),
-                        ]),
-                    ]),
-                    Block::List(vec![
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.C".into())])
-                        ])
-                    ]),
-                ]),
-            ])
-        ]
-    );
+                visit_inlines(inlines, seen);
+                "Heading"
+            },
+            Block::CodeBlock { kind, code: _ } => match kind {
+                CodeBlockKind::Fenced(_) => "CodeBlock(Fenced)",
+                CodeBlockKind::Indented => "CodeBlock(Indented)",
+            },
+            Block::BlockQuote { kind, blocks } => {
+                blocks.iter().for_each(|block| visit_block(block, seen));
+                match kind {
+                    Some(_) => "BlockQuote(Alert)",
+                    None => "BlockQuote",
+                }
+            },
+            Block::Table {
+                alignments: _,
+                headers,
+                rows,
+            } => {
+                for cell in std::iter::once(headers).chain(rows).flatten() {
+                    visit_inlines(cell, seen);
+                }
+                "Table"
+            },
+            Block::Rule => "Rule",
+            Block::Html(_) => "Html",
+            Block::Metadata { .. } => "Metadata",
+            Block::FootnoteDefinition { label: _, blocks } => {
+                blocks.iter().for_each(|block| visit_block(block, seen));
+                "FootnoteDefinition"
+            },
+        };
 
-    #[rustfmt::skip]
-    assert_eq!(
-        markdown_to_ast(indoc!(
-            "
-            * A
-              - A.A
-              - A.B
+        seen.insert(name);
+    }
 
-                separate paragraph
+    fn visit_inlines(
+        Inlines(inlines): &Inlines,
+        seen: &mut BTreeSet<&'static str>,
+    ) {
+        for inline in inlines {
+            let name = match inline {
+                Inline::Text(_) => "Text",
+                Inline::Emphasis(children) => {
+                    visit_inlines(children, seen);
+                    "Emphasis"
+                },
+                Inline::Strong(children) => {
+                    visit_inlines(children, seen);
+                    "Strong"
+                },
+                Inline::Strikethrough(children) => {
+                    visit_inlines(children, seen);
+                    "Strikethrough"
+                },
+                Inline::Code(_) => "Code",
+                Inline::Link { content_text, .. } => {
+                    visit_inlines(content_text, seen);
+                    "Link"
+                },
+                Inline::Image {
+                    image_description, ..
+                } => {
+                    visit_inlines(image_description, seen);
+                    "Image"
+                },
+                Inline::SoftBreak => "SoftBreak",
+                Inline::HardBreak => "HardBreak",
+                Inline::FootnoteReference(_) => "FootnoteReference",
+                Inline::InlineMath(_) => "InlineMath",
+                Inline::DisplayMath(_) => "DisplayMath",
+                Inline::InlineHtml(_) => "InlineHtml",
+                Inline::Positioned(_, inline) => {
+                    visit_inlines(&Inlines(vec![(**inline).clone()]), seen);
+                    "Positioned"
+                },
+            };
 
-              - A.C
-            "
-        )),
-        vec![
-            Block::List(vec![
-                ListItem(vec![
-                    Block::paragraph(vec![Inline::Text("A".into())]),
-                    Block::List(vec![
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.A".into())]),
-                        ]),
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.B".into())]),
-                            Block::paragraph(vec![Inline::Text("separate paragraph".into())]),
-                        ]),
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.C".into())]),
-                        ])
-                    ])
-                ])
-            ])
-        ]
-    );
+            seen.insert(name);
+        }
+    }
 
-    #[rustfmt::skip]
-    assert_eq!(
-        markdown_to_ast(indoc!(
-            "
-            # Example
+    let input = include_str!("../tests/fixtures/every-variant.md");
 
-            * A
-              - A.A
-                * A.A.A
-                  **soft break**
+    let mut seen = BTreeSet::new();
 
-              - A.B
+    for block in &markdown_to_ast(input) {
+        visit_block(block, &mut seen);
+    }
 
-                separate paragraph
+    // Inline::Positioned is only produced when parsing with positions.
+    for block in &markdown_to_ast_with_positions(input) {
+        visit_block(block, &mut seen);
+    }
 
-              - A.C
-            "
-        )),
-        vec![
-            Block::Heading(
-                HeadingLevel::H1,
-                Inlines(vec![Inline::Text("Example".into())])
-            ),
-            Block::List(vec![
-                ListItem(vec![
-                    Block::paragraph(vec![Inline::Text("A".into())]),
-                    Block::List(vec![
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.A".into())]),
-                            Block::List(vec![
-                                ListItem(vec![
-                                    Block::paragraph(vec![
-                                        Inline::Text("A.A.A".into()),
-                                        Inline::SoftBreak,
-                                        Inline::strong(
-                                            Inline::Text("soft break".into()),
-                                        )
-                                    ]),
-                                ])
-                            ]),
-                        ]),
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.B".into())]),
-                            Block::paragraph(vec![Inline::Text("separate paragraph".into())]),
-                        ]),
-                        ListItem(vec![
-                            Block::paragraph(vec![Inline::Text("A.C".into())]),
-                        ]),
-                    ])
-                ])
-            ])
-        ]
+    assert_eq!(
+        seen,
+        BTreeSet::from([
+            "Paragraph",
+            "List",
+            "List(ordered)",
+            "ListItem(checked)",
+            "Heading",
+            "Heading(attributes)",
+            "CodeBlock(Fenced)",
+            "CodeBlock(Indented)",
+            "BlockQuote",
+            "BlockQuote(Alert)",
+            "Table",
+            "Rule",
+            "FootnoteDefinition",
+            "Html",
+            "Metadata",
+            "Text",
+            "Emphasis",
+            "Strong",
+            "Strikethrough",
+            "Code",
+            "Link",
+            "Image",
+            "SoftBreak",
+            "HardBreak",
+            "FootnoteReference",
+            "InlineMath",
+            "DisplayMath",
+            "InlineHtml",
+            "Positioned",
+        ])
     );
 }
 
-//======================================
-// Tests: AST to Markdown string
-//======================================
-
+#[cfg(feature = "serde")]
 #[test]
-fn test_ast_to_markdown() {
+fn test_serde_json_roundtrip() {
     use indoc::indoc;
-    // use pretty_assertions::assert_eq;
+    use pretty_assertions::assert_eq;
 
-    assert_eq!(
-        ast_to_markdown(&[Block::paragraph(vec![Inline::Text(
-            "hello".into()
-        )])]),
-        "hello"
-    );
+    let ast = markdown_to_ast(indoc!(
+        r#"
+        # Title {#top}
 
-    assert_eq!(
-        ast_to_markdown(&[Block::List(vec![ListItem(vec![
-            Block::paragraph(vec![Inline::Text("hello".into())]),
-            Block::paragraph(vec![Inline::Text("world".into())])
-        ])])]),
-        indoc!(
-            "
-            * hello
-              
-              world"
-        ),
-    )
-}
+        * A [link](example.org "Example")
+        * [ ] A task
 
-/// Tests that some of the larger Markdown documents in this repository
-/// all round-trip when processed:
-#[test]
-fn test_md_documents_roundtrip() {
-    let kitchen_sink_md =
-        include_str!("../../md2nb/docs/examples/kitchen-sink.md");
+        | Name | Count |
+        |:-----|------:|
+        | `a`  | **1** |
+        "#
+    ));
 
-    // FIXME:
-    //  Fix the bugs requiring these hacky removals from kitchen-sink.md
-    //  that are needed to make the tests below pass.
-    let kitchen_sink_md = kitchen_sink_md
-        .replace("\n    \"This is an indented code block.\"\n", "")
-        .replace("\nThis is a [shortcut] reference link.\n", "")
-        .replace("\nThis is a [full reference][full reference] link.\n", "")
-        .replace("\n[full reference]: https://example.org\n", "")
-        .replace("[shortcut]: https://example.org\n", "");
+    let json = serde_json::to_string(&ast).unwrap();
 
-    assert_roundtrip(&kitchen_sink_md);
+    let deserialized: Vec<Block> = serde_json::from_str(&json).unwrap();
 
-    //==================================
-    // README.md
-    //==================================
+    assert_eq!(deserialized, ast);
 
-    let readme = include_str!("../../../README.md");
+    // Positions are serialized too.
+    let ast = markdown_to_ast_with_positions("Some *text*");
 
-    assert_roundtrip(readme);
+    let json = serde_json::to_string(&ast).unwrap();
+
+    assert_eq!(serde_json::from_str::<Vec<Block>>(&json).unwrap(), ast);
 }
 
 #[cfg(test)]
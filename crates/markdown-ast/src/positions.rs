@@ -0,0 +1,433 @@
+//! Parse Markdown into an AST whose blocks or inlines are annotated with their
+//! position in the source.
+
+use std::{iter::Peekable, ops::Range};
+
+use pulldown_cmark::{self as md, CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    events_to_ast, markdown_to_ast,
+    visit::{walk_blocks_mut, walk_inline_mut, VisitorMut},
+    Block, Inline,
+};
+
+/// A line and column position in a Markdown input string.
+///
+/// Both `line` and `column` are 1-based. `column` counts characters, not bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl LineCol {
+    /// Returns the position of the byte `offset` in `input`.
+    pub(crate) fn from_offset(input: &str, offset: usize) -> LineCol {
+        line_col(input, &line_offsets(input), offset)
+    }
+}
+
+/// Parse Markdown input string into AST [`Block`]s, with every [`Inline`]
+/// wrapped in an [`Inline::Positioned`] recording where it starts in `input`.
+///
+/// Aside from the position annotations, the AST is the same as that returned
+/// by [`markdown_to_ast()`].
+///
+/// Positions are computed from the byte offsets reported by
+/// [`pulldown_cmark::Parser::into_offset_iter()`]. An inline that has no
+/// single corresponding parser event, e.g. the [`Inline::HardBreak`]s that
+/// separate the paragraphs of a loose list item, is left unwrapped.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{
+///     markdown_to_ast_with_positions, Block, Inline, Inlines, LineCol
+/// };
+///
+/// let ast = markdown_to_ast_with_positions("Some *text*");
+///
+/// let at = |line, column, inline| {
+///     Inline::Positioned(LineCol { line, column }, Box::new(inline))
+/// };
+///
+/// assert_eq!(
+///     ast,
+///     vec![Block::paragraph(vec![
+///         at(1, 1, Inline::plain_text("Some ")),
+///         at(1, 6, Inline::Emphasis(Inlines(vec![
+///             at(1, 7, Inline::plain_text("text")),
+///         ]))),
+///     ])]
+/// );
+/// ```
+pub fn markdown_to_ast_with_positions(input: &str) -> Vec<Block> {
+    let mut blocks = markdown_to_ast(input);
+
+    let mut starts = InlineStarts {
+        input,
+        line_offsets: line_offsets(input),
+        starts: inline_start_events(input).into_iter().peekable(),
+    };
+
+    walk_blocks_mut(&mut starts, &mut blocks);
+
+    blocks
+}
+
+/// Parse Markdown input string into top-level AST [`Block`]s, each paired with
+/// the range of bytes in `input` that it was parsed from.
+///
+/// The blocks are the same as those returned by [`markdown_to_ast()`]. Use
+/// [`LineCol`] positions from [`markdown_to_ast_with_positions()`] to locate
+/// individual inlines instead.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{markdown_to_ast_with_offsets, Block};
+///
+/// let input = "# Title\n\nSome text.\n";
+///
+/// let blocks = markdown_to_ast_with_offsets(input);
+///
+/// assert_eq!(blocks[1].0, Block::plain_text_paragraph("Some text."));
+/// assert_eq!(&input[blocks[1].1.clone()], "Some text.\n");
+/// ```
+pub fn markdown_to_ast_with_offsets(input: &str) -> Vec<(Block, Range<usize>)> {
+    let parser = md::Parser::new_ext(input, crate::parser_options());
+
+    let mut blocks: Vec<(Block, Range<usize>)> = Vec::new();
+
+    // The events of the current top-level block, and its range in `input`.
+    let mut block_events: Vec<Event> = Vec::new();
+    let mut block_range: Range<usize> = 0..0;
+    let mut depth: usize = 0;
+
+    for (event, range) in parser.into_offset_iter() {
+        // The range of a start event spans the entire element it starts.
+        if depth == 0 {
+            block_range = range;
+        }
+
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => (),
+        }
+
+        block_events.push(event);
+
+        if depth == 0 {
+            let events = md::TextMergeStream::new(block_events.drain(..));
+
+            for block in events_to_ast(events) {
+                blocks.push((block, block_range.clone()));
+            }
+        }
+    }
+
+    blocks
+}
+
+//======================================
+// Implementation
+//======================================
+
+/// The events that start an inline, in document order, paired with the byte
+/// offset in the input where the inline starts.
+struct InlineStarts<'i> {
+    input: &'i str,
+    /// The byte offset of the start of each line of `input`.
+    line_offsets: Vec<usize>,
+    starts: Peekable<std::vec::IntoIter<(Event<'i>, usize)>>,
+}
+
+impl<'i> InlineStarts<'i> {
+    /// Returns the position of the event that produced `inline`, or `None` if
+    /// `inline` has no corresponding event.
+    fn next_position(&mut self, inline: &Inline) -> Option<LineCol> {
+        loop {
+            let (event, offset) = self.starts.peek()?;
+
+            if produces(event, inline) {
+                let offset = *offset;
+                self.starts.next();
+                return Some(self.line_col(offset));
+            }
+
+            // Line breaks and task list markers may be dropped while building
+            // the AST, so they do not necessarily produce an inline.
+            match event {
+                Event::SoftBreak
+                | Event::HardBreak
+                | Event::TaskListMarker(_) => {
+                    self.starts.next();
+                },
+                _ => return None,
+            }
+        }
+    }
+
+    fn line_col(&self, offset: usize) -> LineCol {
+        line_col(self.input, &self.line_offsets, offset)
+    }
+}
+
+/// Returns the position of the byte `offset` in `input`, whose lines start at
+/// `line_offsets`.
+fn line_col(input: &str, line_offsets: &[usize], offset: usize) -> LineCol {
+    let line =
+        line_offsets.partition_point(|line_offset| *line_offset <= offset);
+
+    let line_offset = line_offsets[line - 1];
+
+    LineCol {
+        line,
+        column: input[line_offset..offset].chars().count() + 1,
+    }
+}
+
+fn line_offsets(input: &str) -> Vec<usize> {
+    let newlines = input.match_indices('\n').map(|(offset, _)| offset + 1);
+
+    std::iter::once(0).chain(newlines).collect()
+}
+
+/// Parse `input` into the events that start an inline, mirroring the events
+/// that [`markdown_to_ast()`] builds inlines from.
+fn inline_start_events(input: &str) -> Vec<(Event<'_>, usize)> {
+    let parser = md::Parser::new_ext(input, crate::parser_options());
+
+    let mut starts: Vec<(Event, usize)> = Vec::new();
+
+    // The nesting depth of blocks whose content is raw text, not inlines.
+    let mut raw_depth: usize = 0;
+
+    let mut previous_was_text = false;
+
+    for (event, range) in parser.into_offset_iter() {
+        let is_text = matches!(event, Event::Text(_));
+
+        match event {
+            Event::Start(
+                Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_),
+            ) => raw_depth += 1,
+            Event::End(
+                TagEnd::CodeBlock
+                | TagEnd::HtmlBlock
+                | TagEnd::MetadataBlock(_),
+            ) => raw_depth -= 1,
+            _ if raw_depth > 0 => (),
+            // Consecutive text events are merged into a single
+            // Inline::Text, like markdown_to_events() does.
+            Event::Text(text) if previous_was_text => {
+                if let Some((Event::Text(previous), _)) = starts.last_mut() {
+                    *previous = CowStr::from(format!("{previous}{text}"));
+                }
+            },
+            Event::Start(
+                Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Link { .. }
+                | Tag::Image { .. },
+            )
+            | Event::Text(_)
+            | Event::Code(_)
+            | Event::InlineHtml(_)
+            | Event::FootnoteReference(_)
+            | Event::InlineMath(_)
+            | Event::DisplayMath(_)
+            | Event::SoftBreak
+            | Event::HardBreak
+            | Event::TaskListMarker(_) => starts.push((event, range.start)),
+            // Other events belong to blocks, not inlines.
+            _ => (),
+        }
+
+        previous_was_text = is_text;
+    }
+
+    starts
+}
+
+/// Returns `true` if `inline` could have been built from `event`.
+fn produces(event: &Event, inline: &Inline) -> bool {
+    match (event, inline) {
+        (Event::Start(Tag::Emphasis), Inline::Emphasis(_))
+        | (Event::Start(Tag::Strong), Inline::Strong(_))
+        | (Event::Start(Tag::Strikethrough), Inline::Strikethrough(_))
+        | (Event::Start(Tag::Link { .. }), Inline::Link { .. })
+        | (Event::Start(Tag::Image { .. }), Inline::Image { .. })
+        | (Event::SoftBreak, Inline::SoftBreak)
+        | (Event::HardBreak, Inline::HardBreak) => true,
+        // A `<br>` tag in a table cell is parsed as a hard break.
+        (Event::InlineHtml(_), Inline::HardBreak) => true,
+        (Event::Text(text), Inline::Text(inline_text)) => {
+            **text == **inline_text
+        },
+        (Event::TaskListMarker(checked), Inline::Text(inline_text)) => {
+            let marker = if *checked { "[x] " } else { "[ ] " };
+
+            marker == inline_text
+        },
+        (Event::Code(code), Inline::Code(inline_code)) => {
+            **code == **inline_code
+        },
+        (Event::InlineHtml(html), Inline::InlineHtml(inline_html)) => {
+            **html == **inline_html
+        },
+        (
+            Event::FootnoteReference(label),
+            Inline::FootnoteReference(inline_label),
+        ) => **label == **inline_label,
+        (Event::InlineMath(math), Inline::InlineMath(inline_math))
+        | (Event::DisplayMath(math), Inline::DisplayMath(inline_math)) => {
+            **math == **inline_math
+        },
+        _ => false,
+    }
+}
+
+impl VisitorMut for InlineStarts<'_> {
+    fn visit_inline_mut(&mut self, inline: &mut Inline) {
+        // The event that starts an inline precedes the events of its children.
+        let position = self.next_position(inline);
+
+        walk_inline_mut(self, inline);
+
+        if let Some(position) = position {
+            let unpositioned = std::mem::replace(inline, Inline::SoftBreak);
+
+            *inline = Inline::Positioned(position, Box::new(unpositioned));
+        }
+    }
+}
+
+//======================================
+// Tests
+//======================================
+
+#[test]
+fn test_markdown_to_ast_with_positions() {
+    use crate::Inlines;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let at = |line, column, inline| {
+        Inline::Positioned(LineCol { line, column }, Box::new(inline))
+    };
+
+    let ast = markdown_to_ast_with_positions(indoc!(
+        "
+        # Title
+
+        Naïve *emphasis*,
+        then more text.
+        "
+    ));
+
+    assert_eq!(
+        ast,
+        vec![
+            Block::Heading(
+                md::HeadingLevel::H1,
+                Inlines(vec![at(1, 3, Inline::plain_text("Title"))]),
+                Default::default(),
+            ),
+            Block::paragraph(vec![
+                at(3, 1, Inline::plain_text("Naïve ")),
+                at(
+                    3,
+                    7,
+                    Inline::emphasis(at(3, 8, Inline::plain_text("emphasis"))),
+                ),
+                at(3, 17, Inline::plain_text(",")),
+                at(3, 18, Inline::SoftBreak),
+                at(4, 1, Inline::plain_text("then more text.")),
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn test_markdown_to_ast_with_positions_documents() {
+    use crate::ast_to_markdown;
+    use pretty_assertions::assert_eq;
+
+    /// Returns the number of positioned inlines and the total number of
+    /// other inlines, which are equal if every inline has a position.
+    fn count_positioned(blocks: &[Block]) -> (usize, usize) {
+        let (mut positioned, mut total) = (0, 0);
+
+        crate::visit_inlines_mut(&mut blocks.to_vec(), &mut |inline| {
+            match inline {
+                Inline::Positioned(_, _) => positioned += 1,
+                _ => total += 1,
+            }
+        });
+
+        (positioned, total)
+    }
+
+    let documents = [
+        include_str!("../tests/fixtures/every-variant.md"),
+        include_str!("../../md2nb/docs/examples/kitchen-sink.md"),
+        include_str!("../../../README.md"),
+    ];
+
+    for document in documents {
+        let ast = markdown_to_ast_with_positions(document);
+
+        let (positioned, total) = count_positioned(&ast);
+        assert_eq!(positioned, total);
+
+        assert_eq!(
+            ast_to_markdown(&ast),
+            ast_to_markdown(&markdown_to_ast(document))
+        );
+
+        let blocks: Vec<Block> = markdown_to_ast_with_offsets(document)
+            .into_iter()
+            .map(|(block, _)| block)
+            .collect();
+
+        assert_eq!(blocks, markdown_to_ast(document));
+    }
+}
+
+#[test]
+fn test_markdown_to_ast_with_offsets() {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let input = indoc!(
+        "
+        First paragraph.
+
+        Second
+        paragraph.
+        "
+    );
+
+    let blocks = markdown_to_ast_with_offsets(input);
+
+    assert_eq!(
+        blocks
+            .iter()
+            .map(|(block, _)| block.clone())
+            .collect::<Vec<_>>(),
+        markdown_to_ast(input)
+    );
+
+    let (_, range) = &blocks[1];
+
+    assert_eq!(*range, 18..36);
+    assert_eq!(&input[range.clone()], "Second\nparagraph.\n");
+    assert_eq!(
+        LineCol::from_offset(input, range.start),
+        LineCol { line: 3, column: 1 }
+    );
+}
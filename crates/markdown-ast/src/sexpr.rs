@@ -0,0 +1,360 @@
+//! Compact S-expression dump of a Markdown AST, for golden tests.
+
+use std::fmt::Write;
+
+use pulldown_cmark::{self as md, LinkType};
+
+use crate::{
+    Block, CodeBlockKind, HeadingAttributes, Inline, Inlines, LineCol, ListItem,
+};
+
+/// Format `blocks` as an S-expression, with each [`Block`] on its own line.
+///
+/// Nested blocks are written on separate lines, indented by two spaces per
+/// level of nesting. Inline content is written on the same line as the block
+/// that contains it. Strings are quoted and escaped like Rust string literals.
+///
+/// The output is intended to be stable and diff-friendly, for use in golden
+/// tests.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{ast_to_sexpr, markdown_to_ast};
+///
+/// let ast = markdown_to_ast("# Title\n\n* One\n* **Two**\n\n> A quote");
+///
+/// assert_eq!(ast_to_sexpr(&ast), r#"(heading 1 "Title")
+/// (list
+///   (item
+///     (paragraph "One"))
+///   (item
+///     (paragraph (strong "Two"))))
+/// (block-quote
+///   (paragraph "A quote"))
+/// "#);
+/// ```
+pub fn ast_to_sexpr(blocks: &[Block]) -> String {
+    let mut sexpr = String::new();
+
+    for block in blocks {
+        write_block(&mut sexpr, block, 0);
+        sexpr.push('\n');
+    }
+
+    sexpr
+}
+
+fn write_block(out: &mut String, block: &Block, depth: usize) {
+    out.push_str(&"  ".repeat(depth));
+
+    match block {
+        Block::Paragraph(inlines) => {
+            out.push_str("(paragraph");
+            write_inlines(out, inlines);
+        },
+        Block::List(items, start, tight) => {
+            out.push_str("(list");
+
+            if let Some(start) = start {
+                write!(out, " (start {start})").unwrap();
+            }
+
+            if !tight {
+                out.push_str(" loose");
+            }
+
+            for ListItem(blocks, checked) in items {
+                out.push('\n');
+                out.push_str(&"  ".repeat(depth + 1));
+                out.push_str("(item");
+
+                match checked {
+                    Some(true) => out.push_str(" checked"),
+                    Some(false) => out.push_str(" unchecked"),
+                    None => (),
+                }
+
+                write_child_blocks(out, blocks, depth + 2);
+                out.push(')');
+            }
+        },
+        Block::Heading(level, inlines, attributes) => {
+            write!(out, "(heading {}", *level as usize).unwrap();
+
+            let HeadingAttributes { id, classes, attrs } = attributes;
+
+            if let Some(id) = id {
+                write!(out, " (id {id:?})").unwrap();
+            }
+
+            for class in classes {
+                write!(out, " (class {class:?})").unwrap();
+            }
+
+            for (key, value) in attrs {
+                match value {
+                    Some(value) => {
+                        write!(out, " (attr {key:?} {value:?})").unwrap()
+                    },
+                    None => write!(out, " (attr {key:?})").unwrap(),
+                }
+            }
+
+            write_inlines(out, inlines);
+        },
+        Block::CodeBlock { kind, code } => {
+            out.push_str("(code-block ");
+
+            match kind {
+                CodeBlockKind::Fenced(info_string) => {
+                    write!(out, "fenced {info_string:?}").unwrap()
+                },
+                CodeBlockKind::Indented => out.push_str("indented"),
+            }
+
+            write!(out, " {code:?}").unwrap();
+        },
+        Block::BlockQuote { kind, blocks } => {
+            out.push_str("(block-quote");
+
+            if let Some(kind) = kind {
+                let kind = match kind {
+                    md::BlockQuoteKind::Note => "note",
+                    md::BlockQuoteKind::Tip => "tip",
+                    md::BlockQuoteKind::Important => "important",
+                    md::BlockQuoteKind::Warning => "warning",
+                    md::BlockQuoteKind::Caution => "caution",
+                };
+                write!(out, " {kind}").unwrap();
+            }
+
+            write_child_blocks(out, blocks, depth + 1);
+        },
+        Block::Table {
+            alignments,
+            headers,
+            rows,
+        } => {
+            out.push_str("(table (alignments");
+
+            for alignment in alignments {
+                let alignment = match alignment {
+                    md::Alignment::None => "none",
+                    md::Alignment::Left => "left",
+                    md::Alignment::Center => "center",
+                    md::Alignment::Right => "right",
+                };
+                write!(out, " {alignment}").unwrap();
+            }
+
+            out.push(')');
+
+            let rows = std::iter::once(("header", headers))
+                .chain(rows.iter().map(|row| ("row", row)));
+
+            for (name, cells) in rows {
+                out.push('\n');
+                out.push_str(&"  ".repeat(depth + 1));
+                write!(out, "({name}").unwrap();
+
+                for cell in cells {
+                    out.push_str(" (cell");
+                    write_inlines(out, cell);
+                    out.push(')');
+                }
+
+                out.push(')');
+            }
+        },
+        Block::Rule => out.push_str("(rule"),
+        Block::FootnoteDefinition { label, blocks } => {
+            write!(out, "(footnote-definition {label:?}").unwrap();
+            write_child_blocks(out, blocks, depth + 1);
+        },
+        Block::Html(html) => write!(out, "(html {html:?}").unwrap(),
+        Block::Metadata { kind, content } => {
+            let kind = match kind {
+                md::MetadataBlockKind::YamlStyle => "yaml",
+                md::MetadataBlockKind::PlusesStyle => "pluses",
+            };
+
+            write!(out, "(metadata {kind} {content:?}").unwrap();
+        },
+    }
+
+    out.push(')');
+}
+
+fn write_child_blocks(out: &mut String, blocks: &[Block], depth: usize) {
+    for block in blocks {
+        out.push('\n');
+        write_block(out, block, depth);
+    }
+}
+
+/// Write each of `inlines`, preceded by a space.
+fn write_inlines(out: &mut String, Inlines(inlines): &Inlines) {
+    for inline in inlines {
+        out.push(' ');
+        write_inline(out, inline);
+    }
+}
+
+fn write_inline(out: &mut String, inline: &Inline) {
+    match inline {
+        Inline::Text(text) => write!(out, "{text:?}").unwrap(),
+        Inline::Emphasis(inlines) => {
+            out.push_str("(emphasis");
+            write_inlines(out, inlines);
+            out.push(')');
+        },
+        Inline::Strong(inlines) => {
+            out.push_str("(strong");
+            write_inlines(out, inlines);
+            out.push(')');
+        },
+        Inline::Strikethrough(inlines) => {
+            out.push_str("(strikethrough");
+            write_inlines(out, inlines);
+            out.push(')');
+        },
+        Inline::Code(code) => write!(out, "(code {code:?})").unwrap(),
+        Inline::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+            content_text,
+        } => {
+            out.push_str("(link");
+            write_link_fields(out, *link_type, dest_url, title, id);
+            write_inlines(out, content_text);
+            out.push(')');
+        },
+        Inline::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+            image_description,
+        } => {
+            out.push_str("(image");
+            write_link_fields(out, *link_type, dest_url, title, id);
+            write_inlines(out, image_description);
+            out.push(')');
+        },
+        Inline::SoftBreak => out.push_str("soft-break"),
+        Inline::HardBreak => out.push_str("hard-break"),
+        Inline::FootnoteReference(label) => {
+            write!(out, "(footnote-reference {label:?})").unwrap()
+        },
+        Inline::InlineMath(math) => {
+            write!(out, "(inline-math {math:?})").unwrap()
+        },
+        Inline::DisplayMath(math) => {
+            write!(out, "(display-math {math:?})").unwrap()
+        },
+        Inline::InlineHtml(html) => {
+            write!(out, "(inline-html {html:?})").unwrap()
+        },
+        Inline::Positioned(LineCol { line, column }, inline) => {
+            write!(out, "(at {line}:{column} ").unwrap();
+            write_inline(out, inline);
+            out.push(')');
+        },
+    }
+}
+
+/// Write the link type and destination of a link or image, followed by its
+/// title and id, if they are not empty.
+fn write_link_fields(
+    out: &mut String,
+    link_type: LinkType,
+    dest_url: &str,
+    title: &str,
+    id: &str,
+) {
+    let link_type = match link_type {
+        LinkType::Inline => "inline",
+        LinkType::Reference => "reference",
+        LinkType::ReferenceUnknown => "reference-unknown",
+        LinkType::Collapsed => "collapsed",
+        LinkType::CollapsedUnknown => "collapsed-unknown",
+        LinkType::Shortcut => "shortcut",
+        LinkType::ShortcutUnknown => "shortcut-unknown",
+        LinkType::Autolink => "autolink",
+        LinkType::Email => "email",
+    };
+
+    write!(out, " {link_type} {dest_url:?}").unwrap();
+
+    if !title.is_empty() {
+        write!(out, " (title {title:?})").unwrap();
+    }
+
+    if !id.is_empty() {
+        write!(out, " (id {id:?})").unwrap();
+    }
+}
+
+//======================================
+// Tests
+//======================================
+
+#[test]
+fn test_ast_to_sexpr() {
+    use crate::markdown_to_ast;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        r#"
+        # Title {#top .title}
+
+        Some *emphasis*, `code`, and a [link](example.org "Example").
+        A second line with ![an image][img].
+
+        > [!NOTE]
+        > A note.
+        >
+        > * One
+        >
+        >   ```rust
+        >   fn main() {}
+        >   ```
+
+        | Name | Website |
+        |:-----|--------:|
+        | Foo  | ~~foo~~ |
+
+        ---
+
+            indented "code"
+
+        [img]: photo.png
+        "#
+    ));
+
+    assert_eq!(
+        ast_to_sexpr(&ast),
+        indoc!(
+            r#"
+            (heading 1 (id "top") (class "title") "Title")
+            (paragraph "Some " (emphasis "emphasis") ", " (code "code") ", and a " (link inline "example.org" (title "Example") "link") "." soft-break "A second line with " (image reference "photo.png" (id "img") "an image") ".")
+            (block-quote note
+              (paragraph "A note.")
+              (list loose
+                (item
+                  (paragraph "One")
+                  (code-block fenced "rust" "fn main() {}\n"))))
+            (table (alignments left right)
+              (header (cell "Name") (cell "Website"))
+              (row (cell "Foo") (cell (strikethrough "foo"))))
+            (rule)
+            (code-block indented "indented \"code\"\n")
+            "#
+        )
+        .trim_start()
+    );
+}
@@ -0,0 +1,431 @@
+//! Summary statistics about the contents of a Markdown document.
+
+use crate::{Block, HeadingLevel, Inline, Inlines, LinkType, ListItem};
+
+/// Counts of the different kinds of elements in a Markdown document.
+///
+/// See [`document_stats()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// Number of headings at each level, from [`HeadingLevel::H1`] (index 0)
+    /// to [`HeadingLevel::H6`] (index 5).
+    ///
+    /// See also [`DocumentStats::heading_count()`].
+    pub headings: [usize; 6],
+    /// Number of words of text, e.g. for estimating reading time.
+    ///
+    /// Words are runs of non-whitespace characters, containing at least one
+    /// letter or number, in the text of paragraphs, headings, links, and table
+    /// cells. Formatting does not split a word,
+    /// e.g. `foo*bar*` is a single word. Code, URLs (including the text of
+    /// autolinks), image descriptions, math, and HTML are not counted.
+    ///
+    /// See also [`word_count_with()`], which also counts code.
+    pub words: usize,
+    /// Number of non-whitespace characters in the [`words`][Self::words].
+    pub characters: usize,
+    pub paragraphs: usize,
+    /// Number of lists, including lists nested inside other blocks.
+    pub lists: usize,
+    pub list_items: usize,
+    pub code_blocks: usize,
+    pub tables: usize,
+    pub links: usize,
+    pub images: usize,
+}
+
+/// Compute [`DocumentStats`] for `blocks` in a single traversal of the AST.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{document_stats, markdown_to_ast, HeadingLevel};
+///
+/// let ast = markdown_to_ast("# Title\n\nA paragraph with a [link](example.org).");
+///
+/// let stats = document_stats(&ast);
+///
+/// assert_eq!(stats.heading_count(HeadingLevel::H1), 1);
+/// assert_eq!(stats.paragraphs, 1);
+/// assert_eq!(stats.links, 1);
+/// ```
+pub fn document_stats(blocks: &[Block]) -> DocumentStats {
+    let mut stats = DocumentStats::default();
+
+    for block in blocks {
+        stats.add_block(block);
+    }
+
+    stats
+}
+
+/// Count the words in `blocks`, skipping any block, including blocks nested
+/// inside other blocks, for which `skip` returns `true`.
+///
+/// Words are runs of non-whitespace characters in the text of the document,
+/// including the text of code spans, links, images, table cells, and the code
+/// in code blocks. Formatting does not split a word, e.g. `foo*bar*` is a
+/// single word. HTML and metadata blocks are not counted.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{markdown_to_ast, word_count_with, Block};
+///
+/// let ast = markdown_to_ast("Some *text*.\n\n```\nlet x = 1;\n```");
+///
+/// assert_eq!(word_count_with(&ast, |_| false), 6);
+///
+/// // Skip code blocks.
+/// let words = word_count_with(&ast, |block| {
+///     matches!(block, Block::CodeBlock { .. })
+/// });
+///
+/// assert_eq!(words, 2);
+/// ```
+pub fn word_count_with(
+    blocks: &[Block],
+    skip: impl Fn(&Block) -> bool,
+) -> usize {
+    blocks
+        .iter()
+        .map(|block| block_word_count(block, &skip))
+        .sum()
+}
+
+fn block_word_count(block: &Block, skip: &dyn Fn(&Block) -> bool) -> usize {
+    if skip(block) {
+        return 0;
+    }
+
+    let blocks_word_count = |blocks: &[Block]| -> usize {
+        blocks
+            .iter()
+            .map(|block| block_word_count(block, skip))
+            .sum()
+    };
+
+    match block {
+        Block::Paragraph(inlines) | Block::Heading(_, inlines, _) => {
+            inlines_word_count(inlines)
+        },
+        Block::List(items, _, _) => items
+            .iter()
+            .map(|ListItem(blocks, _)| blocks_word_count(blocks))
+            .sum(),
+        Block::CodeBlock { kind: _, code } => code.split_whitespace().count(),
+        Block::BlockQuote { kind: _, blocks }
+        | Block::FootnoteDefinition { label: _, blocks } => {
+            blocks_word_count(blocks)
+        },
+        Block::Table {
+            alignments: _,
+            headers,
+            rows,
+        } => headers
+            .iter()
+            .chain(rows.iter().flatten())
+            .map(inlines_word_count)
+            .sum(),
+        Block::Rule | Block::Html(_) | Block::Metadata { .. } => 0,
+    }
+}
+
+fn inlines_word_count(inlines: &Inlines) -> usize {
+    /// Append the text of `inlines` to `text`, with line breaks written as
+    /// spaces.
+    fn push_text(text: &mut String, Inlines(inlines): &Inlines) {
+        for inline in inlines {
+            push_inline_text(text, inline);
+        }
+    }
+
+    fn push_inline_text(text: &mut String, inline: &Inline) {
+        match inline {
+            Inline::Text(string)
+            | Inline::Code(string)
+            | Inline::InlineMath(string)
+            | Inline::DisplayMath(string) => text.push_str(string),
+            Inline::SoftBreak | Inline::HardBreak => text.push(' '),
+            Inline::Emphasis(children)
+            | Inline::Strong(children)
+            | Inline::Strikethrough(children)
+            | Inline::Link {
+                content_text: children,
+                ..
+            }
+            | Inline::Image {
+                image_description: children,
+                ..
+            } => push_text(text, children),
+            Inline::FootnoteReference(_) | Inline::InlineHtml(_) => (),
+            Inline::Positioned(_, inline) => push_inline_text(text, inline),
+        }
+    }
+
+    let mut text = String::new();
+
+    push_text(&mut text, inlines);
+
+    text.split_whitespace().count()
+}
+
+impl DocumentStats {
+    /// Returns the number of headings with the specified `level`.
+    pub fn heading_count(&self, level: HeadingLevel) -> usize {
+        self.headings[level as usize - 1]
+    }
+
+    /// Returns the total number of headings at any level.
+    pub fn total_headings(&self) -> usize {
+        self.headings.iter().sum()
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        match block {
+            Block::Paragraph(inlines) => {
+                self.paragraphs += 1;
+                self.add_text(inlines);
+                self.add_inlines(inlines);
+            },
+            Block::List(items, _, _) => {
+                self.lists += 1;
+                self.list_items += items.len();
+
+                for ListItem(blocks, _) in items {
+                    for block in blocks {
+                        self.add_block(block);
+                    }
+                }
+            },
+            Block::Heading(level, inlines, _) => {
+                self.headings[*level as usize - 1] += 1;
+                self.add_text(inlines);
+                self.add_inlines(inlines);
+            },
+            Block::CodeBlock { .. } => self.code_blocks += 1,
+            Block::BlockQuote { kind: _, blocks }
+            | Block::FootnoteDefinition { label: _, blocks } => {
+                for block in blocks {
+                    self.add_block(block);
+                }
+            },
+            Block::Table {
+                alignments: _,
+                headers,
+                rows,
+            } => {
+                self.tables += 1;
+
+                for cell in headers.iter().chain(rows.iter().flatten()) {
+                    self.add_text(cell);
+                    self.add_inlines(cell);
+                }
+            },
+            Block::Rule | Block::Html(_) | Block::Metadata { .. } => (),
+        }
+    }
+
+    /// Add the words and characters of the text of `inlines`.
+    fn add_text(&mut self, inlines: &Inlines) {
+        fn push_text(text: &mut String, Inlines(inlines): &Inlines) {
+            for inline in inlines {
+                push_inline_text(text, inline);
+            }
+        }
+
+        fn push_inline_text(text: &mut String, inline: &Inline) {
+            match inline {
+                Inline::Text(string) => text.push_str(string),
+                Inline::SoftBreak | Inline::HardBreak => text.push(' '),
+                Inline::Emphasis(children)
+                | Inline::Strong(children)
+                | Inline::Strikethrough(children) => push_text(text, children),
+                Inline::Link {
+                    link_type: LinkType::Autolink | LinkType::Email,
+                    ..
+                } => (),
+                Inline::Link { content_text, .. } => {
+                    push_text(text, content_text)
+                },
+                Inline::Code(_)
+                | Inline::Image { .. }
+                | Inline::FootnoteReference(_)
+                | Inline::InlineMath(_)
+                | Inline::DisplayMath(_)
+                | Inline::InlineHtml(_) => (),
+                Inline::Positioned(_, inline) => push_inline_text(text, inline),
+            }
+        }
+
+        let mut text = String::new();
+
+        push_text(&mut text, inlines);
+
+        // Skip punctuation left on its own, e.g. after an image.
+        let words = text
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric));
+
+        for word in words {
+            self.words += 1;
+            self.characters += word.chars().count();
+        }
+    }
+
+    fn add_inlines(&mut self, Inlines(inlines): &Inlines) {
+        for inline in inlines {
+            self.add_inline(inline);
+        }
+    }
+
+    fn add_inline(&mut self, inline: &Inline) {
+        match inline {
+            Inline::Text(_)
+            | Inline::Code(_)
+            | Inline::SoftBreak
+            | Inline::HardBreak
+            | Inline::FootnoteReference(_)
+            | Inline::InlineMath(_)
+            | Inline::DisplayMath(_)
+            | Inline::InlineHtml(_) => (),
+            Inline::Emphasis(inlines)
+            | Inline::Strong(inlines)
+            | Inline::Strikethrough(inlines) => self.add_inlines(inlines),
+            Inline::Link { content_text, .. } => {
+                self.links += 1;
+                self.add_inlines(content_text);
+            },
+            Inline::Image {
+                image_description, ..
+            } => {
+                self.images += 1;
+                self.add_inlines(image_description);
+            },
+            Inline::Positioned(_, inline) => self.add_inline(inline),
+        }
+    }
+}
+
+//======================================
+// Tests
+//======================================
+
+#[test]
+fn test_document_stats() {
+    use crate::markdown_to_ast;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        # Title
+
+        An introduction with a [link](example.org) and ![an image](photo.png).
+
+        ## Section
+
+        * One
+        * Two
+          * Nested
+
+        > A quote with [another link](example.org).
+        >
+        > ```rust
+        > fn main() {}
+        > ```
+
+        ## Another Section
+
+        | Name | Website |
+        |------|---------|
+        | Foo  | [foo](foo.org) |
+
+        ---
+
+            indented code
+        "
+    ));
+
+    assert_eq!(
+        document_stats(&ast),
+        DocumentStats {
+            headings: [1, 2, 0, 0, 0, 0],
+            words: 22,
+            characters: 103,
+            paragraphs: 5,
+            lists: 2,
+            list_items: 3,
+            code_blocks: 2,
+            tables: 1,
+            links: 3,
+            images: 1,
+        }
+    );
+}
+
+#[test]
+fn test_word_count_with() {
+    use crate::markdown_to_ast;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        # A Title
+
+        Some *emphasized*text and a [two word](example.org) link.
+
+        > Quoted words are
+        > skipped.
+        >
+        > * Even in lists.
+
+        * Item one
+        * Item two
+
+        | Name | Value |
+        |------|-------|
+        | a b  | c     |
+        "
+    ));
+
+    let quotes = |block: &Block| matches!(block, Block::BlockQuote { .. });
+
+    // 2 + 7 + 4 + 5 words, excluding the 7 words in the block quote.
+    assert_eq!(word_count_with(&ast, quotes), 18);
+    assert_eq!(word_count_with(&ast, |_| false), 25);
+    assert_eq!(word_count_with(&ast, |_| true), 0);
+}
+
+#[test]
+fn test_document_stats_words() {
+    use crate::markdown_to_ast;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(indoc!(
+        "
+        ## Getting started
+
+        Run `cargo build`, see <https://example.org> or the
+        [*user* guide](https://example.org/guide) — ![logo](logo.png)
+
+        ```sh
+        cargo run
+        ```
+        "
+    ));
+
+    let stats = document_stats(&ast);
+
+    // Getting started / Run see or the user guide
+    assert_eq!(stats.words, 8);
+    assert_eq!(stats.characters, 7 + 7 + 3 + 3 + 2 + 3 + 4 + 5);
+    assert_eq!(stats.total_headings(), 1);
+    assert_eq!(stats.code_blocks, 1);
+    assert_eq!(stats.links, 2);
+    assert_eq!(stats.images, 1);
+
+    assert_eq!(ast[1].word_count(), 6);
+}
@@ -2,7 +2,7 @@
 
 use pulldown_cmark::{CowStr, Event, Tag};
 
-use crate::{Block, Inline, Inlines, ListItem};
+use crate::{Block, HeadingAttributes, Inline, Inlines, ListItem};
 
 //======================================
 // AST blocks to Events
@@ -16,45 +16,65 @@ pub(crate) fn block_to_events<'ast>(
         Block::Paragraph(inlines) => wrap(Tag::Paragraph, events, |events| {
             inlines_to_events(inlines, events)
         }),
-        Block::List(list_items) => {
-            // TODO: Handle this for numbered lists.
-            let first_item_number = None;
+        Block::List(list_items, first_item_number, tight) => {
+            let tight = *tight && can_be_tight(list_items);
 
-            wrap(Tag::List(first_item_number), events, |events| {
-                for ListItem(list_item_blocks) in list_items {
+            wrap(Tag::List(*first_item_number), events, |events| {
+                for ListItem(list_item_blocks, checked) in list_items {
                     wrap(Tag::Item, events, |events| {
                         // NOTE:
-                        //  Handle a special case where a single-item list
-                        //  containing a sequence of inlines is parsed by
-                        //  clap-markdown NOT wrapped in paired
-                        //  Start(Tag::Paragraph) / End(_) events.
-                        match list_item_blocks.as_slice() {
-                            [Block::Paragraph(inlines)]
-                                if list_items.len() == 1 =>
-                            {
-                                inlines_to_events(inlines, events);
-
-                                // Return from inner closure.
-                                return;
-                            },
-                            _ => (),
-                        }
+                        //  The task list marker is emitted at the start of the
+                        //  first paragraph of the item, matching where
+                        //  pulldown-cmark emits it.
+                        let mut marker = checked.map(Event::TaskListMarker);
 
                         for list_item_block in list_item_blocks {
-                            block_to_events(list_item_block, events);
+                            match list_item_block {
+                                // NOTE:
+                                //  The paragraphs in the items of a tight list
+                                //  are NOT wrapped in paired
+                                //  Start(Tag::Paragraph) / End(_) events.
+                                Block::Paragraph(inlines) if tight => {
+                                    events.extend(marker.take());
+                                    inlines_to_events(inlines, events)
+                                },
+                                Block::Paragraph(inlines) => {
+                                    wrap(Tag::Paragraph, events, |events| {
+                                        events.extend(marker.take());
+                                        inlines_to_events(inlines, events)
+                                    })
+                                },
+                                _ => {
+                                    events.extend(marker.take());
+                                    block_to_events(list_item_block, events)
+                                },
+                            }
                         }
+
+                        events.extend(marker);
                     });
                 }
             })
         },
-        Block::Heading(level, inlines) => {
+        Block::Heading(level, inlines, attributes) => {
+            let HeadingAttributes { id, classes, attrs } = attributes;
+
             let tag = Tag::Heading {
                 level: *level,
-                // FIXME: Set this id.
-                id: None,
-                // FIXME: Support these classes and attrs.
-                classes: Vec::new(),
-                attrs: Vec::new(),
+                id: id.as_deref().map(CowStr::from),
+                classes: classes
+                    .iter()
+                    .map(|class| CowStr::from(class.as_str()))
+                    .collect(),
+                attrs: attrs
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            CowStr::from(key.as_str()),
+                            value.as_deref().map(CowStr::from),
+                        )
+                    })
+                    .collect(),
             };
 
             wrap(tag, events, |events| inlines_to_events(inlines, events));
@@ -63,8 +83,14 @@ pub(crate) fn block_to_events<'ast>(
             let kind = kind.to_pulldown_cmark();
 
             wrap(Tag::CodeBlock(kind), events, |events| {
-                // FIXME: Is this the right event for raw codeblock content?
-                events.push(Event::Text(CowStr::from(code.as_str())))
+                // NOTE:
+                //  pulldown-cmark emits no Text event at all for a code block
+                //  with no content, and emitting an empty one would cause
+                //  an extra blank line to be written inside the fences.
+                if !code.is_empty() {
+                    // FIXME: Is this the right event for raw codeblock content?
+                    events.push(Event::Text(CowStr::from(code.as_str())))
+                }
             })
         },
         Block::BlockQuote { kind, blocks } => {
@@ -90,27 +116,64 @@ pub(crate) fn block_to_events<'ast>(
             wrap(Tag::Table(alignments.clone()), events, |events| {
                 wrap(Tag::TableHead, events, |events| {
                     for header_cell in headers {
-                        wrap(Tag::TableCell, events, |events| {
-                            inlines_to_events(header_cell, events);
-                        })
+                        table_cell_to_events(header_cell, events)
                     }
                 });
 
                 for row in rows {
                     wrap(Tag::TableRow, events, |events| {
                         for row_cell in row {
-                            wrap(Tag::TableCell, events, |events| {
-                                inlines_to_events(row_cell, events);
-                            })
+                            table_cell_to_events(row_cell, events)
                         }
                     })
                 }
             })
         },
         Block::Rule => events.push(Event::Rule),
+        Block::FootnoteDefinition { label, blocks } => {
+            let tag = Tag::FootnoteDefinition(CowStr::from(label.as_str()));
+
+            wrap(tag, events, |events| {
+                for block in blocks {
+                    block_to_events(block, events)
+                }
+            })
+        },
+        Block::Html(html) => {
+            // NOTE:
+            //  pulldown-cmark emits a separate Event::Html for each line of
+            //  an HTML block.
+            wrap(Tag::HtmlBlock, events, |events| {
+                for line in html.split_inclusive('\n') {
+                    events.push(Event::Html(CowStr::from(line)))
+                }
+            })
+        },
+        Block::Metadata { kind, content } => {
+            wrap(Tag::MetadataBlock(*kind), events, |events| {
+                events.push(Event::Text(CowStr::from(content.as_str())))
+            })
+        },
     }
 }
 
+/// Returns `true` if `list_items` can be written out as a tight list.
+///
+/// A paragraph following a paragraph or a list in a list item, or a rule
+/// following a paragraph, must be separated from it by a blank line, which
+/// makes the entire list loose.
+fn can_be_tight(list_items: &[ListItem]) -> bool {
+    return list_items.iter().all(|ListItem(blocks, _)| {
+        !blocks.windows(2).any(|pair| {
+            matches!(
+                pair,
+                [Block::Paragraph(_) | Block::List(..), Block::Paragraph(_)]
+                    | [Block::Paragraph(_), Block::Rule]
+            )
+        })
+    });
+}
+
 fn wrap<'ast, F: FnOnce(&mut Vec<Event<'ast>>)>(
     tag: Tag<'ast>,
     events: &mut Vec<Event<'ast>>,
@@ -123,6 +186,30 @@ fn wrap<'ast, F: FnOnce(&mut Vec<Event<'ast>>)>(
     events.push(Event::End(end));
 }
 
+fn table_cell_to_events<'ast>(
+    cell: &'ast Inlines,
+    events: &mut Vec<Event<'ast>>,
+) {
+    wrap(Tag::TableCell, events, |events| {
+        let start = events.len();
+
+        inlines_to_events(cell, events);
+
+        // A table cell cannot contain a literal newline, so write hard breaks
+        // as `<br>`, and soft breaks (including newlines in text) as a space
+        // instead.
+        for event in &mut events[start..] {
+            match event {
+                Event::HardBreak => {
+                    *event = Event::InlineHtml(CowStr::from("<br>"))
+                },
+                Event::SoftBreak => *event = Event::Text(CowStr::from(" ")),
+                _ => (),
+            }
+        }
+    })
+}
+
 fn inlines_to_events<'ast>(
     inlines: &'ast Inlines,
     events: &mut Vec<Event<'ast>>,
@@ -130,67 +217,110 @@ fn inlines_to_events<'ast>(
     let Inlines(inlines) = inlines;
 
     for inline in inlines {
-        match inline {
-            Inline::Text(text) => {
-                events.push(Event::Text(CowStr::from(text.as_str())));
-            },
-            Inline::Emphasis(inlines) => {
-                wrap(Tag::Emphasis, events, |events| {
-                    inlines_to_events(inlines, events)
-                })
-            },
-            Inline::Strong(inlines) => wrap(Tag::Strong, events, |events| {
+        inline_to_events(inline, events)
+    }
+}
+
+fn inline_to_events<'ast>(inline: &'ast Inline, events: &mut Vec<Event<'ast>>) {
+    match inline {
+        Inline::Text(text) => text_to_events(text, events),
+        Inline::Emphasis(inlines) => wrap(Tag::Emphasis, events, |events| {
+            inlines_to_events(inlines, events)
+        }),
+        Inline::Strong(inlines) => wrap(Tag::Strong, events, |events| {
+            inlines_to_events(inlines, events)
+        }),
+        Inline::Strikethrough(inlines) => {
+            wrap(Tag::Strikethrough, events, |events| {
                 inlines_to_events(inlines, events)
-            }),
-            Inline::Strikethrough(inlines) => {
-                wrap(Tag::Strikethrough, events, |events| {
-                    inlines_to_events(inlines, events)
-                })
+            })
+        },
+        Inline::Code(code) => {
+            events.push(Event::Code(CowStr::from(code.as_str())))
+        },
+        Inline::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+            content_text,
+        } => wrap(
+            Tag::Link {
+                link_type: *link_type,
+                dest_url: CowStr::from(dest_url.as_str()),
+                // NOTE: The title is passed through verbatim, including
+                //       any whitespace. See
+                //       test_link_title_whitespace_roundtrip().
+                title: CowStr::from(title.as_str()),
+                // NOTE: The title and id are passed through for every
+                //       link type. See test_link_title_roundtrip().
+                id: CowStr::from(id.as_str()),
             },
-            Inline::Code(code) => {
-                events.push(Event::Code(CowStr::from(code.as_str())))
+            events,
+            |events| inlines_to_events(content_text, events),
+        ),
+        Inline::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+            image_description,
+        } => wrap(
+            Tag::Image {
+                link_type: *link_type,
+                dest_url: CowStr::from(dest_url.as_str()),
+                title: CowStr::from(title.as_str()),
+                id: CowStr::from(id.as_str()),
             },
-            Inline::Link {
-                link_type,
-                dest_url,
-                title,
-                id,
-                content_text,
-            } => wrap(
-                Tag::Link {
-                    link_type: *link_type,
-                    dest_url: CowStr::from(dest_url.as_str()),
-                    // FIXME:
-                    //  Pass through this title; have a test that fails
-                    //  if this is empty.
-                    title: CowStr::from(title.as_str()),
-                    // FIXME: Passthrough this id
-                    // FIXME:
-                    //  Add test for the value of this field for every
-                    //  link type.
-                    id: CowStr::from(id.as_str()),
-                },
-                events,
-                |events| inlines_to_events(content_text, events),
-            ),
-            Inline::Image {
-                link_type,
-                dest_url,
-                title,
-                id,
-                image_description,
-            } => wrap(
-                Tag::Image {
-                    link_type: *link_type,
-                    dest_url: CowStr::from(dest_url.as_str()),
-                    title: CowStr::from(title.as_str()),
-                    id: CowStr::from(id.as_str()),
-                },
-                events,
-                |events| inlines_to_events(image_description, events),
-            ),
-            Inline::SoftBreak => events.push(Event::SoftBreak),
-            Inline::HardBreak => events.push(Event::HardBreak),
+            events,
+            |events| inlines_to_events(image_description, events),
+        ),
+        Inline::SoftBreak => events.push(Event::SoftBreak),
+        Inline::HardBreak => events.push(Event::HardBreak),
+        Inline::FootnoteReference(label) => {
+            events.push(Event::FootnoteReference(CowStr::from(label.as_str())))
+        },
+        Inline::InlineMath(math) => {
+            events.push(Event::InlineMath(CowStr::from(math.as_str())))
+        },
+        Inline::DisplayMath(math) => {
+            events.push(Event::DisplayMath(CowStr::from(math.as_str())))
+        },
+        Inline::InlineHtml(html) => {
+            events.push(Event::InlineHtml(CowStr::from(html.as_str())))
+        },
+        Inline::Positioned(_, inline) => inline_to_events(inline, events),
+    }
+}
+
+/// Emit `text`, splitting any newlines it contains into [`Event::SoftBreak`]s.
+///
+/// A newline written as part of an [`Event::Text`] could change the block
+/// structure of the output, e.g. a following line starting with `#` would be
+/// parsed as a heading. Each line is emitted as a separate [`Event::Text`] so
+/// that it is escaped independently. A run of several newlines is emitted as a
+/// single soft break, because a blank line would end the containing paragraph.
+fn text_to_events<'ast>(text: &'ast str, events: &mut Vec<Event<'ast>>) {
+    let mut pending_break = false;
+
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            pending_break = true;
         }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if pending_break {
+            events.push(Event::SoftBreak);
+            pending_break = false;
+        }
+
+        events.push(Event::Text(CowStr::from(line)));
+    }
+
+    if pending_break {
+        events.push(Event::SoftBreak);
     }
 }
@@ -0,0 +1,1640 @@
+//! Prepare "flattened" [`pulldown_cmark::Event`]s to be written out as a
+//! Markdown string by [`pulldown_cmark_to_cmark`].
+//!
+//! `pulldown_cmark_to_cmark` writes each event out independently of its
+//! neighbors, which in some cases produces Markdown that does not parse back
+//! to the original events. The functions in this module rewrite those event
+//! sequences into equivalent events that will be written out unambiguously.
+//!
+//! Where a specific piece of Markdown syntax needs to be written out verbatim,
+//! it is emitted as an [`Event::InlineHtml`], which `pulldown_cmark_to_cmark`
+//! writes out as-is.
+
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, CowStr, Event, LinkType, Tag,
+    TagEnd,
+};
+
+use std::ops::Range;
+
+use crate::MarkdownOptions;
+
+pub(crate) fn prepare_events<'e, I: Iterator<Item = Event<'e>>>(
+    events: I,
+    options: &MarkdownOptions,
+    cmark_options: &pulldown_cmark_to_cmark::Options,
+) -> Vec<Event<'e>> {
+    let mut prepared: Vec<Event<'e>> = Vec::new();
+
+    // The column reached on the current line of a code block, or `None` if
+    // not in a code block.
+    let mut code_block_column: Option<usize> = None;
+
+    // If in an indented code block, whether any of its code has been written.
+    let mut indented_code_block: Option<bool> = None;
+
+    let events: Vec<Event<'e>> = events.collect();
+
+    // The delimiter characters to use for each emphasis or strong element, in
+    // order, and for each currently open one. `None` if the delimiters of the
+    // element are omitted.
+    let mut emphasis_delimiters =
+        emphasis_delimiters(&events, cmark_options.emphasis_token).into_iter();
+    let mut delimiters: Vec<Option<char>> = Vec::new();
+
+    // Whether each list is tight, in order, and for each currently open one.
+    let mut list_tightness = list_tightness(&events).into_iter();
+    let mut tight_lists: Vec<bool> = Vec::new();
+
+    // True if the previous event ended a tight list nested in an item of a
+    // loose list.
+    let mut follows_nested_tight_list = false;
+
+    // The alignments of the current table, and the width of the text in each
+    // of its header cells.
+    let mut table_alignments: Vec<Alignment> = Vec::new();
+    let mut header_widths: Vec<usize> = Vec::new();
+    let mut in_table_head = false;
+    let mut in_table_cell = false;
+
+    // The index in `prepared` of the start of the current table, if its
+    // columns are to be padded once the whole table has been prepared.
+    let mut padded_table_start: Option<usize> = None;
+
+    // True if the previous event ended the header row of a table.
+    let mut follows_table_head = false;
+
+    // True if the previous event left the output at the start of a line of
+    // inline content.
+    let mut follows_line_start = false;
+
+    // True if inside an autolink, whose text is written out verbatim.
+    let mut in_autolink = false;
+
+    // True if inside a metadata block, whose text is written out verbatim.
+    let mut in_metadata_block = false;
+
+    // The number of currently open links and images.
+    let mut link_depth: usize = 0;
+
+    let mut in_heading = false;
+
+    // The kind and label of the currently open reference link and reference
+    // image, if any.
+    let mut reference_link: Option<(LinkType, CowStr<'e>)> = None;
+    let mut reference_image: Option<(LinkType, CowStr<'e>)> = None;
+
+    // The link reference definitions to write at the end of the document, as
+    // (label, destination, title).
+    let mut definitions: Vec<(CowStr<'e>, CowStr<'e>, CowStr<'e>)> = Vec::new();
+
+    let special_characters = cmark_options.special_characters();
+
+    // The index of the last event that starts new content, after which no
+    // more content follows.
+    let last_content_start = events
+        .iter()
+        .rposition(|event| matches!(event, Event::Start(_) | Event::Rule));
+
+    for (index, event) in events.into_iter().enumerate() {
+        let is_followed_by_content =
+            last_content_start.is_some_and(|last| last > index);
+
+        // Separate a tight list nested in an item of a loose list from the
+        // following block in the item with a blank line, which
+        // `pulldown_cmark_to_cmark` only writes after a top-level list.
+        if std::mem::take(&mut follows_nested_tight_list)
+            && matches!(event, Event::Start(_) | Event::Rule)
+        {
+            prepared.push(Event::Html(CowStr::Borrowed("\n")));
+        }
+
+        // Start a block that follows the paragraph of an item of a tight list,
+        // which is not wrapped in paragraph events, on a new line, which
+        // `pulldown_cmark_to_cmark` does for only some kinds of blocks.
+        if matches!(
+            event,
+            Event::Start(Tag::Heading { .. } | Tag::HtmlBlock | Tag::Table(_))
+        ) && prepared.last().is_some_and(is_inline_event)
+        {
+            prepared.push(Event::Html(CowStr::Borrowed("\n")));
+        }
+
+        let is_table_head_end = event == Event::End(TagEnd::TableHead);
+        let is_line_start = matches!(
+            event,
+            Event::Start(
+                Tag::Paragraph
+                    | Tag::Heading { .. }
+                    | Tag::Item
+                    | Tag::TableCell
+            ) | Event::SoftBreak
+                | Event::HardBreak
+        );
+
+        match event {
+            Event::Start(Tag::Link {
+                link_type: LinkType::Autolink | LinkType::Email,
+                ..
+            }) => in_autolink = true,
+            Event::End(TagEnd::Link) => in_autolink = false,
+            Event::Start(Tag::MetadataBlock(_)) => in_metadata_block = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata_block = false,
+            _ => (),
+        }
+
+        match event {
+            Event::Start(Tag::Link { .. } | Tag::Image { .. }) => {
+                link_depth += 1
+            },
+            Event::End(TagEnd::Link | TagEnd::Image) => link_depth -= 1,
+            Event::Start(Tag::Heading { .. }) => in_heading = true,
+            Event::End(TagEnd::Heading(_)) => in_heading = false,
+            Event::Start(Tag::List(_)) => tight_lists
+                .push(list_tightness.next().expect("missing list tightness")),
+            Event::End(TagEnd::List(_)) => {
+                follows_nested_tight_list = tight_lists.pop() == Some(true)
+                    && tight_lists.last() == Some(&false);
+            },
+            _ => (),
+        }
+
+        match event {
+            //
+            // Write out the delimiter row of a table ourselves.
+            //
+            // `pulldown_cmark_to_cmark` sizes each delimiter to the header
+            // text, which for short headers leaves no room for a `-` between
+            // the alignment colons; e.g. a centered header `a` would be written
+            // with the delimiter `:`, which is not a valid delimiter row.
+            //
+            Event::Start(Tag::Table(ref alignments)) => {
+                table_alignments = alignments.clone();
+                if options.pad_table_columns {
+                    padded_table_start = Some(prepared.len());
+                }
+                prepared.push(event);
+            },
+            Event::End(TagEnd::Table) => {
+                prepared.push(event);
+
+                if let Some(start) = padded_table_start.take() {
+                    let table = prepared.split_off(start);
+
+                    prepared.extend(pad_table_columns(
+                        table,
+                        &table_alignments,
+                        cmark_options,
+                    ));
+                }
+            },
+            Event::Start(Tag::TableHead) => {
+                in_table_head = true;
+                header_widths.clear();
+                prepared.push(event);
+            },
+            Event::Start(Tag::TableCell) => {
+                in_table_cell = true;
+                if in_table_head {
+                    header_widths.push(0);
+                }
+                prepared.push(event);
+            },
+            Event::End(TagEnd::TableCell) => {
+                in_table_cell = false;
+                prepared.push(event);
+            },
+            //
+            // Expand tabs in code blocks, if requested.
+            //
+            // Write out indented code blocks ourselves, as
+            // `pulldown_cmark_to_cmark` writes every code block fenced. An
+            // indented code block with no code cannot be written indented, so
+            // it is written as an empty fenced code block instead.
+            //
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Indented)) => {
+                code_block_column = Some(0);
+                indented_code_block = Some(false);
+            },
+            Event::Start(Tag::CodeBlock(_)) => {
+                code_block_column = Some(0);
+                prepared.push(event);
+            },
+            Event::End(TagEnd::CodeBlock) => {
+                code_block_column = None;
+
+                match indented_code_block.take() {
+                    Some(true) => {
+                        // End the last line of code, so that the code block
+                        // is not joined to the following line.
+                        if let Some(Event::Html(line)) = prepared.last() {
+                            if !line.ends_with('\n') {
+                                prepared.push(Event::Html(CowStr::from("\n")));
+                            }
+                        }
+
+                        prepared.push(Event::End(TagEnd::HtmlBlock))
+                    },
+                    Some(false) => {
+                        prepared.push(Event::Start(Tag::CodeBlock(
+                            CodeBlockKind::Fenced(CowStr::Borrowed("")),
+                        )));
+                        prepared.push(event);
+                    },
+                    None => prepared.push(event),
+                }
+            },
+            Event::Text(text) if code_block_column.is_some() => {
+                let text = match (
+                    code_block_column.as_mut(),
+                    options.code_block_tab_width,
+                ) {
+                    (Some(column), Some(tab_width)) => {
+                        CowStr::from(expand_tabs(&text, tab_width, column))
+                    },
+                    _ => text,
+                };
+
+                match indented_code_block.as_mut() {
+                    Some(has_code) => {
+                        if !*has_code {
+                            prepared.push(Event::Start(Tag::HtmlBlock));
+                            *has_code = true;
+                        }
+
+                        push_indented_code(&mut prepared, &text);
+                    },
+                    None => prepared.push(Event::Text(text)),
+                }
+            },
+            //
+            // End the last line of an HTML block with a newline, so that the
+            // block is not joined to the following line, which would then be
+            // parsed as part of the HTML block.
+            //
+            Event::End(TagEnd::HtmlBlock) if is_followed_by_content => {
+                if let Some(Event::Html(line)) = prepared.last() {
+                    if !line.ends_with('\n') {
+                        prepared.push(Event::Html(CowStr::from("\n")));
+                    }
+                }
+
+                prepared.push(event);
+            },
+            //
+            // Don't end an HTML block that ends an item of a tight list with a
+            // newline, as the end of the item is already written with one, and
+            // the extra newline would be written as a blank line, making the
+            // list loose.
+            //
+            Event::End(TagEnd::Item) if tight_lists.last() == Some(&true) => {
+                if let [.., Event::Html(line), Event::End(TagEnd::HtmlBlock)] =
+                    prepared.as_mut_slice()
+                {
+                    if let Some(trimmed) = line.strip_suffix('\n') {
+                        *line = CowStr::from(trimmed.to_owned());
+                    }
+                }
+
+                prepared.push(event);
+            },
+            //
+            // End the content of a metadata block with a newline, so that the
+            // closing delimiter is written on a line of its own.
+            //
+            Event::End(TagEnd::MetadataBlock(_)) => {
+                if let Some(Event::Text(content)) = prepared.last() {
+                    if !content.ends_with('\n') {
+                        prepared.push(Event::Text(CowStr::from("\n")));
+                    }
+                }
+
+                prepared.push(event);
+            },
+            //
+            // Escape Markdown metacharacters in text, which would otherwise be
+            // parsed back as formatting; e.g. the text `a*b*c` would be parsed
+            // as containing emphasis. Ampersands that would begin a character
+            // reference are escaped as `&amp;`.
+            //
+            // Pipes in table cells are escaped too, as they would otherwise be
+            // treated as column separators. This applies even inside code
+            // spans. `#` characters in headings are escaped so that they are
+            // not read as a closing sequence.
+            //
+            // Whitespace at the start of a line would be stripped by the
+            // parser, or begin an indented code block, so the first
+            // whitespace character is written as a character reference.
+            //
+            Event::Text(text) if !in_autolink && !in_metadata_block => {
+                if in_table_cell && in_table_head {
+                    add_header_width(&mut header_widths, text.chars().count());
+                }
+
+                let mut at_line_start = follows_line_start;
+
+                let text = match text.chars().next() {
+                    Some(char @ (' ' | '\t')) if at_line_start => {
+                        prepared.push(raw(format!("&#{};", char as u32)));
+                        at_line_start = false;
+
+                        CowStr::from(text[1..].to_owned())
+                    },
+                    _ => text,
+                };
+
+                // The index of the start of the line, if this text begins one.
+                let line_start = at_line_start.then_some(0);
+
+                let needs_escape =
+                    |index: usize, before: &str, char: char, rest: &str| {
+                        (ESCAPED_CHARACTERS.contains(char)
+                            && !is_bare_url_underscore(before, char, rest))
+                            || (char == '<' && could_start_tag(rest))
+                            || (in_table_cell && char == '|')
+                            || (in_heading && char == '#')
+                            || (Some(index) == line_start
+                                && LINE_START_CHARACTERS.contains(char))
+                            || line_start.is_some_and(|start| {
+                                // The text from the start of the line up to
+                                // `char`, if it is all in `before`.
+                                let line = index
+                                    .checked_sub(start)
+                                    .and_then(|len| {
+                                        before.len().checked_sub(len)
+                                    })
+                                    .and_then(|offset| before.get(offset..));
+
+                                line.is_some_and(|number| {
+                                    is_ordered_list_delimiter(
+                                        number, char, rest,
+                                    )
+                                })
+                            })
+                    };
+
+                // Write out bare URLs as autolinks, if requested.
+                let urls = if options.autolink_bare_urls && link_depth == 0 {
+                    find_bare_urls(&text)
+                } else {
+                    Vec::new()
+                };
+
+                if urls.is_empty() {
+                    push_escaped_text(
+                        &mut prepared,
+                        escape_character_references(text),
+                        &special_characters,
+                        needs_escape,
+                    );
+                } else {
+                    let push_piece =
+                        |prepared: &mut Vec<Event<'e>>, range: Range<usize>| {
+                            if range.is_empty() {
+                                return;
+                            }
+
+                            let piece =
+                                CowStr::from(text[range.clone()].to_owned());
+
+                            push_escaped_text(
+                                prepared,
+                                escape_character_references(piece),
+                                &special_characters,
+                                |index, before, char, rest| {
+                                    needs_escape(
+                                        range.start + index,
+                                        before,
+                                        char,
+                                        rest,
+                                    )
+                                },
+                            );
+                        };
+
+                    let mut piece_start = 0;
+
+                    for url in urls {
+                        push_piece(&mut prepared, piece_start..url.start);
+
+                        let url_text = text[url.clone()].to_owned();
+
+                        prepared.push(Event::Start(Tag::Link {
+                            link_type: LinkType::Autolink,
+                            dest_url: CowStr::from(url_text.clone()),
+                            title: CowStr::Borrowed(""),
+                            id: CowStr::Borrowed(""),
+                        }));
+                        prepared.push(Event::Text(CowStr::from(url_text)));
+                        prepared.push(Event::End(TagEnd::Link));
+
+                        piece_start = url.end;
+                    }
+
+                    push_piece(&mut prepared, piece_start..text.len());
+                }
+            },
+            //
+            // Write out math ourselves.
+            //
+            // `pulldown_cmark_to_cmark` writes math without first writing any
+            // pending newlines, so math at the start of a paragraph would be
+            // joined to the end of the preceding block.
+            //
+            // Dollar signs in the math source are escaped, so that they are not
+            // read as the end of the math.
+            //
+            Event::InlineMath(math) => {
+                prepared.push(raw(format!("${}$", escape_math_dollars(&math))))
+            },
+            Event::DisplayMath(math) => prepared
+                .push(raw(format!("$${}$$", escape_math_dollars(&math)))),
+            Event::Code(code) if in_table_cell => {
+                if in_table_head {
+                    // Plus two for the surrounding backticks.
+                    let width = code.chars().count() + 2;
+                    add_header_width(&mut header_widths, width);
+                }
+
+                if code.contains('|') {
+                    let code = code.replace('|', "\\|");
+                    prepared.push(Event::Code(CowStr::from(code)));
+                } else {
+                    prepared.push(Event::Code(code));
+                }
+            },
+            // The delimiter row of a padded table is written by
+            // pad_table_columns(), once the widths of its columns are known.
+            Event::End(TagEnd::TableHead) if padded_table_start.is_some() => {
+                in_table_head = false;
+                prepared.push(event);
+            },
+            Event::End(TagEnd::TableHead) => {
+                in_table_head = false;
+
+                // Close the header row, and write the delimiter row in place
+                // of the one `pulldown_cmark_to_cmark` would have written.
+                let delimiters: String = table_alignments
+                    .iter()
+                    .zip(&header_widths)
+                    .map(|(&alignment, &width)| {
+                        // Match the width `pulldown_cmark_to_cmark` uses for
+                        // empty header cells.
+                        let width = if width == 0 { 2 } else { width };
+
+                        format!("|{}", delimiter_cell(alignment, width))
+                    })
+                    .collect();
+
+                prepared.push(raw(format!("|\n{delimiters}|")));
+            },
+            Event::Start(Tag::TableRow) if follows_table_head => {
+                prepared.push(raw(String::from("\n")));
+                prepared.push(event);
+            },
+            //
+            // Write out the alert marker of a block quote ourselves.
+            //
+            // `pulldown_cmark_to_cmark` includes the marker in the padding
+            // written at the start of every line of the block quote, instead
+            // of only on its first line.
+            //
+            Event::Start(Tag::BlockQuote(Some(kind))) => {
+                prepared.push(Event::Start(Tag::BlockQuote(None)));
+
+                // NOTE:
+                //  Unlike `Event::InlineHtml`, `Event::Html` is written without
+                //  first starting a new line, so the marker is placed on the
+                //  opening line of the block quote.
+                let marker = format!("[!{}]", alert_marker(kind));
+                prepared.push(Event::Html(CowStr::from(marker)));
+            },
+            //
+            // Write out reference links and images ourselves, collecting their
+            // link reference definitions to be written at the end of the
+            // document.
+            //
+            // `pulldown_cmark_to_cmark` writes reference and collapsed links
+            // as inline links, which parse back with a different link type.
+            //
+            Event::Start(Tag::Link {
+                link_type:
+                    link_type @ (LinkType::Reference
+                    | LinkType::Collapsed
+                    | LinkType::Shortcut),
+                dest_url,
+                title,
+                id,
+            }) if !id.is_empty() => {
+                escape_trailing_exclamation_mark(
+                    &mut prepared,
+                    &special_characters,
+                );
+
+                prepared.push(raw(String::from("[")));
+
+                add_definition(&mut definitions, &id, dest_url, title);
+
+                reference_link = Some((link_type, id));
+            },
+            Event::End(TagEnd::Link) if reference_link.is_some() => {
+                let (link_type, id) = reference_link.take().unwrap();
+
+                // Without any text, a collapsed or shortcut reference link
+                // would be written as `[][]` or `[]`, which are not links.
+                let is_empty = prepared.last() == Some(&raw("[".into()));
+
+                let link_type = match is_empty {
+                    true => LinkType::Reference,
+                    false => link_type,
+                };
+
+                prepared.push(raw(reference_close(link_type, &id)));
+            },
+            //
+            // Write out autolinks without any text as inline links, e.g.
+            // `[](https://example.org)`, as `<>` is not an autolink.
+            //
+            Event::End(TagEnd::Link)
+                if matches!(
+                    prepared.last(),
+                    Some(Event::Start(Tag::Link {
+                        link_type: LinkType::Autolink | LinkType::Email,
+                        ..
+                    }))
+                ) =>
+            {
+                if let Some(Event::Start(Tag::Link { link_type, .. })) =
+                    prepared.last_mut()
+                {
+                    *link_type = LinkType::Inline;
+                }
+
+                prepared.push(event);
+            },
+            Event::Start(Tag::Image {
+                link_type:
+                    link_type @ (LinkType::Reference
+                    | LinkType::Collapsed
+                    | LinkType::Shortcut),
+                dest_url,
+                title,
+                id,
+            }) if !id.is_empty() => {
+                prepared.push(raw(String::from("![")));
+
+                add_definition(&mut definitions, &id, dest_url, title);
+
+                reference_image = Some((link_type, id));
+            },
+            Event::End(TagEnd::Image) if reference_image.is_some() => {
+                let (link_type, id) = reference_image.take().unwrap();
+
+                let is_empty = prepared.last() == Some(&raw("![".into()));
+
+                let link_type = match is_empty {
+                    true => LinkType::Reference,
+                    false => link_type,
+                };
+
+                prepared.push(raw(reference_close(link_type, &id)));
+            },
+            //
+            // Escape ampersands in link destinations and titles that would
+            // otherwise be read as the start of a character reference.
+            //
+            // The parser decodes character references, so e.g. the
+            // destination `&amp;copy;` is stored as `&copy;`, which must not
+            // be written back out as-is, or it would be parsed as `©`.
+            //
+            // The destinations of autolinks are stored and written out
+            // verbatim, so they are left unchanged.
+            //
+            // Pipes in the destinations and titles of links and images in
+            // table cells are escaped, like pipes in text.
+            //
+            // A `!` at the end of the text preceding a link is escaped, as it
+            // would otherwise turn the link into an image.
+            //
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if !matches!(
+                link_type,
+                LinkType::Autolink | LinkType::Email
+            ) =>
+            {
+                let (dest_url, title) =
+                    escape_link_fields(dest_url, title, in_table_cell);
+
+                escape_trailing_exclamation_mark(
+                    &mut prepared,
+                    &special_characters,
+                );
+
+                prepared.push(Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }));
+            },
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let (dest_url, title) =
+                    escape_link_fields(dest_url, title, in_table_cell);
+
+                prepared.push(Event::Start(Tag::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }));
+            },
+            //
+            // Write emphasis and strong elements with the delimiters chosen by
+            // emphasis_delimiters(), so that nested elements parse back
+            // unchanged.
+            //
+            Event::Start(tag @ (Tag::Emphasis | Tag::Strong)) => {
+                let delimiter = emphasis_delimiters
+                    .next()
+                    .expect("emphasis Start event without a delimiter");
+
+                delimiters.push(delimiter);
+
+                match delimiter {
+                    Some(delimiter)
+                        if delimiter == cmark_options.emphasis_token =>
+                    {
+                        prepared.push(Event::Start(tag))
+                    },
+                    Some(delimiter) => {
+                        let strong = tag == Tag::Strong;
+                        prepared.push(raw(delimiter_run(delimiter, strong)));
+                    },
+                    None => (),
+                }
+            },
+            Event::End(tag @ (TagEnd::Emphasis | TagEnd::Strong)) => {
+                let delimiter =
+                    delimiters.pop().expect("unbalanced emphasis End event");
+
+                match delimiter {
+                    Some(delimiter)
+                        if delimiter == cmark_options.emphasis_token =>
+                    {
+                        prepared.push(Event::End(tag))
+                    },
+                    Some(delimiter) => {
+                        let strong = tag == TagEnd::Strong;
+                        prepared.push(raw(delimiter_run(delimiter, strong)));
+                    },
+                    None => (),
+                }
+            },
+            event => prepared.push(event),
+        }
+
+        follows_table_head = is_table_head_end;
+        follows_line_start = is_line_start;
+    }
+
+    if options.sort_reference_definitions {
+        definitions.sort_by_cached_key(|(label, _, _)| label.to_lowercase());
+    }
+
+    if !definitions.is_empty() {
+        let definitions: Vec<String> = definitions
+            .iter()
+            .map(|(label, dest_url, title)| {
+                link_reference_definition(label, dest_url, title)
+            })
+            .collect();
+
+        prepared.push(Event::Start(Tag::Paragraph));
+        prepared.push(raw(definitions.join("\n")));
+        prepared.push(Event::End(TagEnd::Paragraph));
+    }
+
+    prepared
+}
+
+/// Add a link reference definition for `label` to `definitions`, unless one
+/// has already been added. Labels are matched case-insensitively.
+fn add_definition<'e>(
+    definitions: &mut Vec<(CowStr<'e>, CowStr<'e>, CowStr<'e>)>,
+    label: &CowStr<'e>,
+    dest_url: CowStr<'e>,
+    title: CowStr<'e>,
+) {
+    let is_defined = definitions.iter().any(|(defined_label, _, _)| {
+        defined_label.to_lowercase() == label.to_lowercase()
+    });
+
+    if !is_defined {
+        definitions.push((
+            label.clone(),
+            escape_character_references(dest_url),
+            escape_character_references(title),
+        ));
+    }
+}
+
+/// Returns the Markdown that closes the text of a reference link or image of
+/// kind `link_type`, e.g. `][label]`.
+fn reference_close(link_type: LinkType, label: &str) -> String {
+    match link_type {
+        LinkType::Reference => format!("][{label}]"),
+        LinkType::Collapsed => String::from("][]"),
+        _ => String::from("]"),
+    }
+}
+
+/// Returns the Markdown for a link reference definition, e.g.
+/// `[label]: example.org "Title"`.
+///
+/// CommonMark: [link reference definitions](https://spec.commonmark.org/0.30/#link-reference-definitions)
+fn link_reference_definition(
+    label: &str,
+    dest_url: &str,
+    title: &str,
+) -> String {
+    let mut definition = format!("[{label}]: ");
+
+    if dest_url.is_empty()
+        || dest_url.contains(' ')
+        || dest_url.starts_with('<')
+    {
+        let dest_url = dest_url.replace('<', "\\<").replace('>', "\\>");
+        definition.push_str(&format!("<{dest_url}>"));
+    } else {
+        definition.push_str(dest_url);
+    }
+
+    if !title.is_empty() {
+        let title = title.replace('\\', "\\\\").replace('"', "\\\"");
+        definition.push_str(&format!(" \"{title}\""));
+    }
+
+    definition
+}
+
+/// Characters that are escaped wherever they appear in text.
+///
+/// `<` is escaped only where it could start inline HTML or an autolink, see
+/// [`could_start_tag()`].
+const ESCAPED_CHARACTERS: &str = "\\*_`[]~$";
+
+/// Characters that are escaped only when they begin a line of text, where they
+/// would otherwise start a heading, block quote, or list item, or underline the
+/// preceding line as a setext heading.
+const LINE_START_CHARACTERS: &str = "#>-+=";
+
+/// Returns `true` if `char`, preceded on its line by `number` and followed by
+/// `rest`, is the `.` or `)` delimiter of an ordered list item marker, e.g.
+/// `1.` or `3)`.
+///
+/// CommonMark: [ordered list markers](https://spec.commonmark.org/0.30/#ordered-list-marker)
+fn is_ordered_list_delimiter(number: &str, char: char, rest: &str) -> bool {
+    let is_number = (1..=9).contains(&number.len())
+        && number.chars().all(|c| c.is_ascii_digit());
+
+    let ends_marker = rest.is_empty() || rest.starts_with([' ', '\t']);
+
+    return (char == '.' || char == ')') && is_number && ends_marker;
+}
+
+/// Returns `true` if a `<` followed by `rest` could be parsed as the start of
+/// an HTML tag, comment, or autolink.
+///
+/// A `<` at the end of a text event is treated as if it could, since the text
+/// that follows it is not known.
+fn could_start_tag(rest: &str) -> bool {
+    match rest.chars().next() {
+        Some(char) => char.is_ascii_alphabetic() || "/!?".contains(char),
+        None => true,
+    }
+}
+
+/// Escape each `$` in the source of inline or display math that is not already
+/// escaped by a preceding backslash.
+///
+/// The parser keeps the backslash of an escaped `$` in the math source, so math
+/// that was parsed from Markdown is written back out unchanged.
+fn escape_math_dollars(math: &str) -> String {
+    let mut escaped = String::with_capacity(math.len());
+
+    // True if the previous character is an unescaped backslash.
+    let mut follows_backslash = false;
+
+    for char in math.chars() {
+        if char == '$' && !follows_backslash {
+            escaped.push('\\');
+        }
+
+        follows_backslash = char == '\\' && !follows_backslash;
+
+        escaped.push(char);
+    }
+
+    escaped
+}
+
+/// Returns `true` if `char` is an underscore between two alphanumeric
+/// characters in a bare `http://` or `https://` URL, e.g. `https://x.org/a_b`.
+///
+/// Such an underscore cannot begin or end emphasis, so it is not escaped,
+/// keeping the URL intact for renderers that detect bare URLs.
+fn is_bare_url_underscore(before: &str, char: char, rest: &str) -> bool {
+    if char != '_' {
+        return false;
+    }
+
+    let is_intraword = before.ends_with(char::is_alphanumeric)
+        && rest.starts_with(char::is_alphanumeric);
+
+    // The word containing `char`, excluding any leading punctuation, e.g. an
+    // opening parenthesis.
+    let word = before
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(|c: char| !c.is_alphanumeric());
+
+    let in_url = ["http://", "https://"]
+        .iter()
+        .any(|scheme| word.starts_with(scheme));
+
+    return is_intraword && in_url;
+}
+
+/// Push `text` as one or more [`Event::Text`]s, escaping each character for
+/// which `needs_escape` returns `true`.
+///
+/// `needs_escape` is called with the byte index of each character in `text`,
+/// the part of `text` preceding it, the character, and the rest of `text`
+/// following it.
+///
+/// `pulldown_cmark_to_cmark` escapes only the first character of a text event,
+/// and only if it is one of its `special_characters`, so a new text event is
+/// started at every character that needs escaping. Characters that
+/// `pulldown_cmark_to_cmark` would not escape are preceded by an explicit
+/// backslash.
+fn push_escaped_text<'e>(
+    prepared: &mut Vec<Event<'e>>,
+    text: CowStr<'e>,
+    special_characters: &str,
+    needs_escape: impl Fn(usize, &str, char, &str) -> bool,
+) {
+    let mut escapes = text
+        .char_indices()
+        .filter(|&(index, char)| {
+            needs_escape(
+                index,
+                &text[..index],
+                char,
+                &text[index + char.len_utf8()..],
+            )
+        })
+        .peekable();
+
+    if escapes.peek().is_none() {
+        prepared.push(Event::Text(text));
+        return;
+    }
+
+    let mut piece_start = 0;
+
+    for (index, char) in escapes {
+        if index > piece_start {
+            let piece = text[piece_start..index].to_owned();
+            prepared.push(Event::Text(CowStr::from(piece)));
+        }
+
+        if !special_characters.contains(char) {
+            prepared.push(raw(String::from("\\")));
+        }
+
+        piece_start = index;
+    }
+
+    let piece = text[piece_start..].to_owned();
+    prepared.push(Event::Text(CowStr::from(piece)));
+}
+
+/// Escape the `!` at the end of the [`Event::Text`] last pushed to `prepared`,
+/// if there is one, so that it does not combine with a following `[` to start
+/// an image.
+fn escape_trailing_exclamation_mark(
+    prepared: &mut Vec<Event>,
+    special_characters: &str,
+) {
+    let text = match prepared.last() {
+        Some(Event::Text(text)) if text.ends_with('!') => text.clone(),
+        _ => return,
+    };
+
+    prepared.pop();
+
+    let before = &text[..text.len() - 1];
+
+    if !before.is_empty() {
+        prepared.push(Event::Text(CowStr::from(before.to_owned())));
+    }
+
+    if !special_characters.contains('!') {
+        prepared.push(raw(String::from("\\")));
+    }
+
+    prepared.push(Event::Text(CowStr::Borrowed("!")));
+}
+
+/// Write out `events`, as prepared by [`prepare_events()`], as a Markdown string.
+///
+/// `pulldown_cmark_to_cmark` ends a code block, block quote, heading, or table
+/// with a blank line, even at the end of an item of a tight list, where the
+/// blank line would make the list loose. So each item of a tight list is
+/// written out separately, followed by a single newline.
+pub(crate) fn write_events(
+    events: &[Event],
+    cmark_options: &pulldown_cmark_to_cmark::Options,
+) -> String {
+    let mut string = String::new();
+    let mut state: Option<pulldown_cmark_to_cmark::State> = None;
+
+    // The index of the first event not yet written out.
+    let mut written: usize = 0;
+
+    let mut list_tightness = list_tightness(events).into_iter();
+    let mut tight_lists: Vec<bool> = Vec::new();
+
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::List(_)) => tight_lists
+                .push(list_tightness.next().expect("missing list tightness")),
+            Event::End(TagEnd::List(_)) => {
+                tight_lists.pop();
+            },
+            Event::End(TagEnd::Item) if tight_lists.last() == Some(&true) => {
+                let mut item_state =
+                    pulldown_cmark_to_cmark::cmark_resume_with_options(
+                        events[written..=index].iter(),
+                        &mut string,
+                        state,
+                        cmark_options.clone(),
+                    )
+                    .expect(
+                        "error converting Event sequent to Markdown string",
+                    );
+
+                item_state.newlines_before_start = 1;
+
+                state = Some(item_state);
+                written = index + 1;
+            },
+            _ => (),
+        }
+    }
+
+    let _: pulldown_cmark_to_cmark::State =
+        pulldown_cmark_to_cmark::cmark_resume_with_options(
+            events[written..].iter(),
+            &mut string,
+            state,
+            cmark_options.clone(),
+        )
+        .and_then(|state| state.finalize(&mut string))
+        .expect("error converting Event sequent to Markdown string");
+
+    return string;
+}
+
+/// Returns whether each list in `events` is tight, in the order of their `Start`
+/// events.
+///
+/// The paragraphs in the items of a tight list are not wrapped in
+/// `Start(Tag::Paragraph)` / `End(_)` events.
+fn list_tightness(events: &[Event]) -> Vec<bool> {
+    let mut tightness: Vec<bool> = Vec::new();
+
+    // The currently open tags, with the index in `tightness` of each open list.
+    let mut open: Vec<(&Tag, Option<usize>)> = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Start(tag) => {
+                if let (Tag::Paragraph, [.., (_, Some(list)), (Tag::Item, _)]) =
+                    (tag, open.as_slice())
+                {
+                    tightness[*list] = false;
+                }
+
+                let list = match tag {
+                    Tag::List(_) => {
+                        tightness.push(true);
+                        Some(tightness.len() - 1)
+                    },
+                    _ => None,
+                };
+
+                open.push((tag, list));
+            },
+            Event::End(_) => {
+                open.pop();
+            },
+            _ => (),
+        }
+    }
+
+    return tightness;
+}
+
+/// Returns the delimiter character, `*` or `_`, to write for each emphasis and
+/// strong element in `events`, in the order of their `Start` events, or `None`
+/// if the delimiters of the element are to be omitted.
+///
+/// An element nested within an element of the same kind, immediately following
+/// the opening of another element, or immediately following another element,
+/// uses a different delimiter than it. Otherwise the delimiter runs could be
+/// misparsed; e.g. `Emphasis(Emphasis(x))` written as `**x**` would parse as
+/// `Strong(x)`, and two adjacent emphasis elements written as `*a**b*` would
+/// parse as a single one.
+///
+/// `_` cannot open or close emphasis within a word, so `*` is used for an
+/// element that is preceded or followed by an alphanumeric character, and `_`
+/// for the elements it must differ from. Otherwise `default` is preferred.
+///
+/// Within a word, any delimiter directly following an opening `*` (or directly
+/// preceding a closing `*`) would prevent it from opening (or closing), so an
+/// element nested directly inside such an element shares its run of `*`s, e.g.
+/// `a***b***c` for `Emphasis(Strong(b))`. Emphasis nested directly inside
+/// emphasis cannot be written this way, as `**` would parse as strong, so its
+/// delimiters are omitted, writing e.g. `a*b*c`.
+fn emphasis_delimiters(events: &[Event], default: char) -> Vec<Option<char>> {
+    struct Element {
+        strong: bool,
+        parent: Option<usize>,
+        // True if this element is preceded or followed, respectively, by an
+        // alphanumeric character.
+        opens_intraword: bool,
+        closes_intraword: bool,
+        // True if this element immediately follows the opening of its parent,
+        // or immediately precedes its closing, respectively.
+        follows_parent_start: bool,
+        precedes_parent_end: bool,
+        // The elements whose delimiter this element must differ from.
+        differs_from: Vec<usize>,
+    }
+
+    let mut elements: Vec<Element> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+
+    // The most recently closed element.
+    let mut last_closed: Option<usize> = None;
+
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            Event::Start(tag @ (Tag::Emphasis | Tag::Strong)) => {
+                let strong = *tag == Tag::Strong;
+                let parent = open.last().copied();
+                let previous = index.checked_sub(1).map(|index| &events[index]);
+
+                let follows_parent_start = matches!(
+                    previous,
+                    Some(Event::Start(Tag::Emphasis | Tag::Strong))
+                );
+
+                let mut differs_from: Vec<usize> = open
+                    .iter()
+                    .copied()
+                    .filter(|&outer| elements[outer].strong == strong)
+                    .collect();
+
+                let adjacent = match previous {
+                    Some(Event::Start(Tag::Emphasis | Tag::Strong)) => parent,
+                    Some(Event::End(TagEnd::Emphasis | TagEnd::Strong)) => {
+                        last_closed
+                    },
+                    _ => None,
+                };
+
+                if let Some(adjacent) = adjacent {
+                    if !differs_from.contains(&adjacent) {
+                        differs_from.push(adjacent);
+                    }
+                }
+
+                let opens_intraword = matches!(
+                    previous,
+                    Some(Event::Text(text))
+                        if text.ends_with(char::is_alphanumeric)
+                );
+
+                elements.push(Element {
+                    strong,
+                    parent,
+                    opens_intraword,
+                    closes_intraword: false,
+                    follows_parent_start,
+                    precedes_parent_end: false,
+                    differs_from,
+                });
+                open.push(elements.len() - 1);
+            },
+            Event::End(TagEnd::Emphasis | TagEnd::Strong) => {
+                let element =
+                    open.pop().expect("unbalanced emphasis End event");
+
+                match events.get(index + 1) {
+                    Some(Event::Text(text))
+                        if text.starts_with(char::is_alphanumeric) =>
+                    {
+                        elements[element].closes_intraword = true
+                    },
+                    Some(Event::End(TagEnd::Emphasis | TagEnd::Strong)) => {
+                        elements[element].precedes_parent_end = true
+                    },
+                    _ => (),
+                }
+
+                last_closed = Some(element);
+            },
+            _ => (),
+        }
+    }
+
+    // Whether each element opens or closes within a word, including by
+    // sharing a run of delimiters with a parent that does, and whether it
+    // shares its parent's run. Parents are visited before their children.
+    let mut opens_intraword: Vec<bool> = Vec::with_capacity(elements.len());
+    let mut closes_intraword: Vec<bool> = Vec::with_capacity(elements.len());
+    let mut shares_parent_run: Vec<bool> = Vec::with_capacity(elements.len());
+
+    for element in &elements {
+        let (opens, closes) = match element.parent {
+            Some(parent) => (
+                element.follows_parent_start && opens_intraword[parent],
+                element.precedes_parent_end && closes_intraword[parent],
+            ),
+            None => (false, false),
+        };
+
+        opens_intraword.push(element.opens_intraword || opens);
+        closes_intraword.push(element.closes_intraword || closes);
+        shares_parent_run.push(opens || closes);
+    }
+
+    let is_intraword =
+        |index: usize| opens_intraword[index] || closes_intraword[index];
+
+    // The delimiter preferred by each element. Nested and later elements are
+    // visited first, so that an element that must use `*` can ask the
+    // elements it must differ from to use `_`.
+    let mut preferred: Vec<Option<char>> = (0..elements.len())
+        .map(|index| is_intraword(index).then_some('*'))
+        .collect();
+
+    for (index, element) in elements.iter().enumerate().rev() {
+        if shares_parent_run[index] {
+            continue;
+        }
+
+        if let Some(delimiter) = preferred[index] {
+            for &other in &element.differs_from {
+                if !is_intraword(other) {
+                    preferred[other] = Some(alternate_delimiter(delimiter));
+                }
+            }
+        }
+    }
+
+    let mut delimiters: Vec<Option<char>> = Vec::with_capacity(elements.len());
+
+    for (index, element) in elements.iter().enumerate() {
+        if shares_parent_run[index] {
+            let parent = &elements[element.parent.unwrap()];
+
+            let delimiter = match (parent.strong, element.strong) {
+                (false, false) => None,
+                _ => Some('*'),
+            };
+
+            delimiters.push(delimiter);
+            continue;
+        }
+
+        let is_taken = |delimiter: char| {
+            element
+                .differs_from
+                .iter()
+                .any(|&other| delimiters[other] == Some(delimiter))
+        };
+
+        let delimiter = preferred[index].unwrap_or(default);
+
+        let delimiter = if is_taken(delimiter)
+            && !is_taken(alternate_delimiter(delimiter))
+        {
+            alternate_delimiter(delimiter)
+        } else {
+            delimiter
+        };
+
+        delimiters.push(Some(delimiter));
+    }
+
+    return delimiters;
+}
+
+fn alternate_delimiter(delimiter: char) -> char {
+    match delimiter {
+        '*' => '_',
+        _ => '*',
+    }
+}
+
+fn delimiter_run(delimiter: char, strong: bool) -> String {
+    let count = if strong { 2 } else { 1 };
+
+    delimiter.to_string().repeat(count)
+}
+
+fn alert_marker(kind: BlockQuoteKind) -> &'static str {
+    match kind {
+        BlockQuoteKind::Note => "NOTE",
+        BlockQuoteKind::Tip => "TIP",
+        BlockQuoteKind::Important => "IMPORTANT",
+        BlockQuoteKind::Warning => "WARNING",
+        BlockQuoteKind::Caution => "CAUTION",
+    }
+}
+
+/// Push the lines of `code` from an indented code block as [`Event::Html`]s,
+/// each indented by four spaces.
+///
+/// `pulldown_cmark_to_cmark` writes [`Event::Html`] verbatim, apart from
+/// indenting each line to the depth of any enclosing block quote or list item.
+/// Blank lines are written without indentation, to avoid trailing whitespace.
+fn push_indented_code<'e>(prepared: &mut Vec<Event<'e>>, code: &str) {
+    for line in code.split_inclusive('\n') {
+        let line = if line.trim_end_matches(['\r', '\n']).is_empty() {
+            line.to_owned()
+        } else {
+            format!("    {line}")
+        };
+
+        prepared.push(Event::Html(CowStr::from(line)));
+    }
+}
+
+/// Replace each tab in `text` with enough spaces to reach the next tab stop.
+///
+/// `column` is the column at which `text` starts, and is updated to the column
+/// at which it ends.
+fn expand_tabs(text: &str, tab_width: usize, column: &mut usize) -> String {
+    let mut expanded = String::with_capacity(text.len());
+
+    for char in text.chars() {
+        match char {
+            '\t' => {
+                let spaces = tab_width - (*column % tab_width.max(1));
+                expanded.push_str(&" ".repeat(spaces));
+                *column += spaces;
+            },
+            '\n' => {
+                expanded.push(char);
+                *column = 0;
+            },
+            _ => {
+                expanded.push(char);
+                *column += 1;
+            },
+        }
+    }
+
+    expanded
+}
+
+/// Returns the byte ranges of the bare `http://` and `https://` URLs in `text`.
+///
+/// A URL extends up to the next whitespace or `<` character, excluding any
+/// trailing punctuation, e.g. the period ending a sentence, and any unmatched
+/// closing parentheses.
+fn find_bare_urls(text: &str) -> Vec<Range<usize>> {
+    let mut urls = Vec::new();
+    let mut search_start = 0;
+
+    while let Some(found) = text[search_start..].find("http") {
+        let start = search_start + found;
+        let rest = &text[start..];
+
+        let is_word_start =
+            !text[..start].ends_with(|c: char| c.is_alphanumeric() || c == '/');
+
+        let after_scheme = rest
+            .strip_prefix("https://")
+            .or_else(|| rest.strip_prefix("http://"));
+
+        let Some(after_scheme) = after_scheme.filter(|_| is_word_start) else {
+            search_start = start + "http".len();
+            continue;
+        };
+
+        let scheme_len = rest.len() - after_scheme.len();
+        let url_len = rest
+            .find(|c: char| c.is_whitespace() || c == '<')
+            .unwrap_or(rest.len());
+
+        let mut url = &rest[..url_len];
+
+        loop {
+            let trimmed =
+                url.trim_end_matches(|c: char| ".,:;!?'\"".contains(c));
+
+            let trimmed = match trimmed.strip_suffix(')') {
+                Some(without_paren)
+                    if trimmed.matches('(').count()
+                        < trimmed.matches(')').count() =>
+                {
+                    without_paren
+                },
+                _ => trimmed,
+            };
+
+            if trimmed.len() == url.len() {
+                break;
+            }
+
+            url = trimmed;
+        }
+
+        if url.len() > scheme_len {
+            urls.push(start..start + url.len());
+        }
+
+        search_start = start + url_len.max(1);
+    }
+
+    urls
+}
+
+/// Escape the destination and title of a link or image.
+///
+/// Pipes are escaped only if the link is in a table cell, where they would
+/// otherwise be treated as column separators.
+fn escape_link_fields<'e>(
+    dest_url: CowStr<'e>,
+    title: CowStr<'e>,
+    in_table_cell: bool,
+) -> (CowStr<'e>, CowStr<'e>) {
+    let dest_url = escape_character_references(dest_url);
+    let title = escape_character_references(title);
+
+    if !in_table_cell {
+        return (dest_url, title);
+    }
+
+    let escape_pipes = |text: CowStr<'e>, escaped_pipe: &str| {
+        if text.contains('|') {
+            CowStr::from(text.replace('|', escaped_pipe))
+        } else {
+            text
+        }
+    };
+
+    // NOTE:
+    //  `pulldown_cmark_to_cmark` escapes backslashes in titles, so a `\|`
+    //  escape would be written as `\\|`. Use a character reference instead.
+    return (escape_pipes(dest_url, "\\|"), escape_pipes(title, "&#124;"));
+}
+
+/// Replace each `&` in `text` that begins a character reference, e.g. `&copy;`
+/// or `&#65;`, with `&amp;`.
+fn escape_character_references(text: CowStr) -> CowStr {
+    let is_reference_start =
+        |index: usize| starts_with_character_reference(&text[index + 1..]);
+
+    if !text
+        .match_indices('&')
+        .any(|(index, _)| is_reference_start(index))
+    {
+        return text;
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    let mut piece_start = 0;
+
+    for (index, _) in text.match_indices('&') {
+        if is_reference_start(index) {
+            escaped.push_str(&text[piece_start..index]);
+            escaped.push_str("&amp;");
+            piece_start = index + 1;
+        }
+    }
+
+    escaped.push_str(&text[piece_start..]);
+
+    CowStr::from(escaped)
+}
+
+/// Returns `true` if `text` starts with the part of a character reference that
+/// follows the `&`, e.g. `copy;`, `#65;`, or `#x41;`.
+///
+/// CommonMark: [entity and numeric character references](https://spec.commonmark.org/0.30/#entity-and-numeric-character-references)
+fn starts_with_character_reference(text: &str) -> bool {
+    let (digits, is_digit, max_len): (&str, fn(char) -> bool, usize) =
+        if let Some(hex) = text.strip_prefix("#x").or(text.strip_prefix("#X")) {
+            (hex, |c| c.is_ascii_hexdigit(), 6)
+        } else if let Some(decimal) = text.strip_prefix('#') {
+            (decimal, |c| c.is_ascii_digit(), 7)
+        } else if text.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            (text, |c| c.is_ascii_alphanumeric(), usize::MAX)
+        } else {
+            return false;
+        };
+
+    let len = digits.chars().take_while(|&c| is_digit(c)).count();
+
+    (1..=max_len).contains(&len) && digits[len..].starts_with(';')
+}
+
+fn add_header_width(header_widths: &mut [usize], width: usize) {
+    if let Some(last) = header_widths.last_mut() {
+        *last += width;
+    }
+}
+
+/// Returns the delimiter row cell for a column with the specified `alignment`,
+/// e.g. `:---:`.
+///
+/// The cell is `width` characters wide, widened if necessary so that there is
+/// always at least one `-` after the leading and before the trailing colon.
+fn delimiter_cell(alignment: Alignment, width: usize) -> String {
+    let (left, right) = match alignment {
+        Alignment::None => ("", ""),
+        Alignment::Left => (":", ""),
+        Alignment::Center => (":", ":"),
+        Alignment::Right => ("", ":"),
+    };
+
+    let dashes = width.saturating_sub(left.len() + right.len()).max(1);
+
+    format!("{left}{}{right}", "-".repeat(dashes))
+}
+
+/// Pad the cells of the prepared events of a `table` with spaces, so that the
+/// pipes separating its columns line up, and write its delimiter row to match.
+///
+/// The content of each cell is aligned within its column according to the
+/// column's alignment.
+fn pad_table_columns<'e>(
+    table: Vec<Event<'e>>,
+    alignments: &[Alignment],
+    cmark_options: &pulldown_cmark_to_cmark::Options,
+) -> Vec<Event<'e>> {
+    // The written width of the content of each cell, in order.
+    let mut cell_widths: Vec<usize> = Vec::new();
+    let mut cell_start: Option<usize> = None;
+
+    for (index, event) in table.iter().enumerate() {
+        match event {
+            Event::Start(Tag::TableCell) => cell_start = Some(index + 1),
+            Event::End(TagEnd::TableCell) => {
+                let start = cell_start.take().expect("unbalanced table cell");
+
+                let mut content = String::new();
+                let _: pulldown_cmark_to_cmark::State =
+                    pulldown_cmark_to_cmark::cmark_with_options(
+                        table[start..index].iter(),
+                        &mut content,
+                        cmark_options.clone(),
+                    )
+                    .expect("error converting table cell to Markdown string");
+
+                cell_widths.push(content.chars().count());
+            },
+            _ => (),
+        }
+    }
+
+    // The width of each column, at least wide enough for a valid delimiter
+    // cell.
+    let mut column_widths: Vec<usize> = alignments
+        .iter()
+        .map(|&alignment| delimiter_cell(alignment, 0).len())
+        .collect();
+
+    let mut column = 0;
+    let mut cell_widths_iter = cell_widths.iter();
+
+    for event in &table {
+        match event {
+            Event::Start(Tag::TableHead | Tag::TableRow) => column = 0,
+            Event::End(TagEnd::TableCell) => {
+                let width = *cell_widths_iter.next().unwrap();
+
+                if column >= column_widths.len() {
+                    column_widths.push(1);
+                }
+                column_widths[column] = column_widths[column].max(width);
+                column += 1;
+            },
+            _ => (),
+        }
+    }
+
+    let mut padded: Vec<Event<'e>> = Vec::with_capacity(table.len());
+    let mut cell_widths = cell_widths.into_iter();
+
+    // The padding to write at the end of the current cell.
+    let mut padding_after = 0;
+
+    for event in table {
+        match event {
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                column = 0;
+                padded.push(event);
+            },
+            Event::Start(Tag::TableCell) => {
+                let width = cell_widths.next().unwrap();
+                let padding = column_widths[column] - width;
+
+                let alignment =
+                    alignments.get(column).copied().unwrap_or(Alignment::None);
+
+                let (before, after) = match alignment {
+                    Alignment::None | Alignment::Left => (0, padding),
+                    Alignment::Center => (padding / 2, padding - padding / 2),
+                    Alignment::Right => (padding, 0),
+                };
+
+                padded.push(event);
+                padded.push(raw(" ".repeat(before + 1)));
+
+                padding_after = after + 1;
+            },
+            Event::End(TagEnd::TableCell) => {
+                padded.push(raw(" ".repeat(padding_after)));
+                padded.push(event);
+                column += 1;
+            },
+            // Close the header row, and write the delimiter row in place of
+            // the one `pulldown_cmark_to_cmark` would have written.
+            Event::End(TagEnd::TableHead) => {
+                let delimiters: String = column_widths
+                    .iter()
+                    .enumerate()
+                    .map(|(column, &width)| {
+                        let alignment = alignments
+                            .get(column)
+                            .copied()
+                            .unwrap_or(Alignment::None);
+
+                        // Plus two for the spaces around the cell content.
+                        format!("|{}", delimiter_cell(alignment, width + 2))
+                    })
+                    .collect();
+
+                padded.push(raw(format!("|\n{delimiters}|")));
+            },
+            _ => padded.push(event),
+        }
+    }
+
+    padded
+}
+
+/// Returns `true` if `event` is part of the inline content of a block.
+fn is_inline_event(event: &Event) -> bool {
+    return matches!(
+        event,
+        Event::Text(_)
+            | Event::Code(_)
+            | Event::InlineMath(_)
+            | Event::DisplayMath(_)
+            | Event::InlineHtml(_)
+            | Event::FootnoteReference(_)
+            | Event::SoftBreak
+            | Event::HardBreak
+            | Event::TaskListMarker(_)
+            | Event::End(
+                TagEnd::Emphasis
+                    | TagEnd::Strong
+                    | TagEnd::Strikethrough
+                    | TagEnd::Link
+                    | TagEnd::Image
+            )
+    );
+}
+
+/// Construct an event that will be written out verbatim.
+fn raw<'e>(markdown: String) -> Event<'e> {
+    Event::InlineHtml(CowStr::from(markdown))
+}
@@ -0,0 +1,163 @@
+//! Convert AST [`Inlines`] into styled [`ratatui`] terminal text.
+//!
+//! This module is only available when the `ratatui` feature is enabled.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+use crate::{Inline, Inlines};
+
+/// The style added to code spans and math.
+const CODE_STYLE: Style = Style::new().fg(Color::Yellow);
+
+/// Convert `inlines` into styled [`ratatui`] [`Text`], for rendering inline
+/// Markdown content in a terminal.
+///
+/// Formatting is mapped to styles as follows:
+///
+/// | Inline                         | Style                                  |
+/// |--------------------------------|----------------------------------------|
+/// | [`Inline::Emphasis`]           | [`Modifier::ITALIC`]                   |
+/// | [`Inline::Strong`]             | [`Modifier::BOLD`]                     |
+/// | [`Inline::Strikethrough`]      | [`Modifier::CROSSED_OUT`]              |
+/// | [`Inline::Code`], math         | Yellow foreground                      |
+/// | [`Inline::Link`]               | [`Modifier::UNDERLINED`]               |
+///
+/// Styles of nested inlines are combined, e.g. a link inside strong text is
+/// both bold and underlined. Images are written as their description. An
+/// [`Inline::SoftBreak`] is written as a space, and an [`Inline::HardBreak`]
+/// starts a new [`Line`]. HTML and footnote references are omitted. Adjacent
+/// text with the same style is merged into a single [`Span`].
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{inlines_to_ratatui_text, Inline, Inlines};
+/// use ratatui::{
+///     style::{Style, Stylize},
+///     text::{Line, Span, Text},
+/// };
+///
+/// let inlines = Inlines(vec![
+///     Inline::plain_text("Some "),
+///     Inline::strong(Inline::plain_text("bold")),
+///     Inline::plain_text(" text"),
+/// ]);
+///
+/// assert_eq!(
+///     inlines_to_ratatui_text(&inlines),
+///     Text::from(Line::from(vec![
+///         Span::raw("Some "),
+///         Span::styled("bold", Style::new().bold()),
+///         Span::raw(" text"),
+///     ]))
+/// );
+/// ```
+pub fn inlines_to_ratatui_text(inlines: &Inlines) -> Text<'static> {
+    let mut lines: Vec<Line<'static>> = vec![Line::default()];
+
+    push_inlines(&mut lines, inlines, Style::new());
+
+    Text::from(lines)
+}
+
+//======================================
+// Implementation
+//======================================
+
+fn push_inlines(
+    lines: &mut Vec<Line<'static>>,
+    Inlines(inlines): &Inlines,
+    style: Style,
+) {
+    for inline in inlines {
+        push_inline(lines, inline, style);
+    }
+}
+
+fn push_inline(lines: &mut Vec<Line<'static>>, inline: &Inline, style: Style) {
+    match inline {
+        Inline::Text(text) => push_span(lines, text, style),
+        Inline::Emphasis(children) => {
+            push_inlines(lines, children, style.add_modifier(Modifier::ITALIC))
+        },
+        Inline::Strong(children) => {
+            push_inlines(lines, children, style.add_modifier(Modifier::BOLD))
+        },
+        Inline::Strikethrough(children) => push_inlines(
+            lines,
+            children,
+            style.add_modifier(Modifier::CROSSED_OUT),
+        ),
+        Inline::Code(code)
+        | Inline::InlineMath(code)
+        | Inline::DisplayMath(code) => {
+            push_span(lines, code, style.patch(CODE_STYLE))
+        },
+        Inline::Link { content_text, .. } => push_inlines(
+            lines,
+            content_text,
+            style.add_modifier(Modifier::UNDERLINED),
+        ),
+        Inline::Image {
+            image_description, ..
+        } => push_inlines(lines, image_description, style),
+        Inline::SoftBreak => push_span(lines, " ", style),
+        Inline::HardBreak => lines.push(Line::default()),
+        Inline::FootnoteReference(_) | Inline::InlineHtml(_) => (),
+        Inline::Positioned(_, inline) => push_inline(lines, inline, style),
+    }
+}
+
+/// Append `text` with `style` to the last line in `lines`, merging it with the
+/// last span of that line if it has the same style.
+fn push_span(lines: &mut [Line<'static>], text: &str, style: Style) {
+    let line = lines.last_mut().expect("lines is never empty");
+
+    match line.spans.last_mut() {
+        Some(span) if span.style == style => {
+            span.content.to_mut().push_str(text)
+        },
+        _ => line.spans.push(Span::styled(text.to_owned(), style)),
+    }
+}
+
+//======================================
+// Tests
+//======================================
+
+#[test]
+fn test_inlines_to_ratatui_text() {
+    use crate::{markdown_to_ast, Block};
+    use pretty_assertions::assert_eq;
+
+    let ast = markdown_to_ast(concat!(
+        "Some *emphasis*, **strong [link](example.org)**, and `code`  \n",
+        "next\nline",
+    ));
+
+    let [Block::Paragraph(inlines)] = ast.as_slice() else {
+        panic!()
+    };
+
+    let italic = Style::new().add_modifier(Modifier::ITALIC);
+    let bold = Style::new().add_modifier(Modifier::BOLD);
+
+    assert_eq!(
+        inlines_to_ratatui_text(inlines),
+        Text::from(vec![
+            Line::from(vec![
+                Span::raw("Some "),
+                Span::styled("emphasis", italic),
+                Span::raw(", "),
+                Span::styled("strong ", bold),
+                Span::styled("link", bold.add_modifier(Modifier::UNDERLINED)),
+                Span::raw(", and "),
+                Span::styled("code", CODE_STYLE),
+            ]),
+            Line::from("next line"),
+        ])
+    );
+}
@@ -1,20 +1,86 @@
+//! Convert between a flat sequence of [`Event`]s and a tree of nested events.
+
 use pulldown_cmark::{Event, Tag};
 
+use crate::markdown_to_events;
+
 //======================================
 // Representation
 //======================================
 
-#[derive(Debug)]
-pub(crate) enum UnflattenedEvent<'a> {
-    /// This [`Event`] can never by [`Event::Start`] or [`Event::End`]. Those events
-    /// are represented by
+/// A hierarchical representation of a sequence of [`Event`]s, in which the
+/// events between each [`Event::Start`] and its matching [`Event::End`] are
+/// nested inside a single [`EventTree::Nested`].
+///
+/// This sits between the flat [`Event`] stream and the AST
+/// [`Block`][crate::Block]s, e.g. for inspecting raw HTML events in the context
+/// of the blocks that contain them.
+///
+/// See [`markdown_to_event_tree()`] and [`event_tree_to_events()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventTree<'a> {
+    /// This [`Event`] can never be [`Event::Start`] or [`Event::End`]. Those
+    /// events are represented by [`EventTree::Nested`].
     Event(Event<'a>),
+    /// The events between an [`Event::Start`] of `tag` and the matching
+    /// [`Event::End`].
     Nested {
         tag: Tag<'a>,
-        events: Vec<UnflattenedEvent<'a>>,
+        events: Vec<EventTree<'a>>,
     },
 }
 
+pub(crate) type UnflattenedEvent<'a> = EventTree<'a>;
+
+/// Parse Markdown input string into a tree of [`EventTree`]s.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ast::{markdown_to_event_tree, EventTree};
+/// use pulldown_cmark::{Event, Tag};
+///
+/// assert_eq!(
+///     markdown_to_event_tree("Some `code`"),
+///     vec![EventTree::Nested {
+///         tag: Tag::Paragraph,
+///         events: vec![
+///             EventTree::Event(Event::Text("Some ".into())),
+///             EventTree::Event(Event::Code("code".into())),
+///         ],
+///     }]
+/// );
+/// ```
+pub fn markdown_to_event_tree(input: &str) -> Vec<EventTree<'_>> {
+    parse_markdown_to_unflattened_events(markdown_to_events(input))
+}
+
+/// Flatten a tree of [`EventTree`]s back into a sequence of [`Event`]s,
+/// including the [`Event::Start`] and [`Event::End`] of each
+/// [`EventTree::Nested`].
+pub fn event_tree_to_events<'a>(trees: Vec<EventTree<'a>>) -> Vec<Event<'a>> {
+    fn flatten<'a>(trees: Vec<EventTree<'a>>, events: &mut Vec<Event<'a>>) {
+        for tree in trees {
+            match tree {
+                EventTree::Event(event) => events.push(event),
+                EventTree::Nested { tag, events: inner } => {
+                    let end = tag.to_end();
+
+                    events.push(Event::Start(tag));
+                    flatten(inner, events);
+                    events.push(Event::End(end));
+                },
+            }
+        }
+    }
+
+    let mut events = Vec::new();
+
+    flatten(trees, &mut events);
+
+    events
+}
+
 //======================================
 // Implementation
 //======================================
@@ -76,3 +142,40 @@ impl<'a> Unflattener<'a> {
         root
     }
 }
+
+//======================================
+// Tests
+//======================================
+
+#[test]
+fn test_event_tree_roundtrip() {
+    use pretty_assertions::assert_eq;
+
+    let markdown = "* a\n  * b <br>\n  * c\n* d";
+
+    let tree = markdown_to_event_tree(markdown);
+
+    // The nested list is inside the first item of the outer list.
+    let [EventTree::Nested {
+        tag: Tag::List(None),
+        events: items,
+    }] = tree.as_slice()
+    else {
+        panic!("expected a single list: {tree:?}")
+    };
+
+    assert_eq!(items.len(), 2);
+    assert!(matches!(
+        &items[0],
+        EventTree::Nested { tag: Tag::Item, events }
+            if matches!(
+                events.as_slice(),
+                [_, EventTree::Nested { tag: Tag::List(None), .. }]
+            )
+    ));
+
+    assert_eq!(
+        event_tree_to_events(tree),
+        markdown_to_events(markdown).collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,209 @@
+//! Traverse and rewrite a Markdown AST in place.
+//!
+//! Implement [`VisitorMut`], overriding the methods for the nodes of interest,
+//! and pass it to [`walk_blocks_mut()`].
+//!
+//! # Examples
+//!
+//! Rewrite relative link destinations into absolute URLs:
+//!
+//! ```
+//! use markdown_ast::{
+//!     ast_to_markdown, markdown_to_ast,
+//!     visit::{walk_blocks_mut, walk_inline_mut, VisitorMut},
+//!     Inline,
+//! };
+//!
+//! struct AbsoluteLinks;
+//!
+//! impl VisitorMut for AbsoluteLinks {
+//!     fn visit_inline_mut(&mut self, inline: &mut Inline) {
+//!         if let Inline::Link { dest_url, .. } = inline {
+//!             if dest_url.starts_with('/') {
+//!                 *dest_url = format!("https://example.org{dest_url}");
+//!             }
+//!         }
+//!
+//!         walk_inline_mut(self, inline);
+//!     }
+//! }
+//!
+//! let mut ast = markdown_to_ast("* See [the docs](/docs).");
+//!
+//! walk_blocks_mut(&mut AbsoluteLinks, &mut ast);
+//!
+//! assert_eq!(
+//!     ast_to_markdown(&ast),
+//!     "* See [the docs](https://example.org/docs)."
+//! );
+//! ```
+
+use crate::{Block, Inline, Inlines, ListItem};
+
+/// A mutable visitor over the [`Block`]s and [`Inline`]s of a Markdown AST.
+///
+/// Each method is called with a node that may be modified in place. The
+/// default implementations recurse into the children of the node using the
+/// corresponding `walk_*` function. An overriding implementation should call
+/// that function too, unless the children of the node should be skipped.
+pub trait VisitorMut {
+    /// Visit a [`Block`], including blocks nested inside list items, block
+    /// quotes, and footnote definitions.
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block)
+    }
+
+    /// Visit a sequence of [`Inlines`], e.g. the content of a paragraph, a
+    /// table cell, or a container inline.
+    ///
+    /// Override this to insert or remove inlines, which cannot be done from
+    /// [`VisitorMut::visit_inline_mut()`].
+    fn visit_inlines_mut(&mut self, inlines: &mut Inlines) {
+        walk_inlines_mut(self, inlines)
+    }
+
+    /// Visit an [`Inline`], including inlines nested inside other inlines and
+    /// table cells.
+    fn visit_inline_mut(&mut self, inline: &mut Inline) {
+        walk_inline_mut(self, inline)
+    }
+}
+
+/// Call [`VisitorMut::visit_block_mut()`] on each of `blocks`.
+pub fn walk_blocks_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    blocks: &mut [Block],
+) {
+    for block in blocks {
+        visitor.visit_block_mut(block);
+    }
+}
+
+/// Visit the child blocks and inlines of `block`.
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    block: &mut Block,
+) {
+    match block {
+        Block::Paragraph(inlines) | Block::Heading(_, inlines, _) => {
+            visitor.visit_inlines_mut(inlines)
+        },
+        Block::List(items, _, _) => {
+            for ListItem(blocks, _) in items {
+                walk_blocks_mut(visitor, blocks);
+            }
+        },
+        Block::BlockQuote { kind: _, blocks }
+        | Block::FootnoteDefinition { label: _, blocks } => {
+            walk_blocks_mut(visitor, blocks)
+        },
+        Block::Table {
+            alignments: _,
+            headers,
+            rows,
+        } => {
+            for cell in headers.iter_mut().chain(rows.iter_mut().flatten()) {
+                visitor.visit_inlines_mut(cell);
+            }
+        },
+        Block::CodeBlock { .. }
+        | Block::Rule
+        | Block::Html(_)
+        | Block::Metadata { .. } => (),
+    }
+}
+
+/// Call [`VisitorMut::visit_inline_mut()`] on each of `inlines`.
+pub fn walk_inlines_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    Inlines(inlines): &mut Inlines,
+) {
+    for inline in inlines {
+        visitor.visit_inline_mut(inline);
+    }
+}
+
+/// Visit the child [`Inlines`] of `inline`.
+pub fn walk_inline_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    inline: &mut Inline,
+) {
+    match inline {
+        Inline::Emphasis(children)
+        | Inline::Strong(children)
+        | Inline::Strikethrough(children)
+        | Inline::Link {
+            content_text: children,
+            ..
+        }
+        | Inline::Image {
+            image_description: children,
+            ..
+        } => visitor.visit_inlines_mut(children),
+        Inline::Positioned(_, inline) => visitor.visit_inline_mut(inline),
+        Inline::Text(_)
+        | Inline::Code(_)
+        | Inline::SoftBreak
+        | Inline::HardBreak
+        | Inline::FootnoteReference(_)
+        | Inline::InlineMath(_)
+        | Inline::DisplayMath(_)
+        | Inline::InlineHtml(_) => (),
+    }
+}
+
+//======================================
+// Tests
+//======================================
+
+#[test]
+fn test_visitor_mut() {
+    use crate::markdown_to_ast;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    struct LowercaseCode;
+
+    impl VisitorMut for LowercaseCode {
+        fn visit_inline_mut(&mut self, inline: &mut Inline) {
+            if let Inline::Code(code) = inline {
+                *code = code.to_lowercase();
+            }
+
+            walk_inline_mut(self, inline);
+        }
+    }
+
+    let mut ast = markdown_to_ast(indoc!(
+        "
+        Some `CODE`.
+
+        * An item with **`BOLD CODE`**
+
+          > `QUOTED`
+
+        | `HEADER` |
+        |----------|
+        | `CELL`   |
+        "
+    ));
+
+    walk_blocks_mut(&mut LowercaseCode, &mut ast);
+
+    assert_eq!(
+        ast,
+        markdown_to_ast(indoc!(
+            "
+            Some `code`.
+
+            * An item with **`bold code`**
+
+              > `quoted`
+
+            | `header` |
+            |----------|
+            | `cell`   |
+            "
+        ))
+    );
+}
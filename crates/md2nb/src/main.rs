@@ -30,6 +30,11 @@ struct Args {
     /// blocks will instead be converted to inert "Program" cells.
     #[clap(long)]
     no_external_language_cells: bool,
+
+    /// If set, tables with more than this many columns are split into multiple
+    /// grids, stacked vertically, so that they fit within the notebook width.
+    #[clap(long)]
+    max_table_columns: Option<usize>,
 }
 
 fn main() -> Result<(), kernel::Error> {
@@ -37,6 +42,7 @@ fn main() -> Result<(), kernel::Error> {
         input,
         output,
         no_external_language_cells,
+        max_table_columns,
         open,
     } = Args::parse();
 
@@ -59,6 +65,8 @@ fn main() -> Result<(), kernel::Error> {
 
     let nb_options = nb::Options {
         create_external_language_cells: !no_external_language_cells,
+        max_table_columns,
+        ..nb::Options::default()
     };
 
     //-----------------------------------
@@ -1,4 +1,7 @@
-use markdown_ast::{Block, HeadingLevel, Inline, Inlines, ListItem};
+use markdown_ast::{
+    Block, BlockQuoteKind, CodeBlockKind, HeadingLevel, Inline, Inlines,
+    ListItem,
+};
 
 use wolfram_expr::{Expr, Symbol};
 
@@ -6,6 +9,29 @@ use wolfram_expr::{Expr, Symbol};
 #[derive(Debug, Clone)]
 pub struct Options {
     pub create_external_language_cells: bool,
+    /// The cell style used for each heading level, from `H1` to `H6`.
+    pub heading_styles: [String; 6],
+    /// If set, tables with more columns than this are split into multiple
+    /// grids, stacked vertically, each containing at most this many columns.
+    pub max_table_columns: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            create_external_language_cells: true,
+            heading_styles: [
+                "Title",
+                "Chapter",
+                "Section",
+                "Subsection",
+                "Subsubsection",
+                "Subsubsubsection",
+            ]
+            .map(String::from),
+            max_table_columns: None,
+        }
+    }
 }
 
 struct State {
@@ -24,32 +50,32 @@ fn block_to_cells_(
     block: Block,
 ) -> Vec<Expr> {
     match block {
-        Block::Heading(level, text) => {
+        Block::Heading(level, text, _) => {
             let style = match level {
-                HeadingLevel::H1 => "Title",
-                HeadingLevel::H2 => "Chapter",
-                HeadingLevel::H3 => "Section",
-                HeadingLevel::H4 => "Subsection",
-                HeadingLevel::H5 => "Subsubsection",
-                HeadingLevel::H6 => "Subsubsubsection",
+                HeadingLevel::H1 => &opts.heading_styles[0],
+                HeadingLevel::H2 => &opts.heading_styles[1],
+                HeadingLevel::H3 => &opts.heading_styles[2],
+                HeadingLevel::H4 => &opts.heading_styles[3],
+                HeadingLevel::H5 => &opts.heading_styles[4],
+                HeadingLevel::H6 => &opts.heading_styles[5],
             };
 
             vec![Expr::normal(
                 Symbol::new("System`Cell"),
-                vec![inlines_to_text_data(text), Expr::from(style)],
+                vec![inlines_to_text_data(text), Expr::string(style)],
             )]
         },
         Block::Paragraph(text) => vec![Expr::normal(
             Symbol::new("System`Cell"),
             vec![inlines_to_text_data(text), Expr::from("Text")],
         )],
-        Block::List(items) => {
+        Block::List(items, _, _) => {
             let mut list_cells = Vec::new();
 
             state.list_depth += 1;
 
             for item in items {
-                list_cells.extend(list_item_to_cells(state, item));
+                list_cells.extend(list_item_to_cells(state, opts, item));
             }
 
             state.list_depth -= 1;
@@ -60,11 +86,42 @@ fn block_to_cells_(
             kind,
             code: code_text,
         } => {
+            let attrs = kind.parse_attrs();
+
+            // The info string, excluding any attributes.
+            let info_string =
+                kind.info_string().map(|info_string| match attrs {
+                    Some(_) => info_string
+                        .rsplit_once('{')
+                        .map_or(info_string, |(info_string, _)| {
+                            info_string.trim()
+                        }),
+                    None => info_string,
+                });
+
+            // Code blocks with a `{.no-eval}` or `{cell=false}` attribute are
+            // never made into executable cells.
+            let attrs = attrs.unwrap_or_default();
+            let is_evaluatable = !attrs.classes.iter().any(|c| c == "no-eval")
+                && !attrs.attrs.iter().any(|(key, value)| {
+                    key == "cell" && value.as_deref() == Some("false")
+                });
+
+            // If the code block has no info string, fall back to guessing
+            // the language from a shebang line, if present.
+            let language: Option<String> = match info_string {
+                Some(info_string) if !info_string.is_empty() => {
+                    Some(info_string.to_lowercase())
+                },
+                _ => CodeBlockKind::sniff_language(&code_text)
+                    .map(|s| s.to_lowercase()),
+            };
+
             let external_language: Option<&str> =
                 // The languages listed here should be all of those currently supported
                 // by ExternalEvaluate.
-                match kind.info_string().map(|s| s.to_lowercase()).as_deref() {
-                    Some("python") => Some("Python"),
+                match language.as_deref() {
+                    Some("python" | "python3") => Some("Python"),
                     Some("shell" | "bash" | "sh" | "zsh") => Some("Shell"),
                     Some("julia") => Some("Julia"),
                     Some("r") => Some("R"),
@@ -81,7 +138,10 @@ fn block_to_cells_(
             match external_language {
                 // Only create "ExternalLanguage" cells if the option is set (enabled by
                 // default).
-                Some(lang) if opts.create_external_language_cells => {
+                Some(lang)
+                    if opts.create_external_language_cells
+                        && is_evaluatable =>
+                {
                     vec![Expr::normal(
                         Symbol::new("System`Cell"),
                         vec![
@@ -103,14 +163,16 @@ fn block_to_cells_(
             }
         },
         Block::BlockQuote {
-            kind: _,
+            kind,
             blocks: quote_blocks,
         } => {
             let quote_cells: Vec<Expr> = quote_blocks
                 .into_iter()
-                .flat_map(|block| block_to_cells(block, opts))
+                .flat_map(|block| block_to_cells_(state, opts, block))
                 .collect();
 
+            let (frame_color, background) = block_quote_colors(kind);
+
             // TODO: Use a dedicated "BlockQuote" cell style. There is no "BlockQuote"
             //       style in the default Wolfram notebook stylesheet, but we could add
             //       a StyleData definition to this notebook.
@@ -131,24 +193,11 @@ fn block_to_cells_(
                             Expr::list(vec![Expr::from(0), Expr::from(0)]),
                         ]),
                     ),
-                    // The cell frame should have a medium-light gray color:
-                    //   CellFrameColor -> GrayLevel[0.8]
                     Expr::rule(
                         Symbol::new("System`CellFrameColor"),
-                        Expr::normal(
-                            Symbol::new("System`GrayLevel"),
-                            vec![Expr::real(0.8)],
-                        ),
-                    ),
-                    // The cell background should be a light gray color:
-                    //   Background -> GrayLevel[0.95]
-                    Expr::rule(
-                        Symbol::new("System`Background"),
-                        Expr::normal(
-                            Symbol::new("System`GrayLevel"),
-                            vec![Expr::real(0.95)],
-                        ),
+                        frame_color,
                     ),
+                    Expr::rule(Symbol::new("System`Background"), background),
                 ],
             );
             vec![cell]
@@ -159,9 +208,7 @@ fn block_to_cells_(
             headers,
             rows,
         } => {
-            let mut grid_rows: Vec<Expr> = Vec::new();
-
-            let header_row = headers
+            let header_row: Vec<Expr> = headers
                 .into_iter()
                 .map(|content: Inlines| {
                     Expr::normal(
@@ -174,60 +221,45 @@ fn block_to_cells_(
                 })
                 .collect();
 
-            grid_rows.push(Expr::list(header_row));
-
-            for row_content in rows {
-                let row: Vec<Expr> = row_content
-                    .into_iter()
-                    .map(|content: Inlines| {
-                        Expr::normal(
-                            Symbol::new("System`Cell"),
-                            vec![
-                                inlines_to_text_data(content),
-                                Expr::from("Text"),
-                            ],
-                        )
-                    })
-                    .collect();
-
-                grid_rows.push(Expr::list(row));
-            }
-
-            let grid_box = Expr::normal(
-                Symbol::new("System`GridBox"),
-                vec![
-                    Expr::list(grid_rows),
-                    // GridBoxItemSize -> {
-                    //     "Columns" -> {{Automatic}},
-                    //     "Rows" -> {{Automatic}}
-                    // }
-                    Expr::rule(
-                        Symbol::new("System`GridBoxItemSize"),
-                        Expr::list(vec![
-                            Expr::rule(
-                                Expr::from("Columns"),
-                                Expr::list(vec![Expr::list(vec![Expr::from(
-                                    Symbol::new("System`Automatic"),
-                                )])]),
-                            ),
-                            Expr::rule(
-                                Expr::from("Rows"),
-                                Expr::list(vec![Expr::list(vec![Expr::from(
-                                    Symbol::new("System`Automatic"),
-                                )])]),
-                            ),
-                        ]),
-                    ),
-                ],
-            );
+            let rows: Vec<Vec<Expr>> = rows
+                .into_iter()
+                .map(|row_content| {
+                    row_content
+                        .into_iter()
+                        .map(|content: Inlines| {
+                            Expr::normal(
+                                Symbol::new("System`Cell"),
+                                vec![
+                                    inlines_to_text_data(content),
+                                    Expr::from("Text"),
+                                ],
+                            )
+                        })
+                        .collect()
+                })
+                .collect();
 
-            vec![Expr::normal(
-                Symbol::new("System`Cell"),
-                vec![
-                    Expr::normal(Symbol::new("System`BoxData"), vec![grid_box]),
-                    Expr::from("Text"),
-                ],
-            )]
+            // Wrap the columns of tables wider than `max_table_columns` into
+            // multiple grids, stacked vertically, each with its own header row.
+            let max_columns =
+                opts.max_table_columns.unwrap_or(usize::MAX).max(1);
+
+            (0..header_row.len().max(1))
+                .step_by(max_columns)
+                .map(|start| {
+                    let columns = |row: &Vec<Expr>| {
+                        let row = row.iter().skip(start).take(max_columns);
+                        Expr::list(row.cloned().collect())
+                    };
+
+                    let grid_rows = std::iter::once(&header_row)
+                        .chain(&rows)
+                        .map(columns)
+                        .collect();
+
+                    table_grid_cell(grid_rows)
+                })
+                .collect()
         },
         Block::Rule => {
             // Note: This formatting is based on the menu item:
@@ -302,63 +334,195 @@ fn block_to_cells_(
                 ],
             )]
         },
+        Block::FootnoteDefinition { label, blocks } => {
+            let definition_cells: Vec<Expr> = blocks
+                .into_iter()
+                .flat_map(|block| block_to_cells_(state, opts, block))
+                .collect();
+
+            // TODO: Link footnote references to their definitions.
+            vec![Expr::normal(
+                Symbol::new("System`Cell"),
+                vec![
+                    Expr::normal(
+                        Symbol::new("System`BoxData"),
+                        vec![Expr::list(definition_cells)],
+                    ),
+                    Expr::string("Text"),
+                    // Show the footnote label in the left margin:
+                    //   CellDingbat -> "label"
+                    Expr::rule(
+                        Symbol::new("System`CellDingbat"),
+                        Expr::string(label),
+                    ),
+                ],
+            )]
+        },
+        // TODO: Render HTML instead of showing its source.
+        Block::Html(html) => vec![Expr::normal(
+            Symbol::new("System`Cell"),
+            vec![Expr::string(html), Expr::string("Program")],
+        )],
+        // Frontmatter describes the document, and is not part of its content.
+        Block::Metadata { .. } => vec![],
     }
 }
 
 fn list_item_to_cells(
     state: &mut State,
-    ListItem(blocks): ListItem,
+    opts: &Options,
+    ListItem(blocks, mut checked): ListItem,
 ) -> Vec<Expr> {
     let mut cells = vec![];
 
     for block in blocks {
+        // The checkbox of a task list item is shown at the start of its first
+        // block.
+        let checkbox = checked.take();
+
         match block {
             Block::Paragraph(text) => {
                 let style = match state.list_depth {
                     0 => panic!(),
                     1 => "Item",
                     2 => "Subitem",
-                    3 => "Subsubitem",
-                    _ => todo!("return list depth error"),
+                    // The default stylesheet has no item styles for deeper
+                    // nesting levels, so use the deepest available style.
+                    _ => "Subsubitem",
+                };
+
+                let text_data = match checkbox {
+                    // TextData[{CheckboxBox[checked], " ", RowBox[{...}]}]
+                    Some(checked) => {
+                        let checked = match checked {
+                            true => "System`True",
+                            false => "System`False",
+                        };
+
+                        Expr::normal(
+                            Symbol::new("System`TextData"),
+                            vec![Expr::list(vec![
+                                Expr::normal(
+                                    Symbol::new("System`CheckboxBox"),
+                                    vec![Expr::symbol(Symbol::new(checked))],
+                                ),
+                                Expr::string(" "),
+                                text_to_boxes(text),
+                            ])],
+                        )
+                    },
+                    None => inlines_to_text_data(text),
                 };
 
                 cells.push(Expr::normal(
                     Symbol::new("System`Cell"),
-                    vec![inlines_to_text_data(text), Expr::from(style)],
+                    vec![text_data, Expr::from(style)],
                 ));
             },
-            Block::List(items) => {
+            Block::List(items, _, _) => {
                 let mut list_cells = Vec::new();
 
                 state.list_depth += 1;
 
                 for item in items {
-                    list_cells.extend(list_item_to_cells(state, item));
+                    list_cells.extend(list_item_to_cells(state, opts, item));
                 }
 
                 state.list_depth -= 1;
 
                 cells.extend(list_cells);
             },
-            Block::BlockQuote { kind: _, blocks: _ } => {
-                todo!("handle markdown block quote inside list items")
-            },
-            Block::Heading(_, _) => {
-                todo!("handle markdown headings inside list items")
-            },
-            Block::CodeBlock { .. } => {
-                todo!("handle markdown code block inside list item")
+            // Other blocks are converted the same way as outside of a list,
+            // keeping the current list depth for any lists nested in them.
+            Block::BlockQuote { .. }
+            | Block::Heading(_, _, _)
+            | Block::CodeBlock { .. }
+            | Block::Table { .. }
+            | Block::Rule
+            | Block::FootnoteDefinition { .. }
+            | Block::Html(_)
+            | Block::Metadata { .. } => {
+                cells.extend(block_to_cells_(state, opts, block));
             },
-            Block::Table { .. } => {
-                todo!("handle markdown table inside list item")
-            },
-            Block::Rule => todo!("handle markdown rule inside list item"),
         }
     }
 
     cells
 }
 
+/// Returns a `"Text"` cell containing a `GridBox` with the specified rows.
+fn table_grid_cell(grid_rows: Vec<Expr>) -> Expr {
+    let grid_box = Expr::normal(
+        Symbol::new("System`GridBox"),
+        vec![
+            Expr::list(grid_rows),
+            // GridBoxItemSize -> {
+            //     "Columns" -> {{Automatic}},
+            //     "Rows" -> {{Automatic}}
+            // }
+            Expr::rule(
+                Symbol::new("System`GridBoxItemSize"),
+                Expr::list(vec![
+                    Expr::rule(
+                        Expr::from("Columns"),
+                        Expr::list(vec![Expr::list(vec![Expr::from(
+                            Symbol::new("System`Automatic"),
+                        )])]),
+                    ),
+                    Expr::rule(
+                        Expr::from("Rows"),
+                        Expr::list(vec![Expr::list(vec![Expr::from(
+                            Symbol::new("System`Automatic"),
+                        )])]),
+                    ),
+                ]),
+            ),
+        ],
+    );
+
+    Expr::normal(
+        Symbol::new("System`Cell"),
+        vec![
+            Expr::normal(Symbol::new("System`BoxData"), vec![grid_box]),
+            Expr::from("Text"),
+        ],
+    )
+}
+
+/// Returns the `(CellFrameColor, Background)` colors of a block quote cell.
+///
+/// GitHub-style alert block quotes are given a distinct color for each `kind`,
+/// similar to the colors GitHub uses to render them.
+fn block_quote_colors(kind: Option<BlockQuoteKind>) -> (Expr, Expr) {
+    let gray = |level: f64| {
+        Expr::normal(Symbol::new("System`GrayLevel"), vec![Expr::real(level)])
+    };
+
+    let rgb = |[r, g, b]: [f64; 3]| {
+        Expr::normal(
+            Symbol::new("System`RGBColor"),
+            vec![Expr::real(r), Expr::real(g), Expr::real(b)],
+        )
+    };
+
+    let (frame, background) = match kind {
+        // A plain block quote has a medium-light gray frame and a light gray
+        // background.
+        None => return (gray(0.8), gray(0.95)),
+        Some(BlockQuoteKind::Note) => ([0.04, 0.41, 0.85], [0.87, 0.94, 1.0]),
+        Some(BlockQuoteKind::Tip) => ([0.1, 0.5, 0.22], [0.88, 0.96, 0.89]),
+        Some(BlockQuoteKind::Important) => {
+            ([0.51, 0.31, 0.87], [0.94, 0.91, 1.0])
+        },
+        Some(BlockQuoteKind::Warning) => ([0.6, 0.4, 0.0], [1.0, 0.97, 0.86]),
+        Some(BlockQuoteKind::Caution) => {
+            ([0.81, 0.13, 0.18], [1.0, 0.92, 0.92])
+        },
+    };
+
+    (rgb(frame), rgb(background))
+}
+
 /// Returns a `TextData[{...}]` expression.
 fn inlines_to_text_data(inlines: Inlines) -> Expr {
     Expr::normal(Symbol::new("System`TextData"), vec![text_to_boxes(inlines)])
@@ -391,7 +555,19 @@ fn text_to_boxes(text: Inlines) -> Expr {
                     ),
                 ],
             ),
-            Inline::Strikethrough(_) => todo!("strikethrough text"),
+            Inline::Strikethrough(inlines) => Expr::normal(
+                Symbol::new("System`StyleBox"),
+                vec![
+                    text_to_boxes(inlines),
+                    Expr::rule(
+                        Symbol::new("System`FontVariations"),
+                        Expr::list(vec![Expr::rule(
+                            Expr::string("StrikeThrough"),
+                            Expr::symbol(Symbol::new("System`True")),
+                        )]),
+                    ),
+                ],
+            ),
             Inline::Code(code) => Expr::normal(
                 Symbol::new("System`StyleBox"),
                 vec![Expr::string(code), Expr::string("Code")],
@@ -405,54 +581,402 @@ fn text_to_boxes(text: Inlines) -> Expr {
                 // FIXME: Pass through this link id.
                 id: _,
                 content_text,
-            } => Expr::normal(
-                Symbol::new("System`ButtonBox"),
+            } => hyperlink_box(text_to_boxes(content_text), dest_url),
+            // TODO: Embed the image itself, instead of linking to it.
+            Inline::Image {
+                link_type: _,
+                title: _,
+                dest_url,
+                id: _,
+                image_description,
+            } => hyperlink_box(text_to_boxes(image_description), dest_url),
+            Inline::SoftBreak => Expr::string(" "),
+            Inline::HardBreak => Expr::string("\n"),
+            // SuperscriptBox["", "label"]
+            Inline::FootnoteReference(label) => Expr::normal(
+                Symbol::new("System`SuperscriptBox"),
+                vec![Expr::string(""), Expr::string(label)],
+            ),
+            // TODO: Typeset math instead of showing its TeX source.
+            Inline::InlineMath(tex) | Inline::DisplayMath(tex) => Expr::normal(
+                Symbol::new("System`StyleBox"),
+                vec![Expr::string(tex), Expr::string("Code")],
+            ),
+            // TODO: Render HTML instead of showing its source.
+            Inline::InlineHtml(html) => Expr::normal(
+                Symbol::new("System`StyleBox"),
+                vec![Expr::string(html), Expr::string("Code")],
+            ),
+            // Source positions are not shown in the notebook.
+            Inline::Positioned(_, inline) => {
+                text_to_boxes(Inlines(vec![*inline]))
+            },
+        };
+
+        row.push(expr);
+    }
+
+    Expr::normal(
+        Symbol::new("System`RowBox"),
+        vec![Expr::normal(Symbol::new("System`List"), row)],
+    )
+}
+
+/// Returns a `ButtonBox[..]` expression that opens `dest_url` when clicked.
+fn hyperlink_box(content: Expr, dest_url: String) -> Expr {
+    Expr::normal(
+        Symbol::new("System`ButtonBox"),
+        vec![
+            content,
+            Expr::normal(
+                Symbol::new("System`Rule"),
                 vec![
-                    text_to_boxes(content_text),
-                    Expr::normal(
-                        Symbol::new("System`Rule"),
-                        vec![
-                            Expr::from(Symbol::new("System`BaseStyle")),
-                            Expr::string("Hyperlink"),
-                        ],
-                    ),
+                    Expr::from(Symbol::new("System`BaseStyle")),
+                    Expr::string("Hyperlink"),
+                ],
+            ),
+            Expr::normal(
+                Symbol::new("System`Rule"),
+                vec![
+                    Expr::from(Symbol::new("System`ButtonData")),
                     Expr::normal(
-                        Symbol::new("System`Rule"),
+                        Symbol::new("System`List"),
                         vec![
-                            Expr::from(Symbol::new("System`ButtonData")),
                             Expr::normal(
-                                Symbol::new("System`List"),
-                                vec![
-                                    Expr::normal(
-                                        Symbol::new("System`URL"),
-                                        vec![Expr::string(dest_url.clone())],
-                                    ),
-                                    Expr::from(Symbol::new("System`None")),
-                                ],
+                                Symbol::new("System`URL"),
+                                vec![Expr::string(dest_url.clone())],
                             ),
+                            Expr::from(Symbol::new("System`None")),
                         ],
                     ),
+                ],
+            ),
+            Expr::normal(
+                Symbol::new("System`Rule"),
+                vec![
+                    Expr::from(Symbol::new("System`ButtonNote")),
+                    Expr::string(dest_url),
+                ],
+            ),
+        ],
+    )
+}
+
+
+//======================================
+// Tests
+//======================================
+
+/// Tests that every kind of Markdown content in the shared `every-variant.md`
+/// fixture can be converted to notebook cells.
+#[test]
+fn test_every_variant_to_cells() {
+    let ast = markdown_ast::markdown_to_ast(include_str!(
+        "../../markdown-ast/tests/fixtures/every-variant.md"
+    ));
+
+    let opts = Options {
+        create_external_language_cells: false,
+        ..Options::default()
+    };
+
+    for block in ast {
+        let _: Vec<Expr> = block_to_cells(block, &opts);
+    }
+}
+
+#[test]
+fn test_custom_heading_styles() {
+    let opts = Options {
+        heading_styles: ["H1", "H2", "H3", "H4", "H5", "H6"].map(String::from),
+        ..Options::default()
+    };
+
+    let ast = markdown_ast::markdown_to_ast("# Title\n\n### Section");
+
+    let cells: Vec<Expr> = ast
+        .into_iter()
+        .flat_map(|block| block_to_cells(block, &opts))
+        .collect();
+
+    let heading = |text: &str, style: &str| {
+        Expr::normal(
+            Symbol::new("System`Cell"),
+            vec![
+                inlines_to_text_data(Inlines::plain_text(text)),
+                Expr::string(style),
+            ],
+        )
+    };
+
+    assert_eq!(
+        cells,
+        vec![heading("Title", "H1"), heading("Section", "H3")]
+    );
+
+    // The default styles match the standard notebook stylesheet.
+    let block = markdown_ast::Block::Heading(
+        HeadingLevel::H2,
+        Inlines::plain_text("Chapter"),
+        Default::default(),
+    );
+
+    let cells = block_to_cells(block, &Options::default());
+
+    assert_eq!(cells, vec![heading("Chapter", "Chapter")]);
+}
+
+#[test]
+fn test_alert_block_quote_colors() {
+    let has_background = |markdown: &str, background: Expr| -> bool {
+        let ast = markdown_ast::markdown_to_ast(markdown);
+
+        let cells: Vec<Expr> = ast
+            .into_iter()
+            .flat_map(|block| block_to_cells(block, &Options::default()))
+            .collect();
+
+        let background =
+            Expr::rule(Symbol::new("System`Background"), background);
+
+        let [cell] = cells.as_slice() else {
+            panic!("expected one cell, got: {cells:?}")
+        };
+
+        cell.try_as_normal()
+            .unwrap()
+            .elements()
+            .contains(&background)
+    };
+
+    let (_, plain) = block_quote_colors(None);
+    let (_, note) = block_quote_colors(Some(BlockQuoteKind::Note));
+    let (_, warning) = block_quote_colors(Some(BlockQuoteKind::Warning));
+
+    assert_ne!(note, plain);
+    assert_ne!(warning, plain);
+    assert_ne!(warning, note);
+
+    assert!(has_background("> text", plain));
+    assert!(has_background("> [!NOTE]\n> text", note));
+    assert!(has_background("> [!WARNING]\n> text", warning.clone()));
+    assert!(!has_background("> text", warning));
+}
+
+#[test]
+fn test_strikethrough_to_boxes() {
+    let ast = markdown_ast::markdown_to_ast("~~gone~~");
+
+    let [markdown_ast::Block::Paragraph(inlines)] = ast.as_slice() else {
+        panic!("expected a single paragraph, got: {ast:?}")
+    };
+
+    assert_eq!(
+        text_to_boxes(inlines.clone()),
+        Expr::normal(
+            Symbol::new("System`RowBox"),
+            vec![Expr::list(vec![Expr::normal(
+                Symbol::new("System`StyleBox"),
+                vec![
                     Expr::normal(
-                        Symbol::new("System`Rule"),
-                        vec![
-                            Expr::from(Symbol::new("System`ButtonNote")),
-                            Expr::string(dest_url),
-                        ],
+                        Symbol::new("System`RowBox"),
+                        vec![Expr::list(vec![Expr::string("gone")])],
+                    ),
+                    Expr::rule(
+                        Symbol::new("System`FontVariations"),
+                        Expr::list(vec![Expr::rule(
+                            Expr::string("StrikeThrough"),
+                            Expr::symbol(Symbol::new("System`True")),
+                        )]),
                     ),
                 ],
+            )])],
+        )
+    );
+}
+
+#[test]
+fn test_list_item_code_block_to_cells() {
+    let ast = markdown_ast::markdown_to_ast(concat!(
+        "* Run this:\n\n",
+        "  ```python\n  print(1)\n  ```\n\n",
+        "  ```\n  plain\n  ```",
+    ));
+
+    let cells: Vec<Expr> = ast
+        .into_iter()
+        .flat_map(|block| block_to_cells(block, &Options::default()))
+        .collect();
+
+    assert_eq!(
+        cells,
+        vec![
+            Expr::normal(
+                Symbol::new("System`Cell"),
+                vec![
+                    inlines_to_text_data(Inlines::plain_text("Run this:")),
+                    Expr::from("Item"),
+                ],
             ),
-            Inline::Image { .. } => {
-                todo!("Support Image link conversion to notebook")
-            },
-            Inline::SoftBreak => Expr::string(" "),
-            Inline::HardBreak => Expr::string("\n"),
-        };
+            Expr::normal(
+                Symbol::new("System`Cell"),
+                vec![
+                    Expr::string("print(1)\n"),
+                    Expr::string("ExternalLanguage"),
+                    Expr::rule(
+                        Symbol::new("System`CellEvaluationLanguage"),
+                        Expr::string("Python"),
+                    ),
+                ],
+            ),
+            Expr::normal(
+                Symbol::new("System`Cell"),
+                vec![Expr::string("plain\n"), Expr::string("Program")],
+            ),
+        ]
+    );
+}
 
-        row.push(expr);
-    }
+#[test]
+fn test_deeply_nested_list_to_cells() {
+    let ast = markdown_ast::markdown_to_ast(
+        "* One\n  * Two\n    * Three\n      * Four\n        * Five",
+    );
 
-    Expr::normal(
-        Symbol::new("System`RowBox"),
-        vec![Expr::normal(Symbol::new("System`List"), row)],
-    )
+    let cells: Vec<Expr> = ast
+        .into_iter()
+        .flat_map(|block| block_to_cells(block, &Options::default()))
+        .collect();
+
+    let item = |text: &str, style: &str| {
+        Expr::normal(
+            Symbol::new("System`Cell"),
+            vec![
+                inlines_to_text_data(Inlines::plain_text(text)),
+                Expr::from(style),
+            ],
+        )
+    };
+
+    assert_eq!(
+        cells,
+        vec![
+            item("One", "Item"),
+            item("Two", "Subitem"),
+            item("Three", "Subsubitem"),
+            item("Four", "Subsubitem"),
+            item("Five", "Subsubitem"),
+        ]
+    );
+}
+
+/// Tests that tables wider than `max_table_columns` are split into multiple
+/// grids, each containing a subset of the columns of the table.
+#[test]
+fn test_max_table_columns() {
+    use markdown_ast::Block;
+
+    let ast = markdown_ast::markdown_to_ast(include_str!(
+        "../docs/examples/wide-table.md"
+    ));
+
+    let table = ast
+        .into_iter()
+        .find(|block| matches!(block, Block::Table { .. }))
+        .unwrap();
+
+    let Block::Table {
+        alignments,
+        headers,
+        rows,
+    } = table.clone()
+    else {
+        unreachable!()
+    };
+
+    assert_eq!(headers.len(), 10);
+
+    // The table containing only the specified range of columns.
+    let columns = |range: std::ops::Range<usize>| Block::Table {
+        alignments: alignments[range.clone()].to_vec(),
+        headers: headers[range.clone()].to_vec(),
+        rows: rows.iter().map(|row| row[range.clone()].to_vec()).collect(),
+    };
+
+    let opts = Options {
+        max_table_columns: Some(4),
+        ..Options::default()
+    };
+
+    let expected: Vec<Expr> = [columns(0..4), columns(4..8), columns(8..10)]
+        .into_iter()
+        .flat_map(|table| block_to_cells(table, &Options::default()))
+        .collect();
+
+    assert_eq!(block_to_cells(table.clone(), &opts), expected);
+
+    // Tables that fit are not split.
+    let opts = Options {
+        max_table_columns: Some(10),
+        ..Options::default()
+    };
+
+    assert_eq!(
+        block_to_cells(table.clone(), &opts),
+        block_to_cells(table, &Options::default())
+    );
+    assert_eq!(block_to_cells(columns(0..4), &opts).len(), 1);
+}
+
+/// Tests that code blocks with a `{.no-eval}` or `{cell=false}` attribute are
+/// converted into plain "Program" cells, even in a supported language.
+#[test]
+fn test_no_eval_code_blocks_to_cells() {
+    let ast = markdown_ast::markdown_to_ast(include_str!(
+        "../docs/examples/no-eval.md"
+    ));
+
+    let code_cells: Vec<Expr> = ast
+        .into_iter()
+        .filter(|block| matches!(block, Block::CodeBlock { .. }))
+        .flat_map(|block| block_to_cells(block, &Options::default()))
+        .collect();
+
+    let program = |code: &str| {
+        Expr::normal(
+            Symbol::new("System`Cell"),
+            vec![Expr::string(code), Expr::string("Program")],
+        )
+    };
+
+    assert_eq!(
+        code_cells,
+        vec![
+            Expr::normal(
+                Symbol::new("System`Cell"),
+                vec![
+                    Expr::string("print(\"Hello from Python\")\n"),
+                    Expr::string("ExternalLanguage"),
+                    Expr::rule(
+                        Symbol::new("System`CellEvaluationLanguage"),
+                        Expr::string("Python"),
+                    ),
+                ],
+            ),
+            program("import os\n\nos.remove(\"important.txt\")\n"),
+            program("rm -rf build/\n"),
+        ]
+    );
+
+    // Attributes other than these don't prevent an executable cell.
+    let cells = block_to_cells(
+        markdown_ast::markdown_to_ast("```python {.example}\nx = 1\n```")
+            .remove(0),
+        &Options::default(),
+    );
+
+    assert!(
+        matches!(&cells[..], [cell] if cell.to_string().contains("Python")),
+        "{cells:?}"
+    );
 }
@@ -3,13 +3,16 @@ mod from_expr_utils;
 use wolfram_library_link::{
     export,
     expr::{Expr, ExprKind, Number, Symbol},
+    wstp::Link,
 };
 
 use markdown_ast::{
-    Block, CodeBlockKind, HeadingLevel, Inline, Inlines, ListItem,
+    Alignment, Block, BlockQuoteKind, CodeBlockKind, HeadingAttributes,
+    HeadingLevel, Inline, Inlines, LineCol, LinkType, ListItem,
+    MetadataBlockKind,
 };
 
-use self::from_expr_utils::try_headed;
+use self::from_expr_utils::{try_headed, try_headed_len};
 
 // TODO(cleanup):
 //  Rename to MarkdownInline[..] and MarkdownBlock[..]?
@@ -39,6 +42,34 @@ fn parse_markdown(args: Vec<Expr>) -> Expr {
     Expr::list(ast)
 }
 
+/// Variant of `parse_markdown` for large inputs.
+///
+/// The blocks of the input are parsed, converted to expressions, and written
+/// to `link` one at a time, so the complete AST and list of expressions are
+/// never held in memory at once.
+#[export(wstp)]
+fn parse_markdown_streaming(link: &mut Link) {
+    let arg_count = link
+        .test_head("System`List")
+        .expect("expected arguments list");
+
+    if arg_count != 1 {
+        panic!("incorrect argument count");
+    }
+
+    let s: String = link.get_string().expect("expected String argument");
+
+    // The length of the returned list must be written before its elements, so
+    // count the blocks in a separate first pass.
+    let block_count = markdown_ast::markdown_to_ast_iter(&s).count();
+
+    link.put_function("System`List", block_count).unwrap();
+
+    for expr in markdown_to_block_exprs(&s) {
+        link.put_expr(&expr).unwrap();
+    }
+}
+
 #[export(wstp)]
 fn markdown_ast_to_markdown(args: Vec<Expr>) -> Expr {
     let [blocks]: [Expr; 1] = match args.try_into() {
@@ -56,22 +87,38 @@ fn markdown_ast_to_markdown(args: Vec<Expr>) -> Expr {
 // Block to Expr Conversion
 //======================================
 
+/// Lazily parse `markdown` into a sequence of block expressions.
+fn markdown_to_block_exprs(markdown: &str) -> impl Iterator<Item = Expr> + '_ {
+    markdown_ast::markdown_to_ast_iter(markdown)
+        .map(|block| block_to_expr(&block))
+}
+
 fn block_to_expr(block: &Block) -> Expr {
     match block {
         Block::Paragraph(inlines) => Expr::normal(
             Symbol::new(MarkdownElement),
             vec![Expr::string("Paragraph"), inlines_to_expr(inlines)],
         ),
-        // FIXME: Should say whether the list is ordered or not
-        Block::List(items) => {
+        // MarkdownElement["List", {...}]
+        // MarkdownElement["List", {...}, start]
+        Block::List(items, start, _) => {
             let exprs = items.iter().map(list_item_to_expr).collect();
 
-            Expr::normal(
-                Symbol::new(MarkdownElement),
-                vec![Expr::string("List"), Expr::list(exprs)],
-            )
+            let mut args = vec![Expr::string("List"), Expr::list(exprs)];
+
+            if let Some(start) = start {
+                // NOTE:
+                //  CommonMark limits list start numbers to 9 digits, so this
+                //  conversion cannot fail for parsed Markdown.
+                let start = i64::try_from(*start)
+                    .expect("list start number is too large");
+
+                args.push(Expr::from(start));
+            }
+
+            Expr::normal(Symbol::new(MarkdownElement), args)
         },
-        Block::Heading(level, inlines) => {
+        Block::Heading(level, inlines, _) => {
             let level = match level {
                 HeadingLevel::H1 => 1,
                 HeadingLevel::H2 => 2,
@@ -104,24 +151,101 @@ fn block_to_expr(block: &Block) -> Expr {
             ],
         ),
         // MarkdownElement["BlockQuote", {...}]
-        // FIXME: Convert the `kind` as well.
-        Block::BlockQuote { kind: _, blocks } => {
+        // MarkdownElement["BlockQuote", {...}, "kind"]
+        Block::BlockQuote { kind, blocks } => {
             let blocks = blocks.into_iter().map(block_to_expr).collect();
 
+            let mut args = vec![Expr::string("BlockQuote"), Expr::list(blocks)];
+
+            if let Some(kind) = kind {
+                let kind = match kind {
+                    BlockQuoteKind::Note => "Note",
+                    BlockQuoteKind::Tip => "Tip",
+                    BlockQuoteKind::Important => "Important",
+                    BlockQuoteKind::Warning => "Warning",
+                    BlockQuoteKind::Caution => "Caution",
+                };
+
+                args.push(Expr::string(kind));
+            }
+
+            Expr::normal(Symbol::new(MarkdownElement), args)
+        },
+        // MarkdownElement["Table", {alignment...}, {header...}, {{cell...}...}]
+        Block::Table {
+            alignments,
+            headers,
+            rows,
+        } => {
+            let alignments = alignments
+                .iter()
+                .map(|alignment| {
+                    Expr::string(match alignment {
+                        Alignment::None => "None",
+                        Alignment::Left => "Left",
+                        Alignment::Center => "Center",
+                        Alignment::Right => "Right",
+                    })
+                })
+                .collect();
+
+            let headers = headers.iter().map(inlines_to_expr).collect();
+
+            let rows = rows
+                .iter()
+                .map(|row| {
+                    Expr::list(row.iter().map(inlines_to_expr).collect())
+                })
+                .collect();
+
             Expr::normal(
                 Symbol::new(MarkdownElement),
-                vec![Expr::string("BlockQuote"), Expr::list(blocks)],
+                vec![
+                    Expr::string("Table"),
+                    Expr::list(alignments),
+                    Expr::list(headers),
+                    Expr::list(rows),
+                ],
             )
         },
-        Block::Table {
-            alignments: _,
-            headers: _,
-            rows: _,
-        } => todo!(),
         Block::Rule => Expr::normal(
             Symbol::new(MarkdownElement),
             vec![Expr::string("ThematicBreak")],
         ),
+        // MarkdownElement["FootnoteDefinition", "label", {...}]
+        Block::FootnoteDefinition { label, blocks } => {
+            let blocks = blocks.iter().map(block_to_expr).collect();
+
+            Expr::normal(
+                Symbol::new(MarkdownElement),
+                vec![
+                    Expr::string("FootnoteDefinition"),
+                    Expr::string(label),
+                    Expr::list(blocks),
+                ],
+            )
+        },
+        // MarkdownElement["Html", "html"]
+        Block::Html(html) => Expr::normal(
+            Symbol::new(MarkdownElement),
+            vec![Expr::string("Html"), Expr::string(html)],
+        ),
+        // MarkdownElement["Metadata", "YAML" | "TOML", "content"]
+        Block::Metadata { kind, content } => {
+            let kind = match kind {
+                MetadataBlockKind::YamlStyle => "YAML",
+                MetadataBlockKind::PlusesStyle => "TOML",
+            };
+
+            Expr::normal(
+                Symbol::new(MarkdownElement),
+                vec![
+                    Expr::string("Metadata"),
+                    Expr::string(kind),
+                    Expr::string(content),
+                ],
+            )
+        },
     }
 }
 
@@ -152,27 +276,27 @@ fn inline_to_expr(span: &Inline) -> Expr {
         },
         // MarkdownElement["Code", "code"]
         Inline::Code(code) => vec![Expr::string("Code"), Expr::string(code)],
-        // MarkdownElement["Hyperlink", label, destination]
+        // MarkdownElement["Hyperlink", label, destination, "title", "id"]
         Inline::Link {
             // FIXME: Pass through this link type
             link_type: _,
-            // FIXME: Pass through this link title as well
-            title: _,
+            title,
             dest_url,
-            // FIXME: Pass through this link id
-            id: _,
+            id,
             content_text,
         } => vec![
             Expr::string("Hyperlink"),
             inlines_to_expr(content_text),
             Expr::string(dest_url),
+            Expr::string(title),
+            Expr::string(id),
         ],
+        // MarkdownElement["Image", description, destination, "title"]
         Inline::Image {
             // FIXME: Pass through this link type
             link_type: _,
             dest_url,
-            // FIXME: Pass through this link title as well
-            title: _,
+            title,
             // FIXME: Pass through this link id
             id: _,
             image_description,
@@ -180,21 +304,61 @@ fn inline_to_expr(span: &Inline) -> Expr {
             Expr::string("Image"),
             inlines_to_expr(image_description),
             Expr::string(dest_url),
+            Expr::string(title),
         ],
         Inline::SoftBreak => vec![Expr::string("SoftBreak")],
         Inline::HardBreak => vec![Expr::string("HardBreak")],
+        // MarkdownElement["FootnoteReference", "label"]
+        Inline::FootnoteReference(label) => {
+            vec![Expr::string("FootnoteReference"), Expr::string(label)]
+        },
+        // MarkdownElement["InlineMath", "tex"]
+        Inline::InlineMath(tex) => {
+            vec![Expr::string("InlineMath"), Expr::string(tex)]
+        },
+        // MarkdownElement["DisplayMath", "tex"]
+        Inline::DisplayMath(tex) => {
+            vec![Expr::string("DisplayMath"), Expr::string(tex)]
+        },
+        // MarkdownElement["InlineHtml", "html"]
+        Inline::InlineHtml(html) => {
+            vec![Expr::string("InlineHtml"), Expr::string(html)]
+        },
+        // MarkdownElement["Positioned", {line, column}, inline]
+        Inline::Positioned(LineCol { line, column }, inline) => {
+            let [line, column] = [*line, *column].map(|value| {
+                Expr::from(
+                    i64::try_from(value).expect("source position is too large"),
+                )
+            });
+
+            vec![
+                Expr::string("Positioned"),
+                Expr::list(vec![line, column]),
+                inline_to_expr(inline),
+            ]
+        },
     };
 
     Expr::normal(Symbol::new(MarkdownElement), inline_args)
 }
 
-fn list_item_to_expr(ListItem(blocks): &ListItem) -> Expr {
+fn list_item_to_expr(ListItem(blocks, checked): &ListItem) -> Expr {
     let blocks = blocks.iter().map(block_to_expr).collect();
 
-    Expr::normal(
-        Symbol::new(MarkdownElement),
-        vec![Expr::string("ListItem"), Expr::list(blocks)],
-    )
+    let mut args = vec![Expr::string("ListItem"), Expr::list(blocks)];
+
+    // MarkdownElement["ListItem", {...}, True | False]
+    if let Some(checked) = checked {
+        let checked = match checked {
+            true => "System`True",
+            false => "System`False",
+        };
+
+        args.push(Expr::symbol(Symbol::new(checked)));
+    }
+
+    Expr::normal(Symbol::new(MarkdownElement), args)
 }
 
 //======================================
@@ -239,11 +403,11 @@ fn parse_expr_to_block(expr: &Expr) -> Result<Block, String> {
         ("Heading", [level, inlines]) => {
             let level = match level.try_as_number() {
                 Some(Number::Integer(1)) => HeadingLevel::H1,
-                Some(Number::Integer(2)) => HeadingLevel::H1,
-                Some(Number::Integer(3)) => HeadingLevel::H1,
-                Some(Number::Integer(4)) => HeadingLevel::H1,
-                Some(Number::Integer(5)) => HeadingLevel::H1,
-                Some(Number::Integer(6)) => HeadingLevel::H1,
+                Some(Number::Integer(2)) => HeadingLevel::H2,
+                Some(Number::Integer(3)) => HeadingLevel::H3,
+                Some(Number::Integer(4)) => HeadingLevel::H4,
+                Some(Number::Integer(5)) => HeadingLevel::H5,
+                Some(Number::Integer(6)) => HeadingLevel::H6,
                 _ => {
                     return Err(format!(
                         "unsupported heading level value: {level}"
@@ -253,7 +417,7 @@ fn parse_expr_to_block(expr: &Expr) -> Result<Block, String> {
 
             let inlines = parse_expr_inlines(inlines)?;
 
-            Block::Heading(level, inlines)
+            Block::Heading(level, inlines, HeadingAttributes::default())
         },
         ("CodeBlock", [kind, content]) => {
             let kind = match kind.kind() {
@@ -291,9 +455,11 @@ fn parse_expr_to_inline(expr: &Expr) -> Result<Inline, String> {
 
     let element_args = try_headed(expr, Symbol::new(MarkdownElement))?;
 
-    if element_args.len() < 2 {
+    // Note: Some inline elements, like MarkdownElement["SoftBreak"], have
+    //       only a single argument.
+    if element_args.is_empty() {
         return Err(format!(
-            "expected MarkdownElement[..] to have at least 2 args: {expr}",
+            "expected MarkdownElement[..] to have at least 1 arg: {expr}",
         ));
     }
 
@@ -322,6 +488,119 @@ fn parse_expr_to_inline(expr: &Expr) -> Result<Inline, String> {
 
             Inline::Emphasis(inlines)
         },
+        ("Strikethrough", [inlines]) => {
+            let inlines = parse_expr_inlines(inlines)?;
+
+            Inline::Strikethrough(inlines)
+        },
+        ("Code", [code]) => {
+            let Some(code) = code.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"Code\", ..] 2nd argument to be a string, got: {code}"));
+            };
+
+            Inline::Code(code.to_owned())
+        },
+        ("Hyperlink", [content_text, dest_url, title, id]) => {
+            let content_text = parse_expr_inlines(content_text)?;
+
+            let Some(dest_url) = dest_url.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"Hyperlink\", ..] 3rd argument to be a string, got: {dest_url}"));
+            };
+
+            let Some(title) = title.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"Hyperlink\", ..] 4th argument to be a string, got: {title}"));
+            };
+
+            let Some(id) = id.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"Hyperlink\", ..] 5th argument to be a string, got: {id}"));
+            };
+
+            // FIXME: Parse the link type once it is included in the
+            //        expression form of a link. Until then, assume that a
+            //        link with an id is a full reference link.
+            let link_type = match id.is_empty() {
+                true => LinkType::Inline,
+                false => LinkType::Reference,
+            };
+
+            Inline::Link {
+                link_type,
+                dest_url: dest_url.to_owned(),
+                title: title.to_owned(),
+                id: id.to_owned(),
+                content_text,
+            }
+        },
+        // FIXME: Parse the link type and id once they are included in the
+        //        expression form of an image.
+        ("Image", [image_description, dest_url, title]) => {
+            let image_description = parse_expr_inlines(image_description)?;
+
+            let Some(dest_url) = dest_url.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"Image\", ..] 3rd argument to be a string, got: {dest_url}"));
+            };
+
+            let Some(title) = title.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"Image\", ..] 4th argument to be a string, got: {title}"));
+            };
+
+            Inline::Image {
+                link_type: LinkType::Inline,
+                dest_url: dest_url.to_owned(),
+                title: title.to_owned(),
+                id: String::new(),
+                image_description,
+            }
+        },
+        ("SoftBreak", []) => Inline::SoftBreak,
+        ("HardBreak", []) => Inline::HardBreak,
+        ("FootnoteReference", [label]) => {
+            let Some(label) = label.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"FootnoteReference\", ..] 2nd argument to be a string, got: {label}"));
+            };
+
+            Inline::FootnoteReference(label.to_owned())
+        },
+        ("InlineMath", [tex]) => {
+            let Some(tex) = tex.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"InlineMath\", ..] 2nd argument to be a string, got: {tex}"));
+            };
+
+            Inline::InlineMath(tex.to_owned())
+        },
+        ("DisplayMath", [tex]) => {
+            let Some(tex) = tex.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"DisplayMath\", ..] 2nd argument to be a string, got: {tex}"));
+            };
+
+            Inline::DisplayMath(tex.to_owned())
+        },
+        ("InlineHtml", [html]) => {
+            let Some(html) = html.try_as_str() else {
+                return Err(format!("expected MarkdownElement[\"InlineHtml\", ..] 2nd argument to be a string, got: {html}"));
+            };
+
+            Inline::InlineHtml(html.to_owned())
+        },
+        ("Positioned", [position, inline]) => {
+            let [line, column] =
+                try_headed_len(position, Symbol::new("System`List"))?
+                    .clone()
+                    .map(|value| match value.try_as_number() {
+                        Some(Number::Integer(value)) => {
+                            usize::try_from(value).ok()
+                        },
+                        _ => None,
+                    });
+
+            let (Some(line), Some(column)) = (line, column) else {
+                return Err(format!("expected MarkdownElement[\"Positioned\", ..] 2nd argument to be a list of two non-negative integers, got: {position}"));
+            };
+
+            let inline = parse_expr_to_inline(inline)?;
+
+            Inline::Positioned(LineCol { line, column }, Box::new(inline))
+        },
         (other, _) => {
             panic!("unrecognized inline MarkdownElement[{other:?}, ..] form")
         },
@@ -341,3 +620,260 @@ fn parse_expr_inlines(inlines: &Expr) -> Result<Inlines, String> {
 
     Ok(Inlines(inlines))
 }
+
+//======================================
+// Tests
+//======================================
+
+/// Returns an example of every [`Inline`] variant.
+///
+/// When adding a new [`Inline`] variant, the `match` below will fail to
+/// compile until an example of the new variant is added here, which in turn
+/// requires that the new variant be supported by [`inline_to_expr()`] and
+/// [`parse_expr_to_inline()`] in order for
+/// `test_inline_expr_roundtrip()` to pass.
+#[cfg(test)]
+fn inline_examples() -> Vec<Inline> {
+    let examples = vec![
+        Inline::plain_text("text"),
+        Inline::emphasis(Inline::plain_text("emphasis")),
+        Inline::strong(Inline::plain_text("strong")),
+        Inline::strikethrough(Inline::plain_text("strikethrough")),
+        Inline::code("code"),
+        Inline::Link {
+            link_type: LinkType::Inline,
+            dest_url: "example.org".to_owned(),
+            title: "Title".to_owned(),
+            id: String::new(),
+            content_text: Inlines::plain_text("link"),
+        },
+        Inline::Link {
+            link_type: LinkType::Reference,
+            dest_url: "example.org".to_owned(),
+            title: String::new(),
+            id: "example".to_owned(),
+            content_text: Inlines::plain_text("reference link"),
+        },
+        Inline::Image {
+            link_type: LinkType::Inline,
+            dest_url: "example.org/photo.png".to_owned(),
+            title: "Title".to_owned(),
+            id: String::new(),
+            image_description: Inlines::plain_text("image"),
+        },
+        Inline::SoftBreak,
+        Inline::HardBreak,
+        Inline::FootnoteReference("1".to_owned()),
+        Inline::InlineMath("x^2".to_owned()),
+        Inline::DisplayMath("x^2".to_owned()),
+        Inline::InlineHtml("<sub>".to_owned()),
+        Inline::Positioned(
+            LineCol { line: 3, column: 7 },
+            Box::new(Inline::emphasis(Inline::plain_text("positioned"))),
+        ),
+    ];
+
+    for example in &examples {
+        match example {
+            Inline::Text(_)
+            | Inline::Emphasis(_)
+            | Inline::Strong(_)
+            | Inline::Strikethrough(_)
+            | Inline::Code(_)
+            | Inline::Link { .. }
+            | Inline::Image { .. }
+            | Inline::SoftBreak
+            | Inline::HardBreak
+            | Inline::FootnoteReference(_)
+            | Inline::InlineMath(_)
+            | Inline::DisplayMath(_)
+            | Inline::InlineHtml(_)
+            | Inline::Positioned(_, _) => (),
+        }
+    }
+
+    examples
+}
+
+/// Tests that every [`Inline`] variant can be converted to an [`Expr`] and
+/// parsed back.
+#[test]
+fn test_inline_expr_roundtrip() {
+    for inline in inline_examples() {
+        let expr = inline_to_expr(&inline);
+
+        assert_eq!(
+            parse_expr_to_inline(&expr),
+            Ok(inline),
+            "inline did not round-trip through expr: {expr}"
+        );
+    }
+}
+
+/// Tests that each heading level is parsed back from a
+/// `MarkdownElement["Heading", level, {...}]` expression.
+#[test]
+fn test_parse_expr_heading_level() {
+    let heading = |level: i64| {
+        Expr::normal(
+            Symbol::new(MarkdownElement),
+            vec![
+                Expr::string("Heading"),
+                Expr::from(level),
+                inlines_to_expr(&Inlines::plain_text("Title")),
+            ],
+        )
+    };
+
+    assert_eq!(
+        parse_expr_to_block(&heading(3)),
+        Ok(Block::Heading(
+            HeadingLevel::H3,
+            Inlines::plain_text("Title"),
+            HeadingAttributes::default(),
+        ))
+    );
+
+    for level in [
+        HeadingLevel::H1,
+        HeadingLevel::H2,
+        HeadingLevel::H3,
+        HeadingLevel::H4,
+        HeadingLevel::H5,
+        HeadingLevel::H6,
+    ] {
+        let block = Block::Heading(
+            level,
+            Inlines::plain_text("Title"),
+            HeadingAttributes::default(),
+        );
+
+        assert_eq!(parse_expr_to_block(&block_to_expr(&block)), Ok(block));
+    }
+
+    assert!(parse_expr_to_block(&heading(0)).is_err());
+    assert!(parse_expr_to_block(&heading(7)).is_err());
+}
+
+/// Tests that the block expressions written by `parse_markdown_streaming` are
+/// the same as those returned by `parse_markdown`.
+#[test]
+fn test_markdown_to_block_exprs() {
+    let markdown = "# Title\n\nSome *text*.\n\n* a\n* b\n\n---\n\n> Quoted";
+
+    let expected: Vec<Expr> = markdown_ast::markdown_to_ast(markdown)
+        .iter()
+        .map(block_to_expr)
+        .collect();
+
+    assert_eq!(expected.len(), 5);
+
+    assert_eq!(
+        markdown_to_block_exprs(markdown).collect::<Vec<_>>(),
+        expected
+    );
+}
+
+/// Tests that every kind of Markdown content in the shared `every-variant.md`
+/// fixture can be converted to an [`Expr`].
+#[test]
+fn test_every_variant_to_expr() {
+    let ast = markdown_ast::markdown_to_ast(include_str!(
+        "../../markdown-ast/tests/fixtures/every-variant.md"
+    ));
+
+    for block in &ast {
+        let _: Expr = block_to_expr(block);
+    }
+}
+
+/// Tests that `parse_markdown` converts a table into a
+/// `MarkdownElement["Table", ..]` expression.
+#[test]
+fn test_parse_markdown_table() {
+    let element =
+        |args: Vec<Expr>| Expr::normal(Symbol::new(MarkdownElement), args);
+
+    let text = |text: &str| {
+        Expr::list(vec![element(vec![
+            Expr::string("Text"),
+            Expr::string(text),
+        ])])
+    };
+
+    let expr = parse_markdown(vec![Expr::string(
+        "| Name | Value |\n|:-----|------:|\n| a    | 1     |\n| b    | 2     |",
+    )]);
+
+    assert_eq!(
+        expr,
+        Expr::list(vec![element(vec![
+            Expr::string("Table"),
+            Expr::list(vec![Expr::string("Left"), Expr::string("Right")]),
+            Expr::list(vec![text("Name"), text("Value")]),
+            Expr::list(vec![
+                Expr::list(vec![text("a"), text("1")]),
+                Expr::list(vec![text("b"), text("2")]),
+            ]),
+        ])])
+    );
+}
+
+/// Tests that `parse_markdown` converts an image, including its title, into a
+/// `MarkdownElement["Image", ..]` expression.
+#[test]
+fn test_parse_markdown_image() {
+    let element =
+        |args: Vec<Expr>| Expr::normal(Symbol::new(MarkdownElement), args);
+
+    let expr = parse_markdown(vec![Expr::string(r#"![alt](img.png "t")"#)]);
+
+    assert_eq!(
+        expr,
+        Expr::list(vec![element(vec![
+            Expr::string("Paragraph"),
+            Expr::list(vec![element(vec![
+                Expr::string("Image"),
+                Expr::list(vec![element(vec![
+                    Expr::string("Text"),
+                    Expr::string("alt"),
+                ])]),
+                Expr::string("img.png"),
+                Expr::string("t"),
+            ])]),
+        ])])
+    );
+}
+
+/// Tests that the kind of a GitHub-style alert block quote is included in its
+/// `MarkdownElement["BlockQuote", ..]` expression.
+#[test]
+fn test_parse_markdown_alert_block_quote() {
+    let element =
+        |args: Vec<Expr>| Expr::normal(Symbol::new(MarkdownElement), args);
+
+    let paragraph = element(vec![
+        Expr::string("Paragraph"),
+        Expr::list(vec![element(vec![
+            Expr::string("Text"),
+            Expr::string("text"),
+        ])]),
+    ]);
+
+    assert_eq!(
+        parse_markdown(vec![Expr::string("> [!WARNING]\n> text")]),
+        Expr::list(vec![element(vec![
+            Expr::string("BlockQuote"),
+            Expr::list(vec![paragraph.clone()]),
+            Expr::string("Warning"),
+        ])])
+    );
+
+    assert_eq!(
+        parse_markdown(vec![Expr::string("> text")]),
+        Expr::list(vec![element(vec![
+            Expr::string("BlockQuote"),
+            Expr::list(vec![paragraph]),
+        ])])
+    );
+}
@@ -113,10 +113,13 @@ markdownToCells[markdown0_] := ConfirmReplace[markdown0, {
 		]
 	),
 
+	(* Note:
+		The optional 3rd argument is the start number of an ordered list,
+		which is not currently shown in the notebook. *)
 	(* TID:240625/3: Converting simple lists to cells *)
-	element:MarkdownElement["List", listItems_List] :> Module[{},
+	element:MarkdownElement["List", listItems_List, _Integer...] :> Module[{},
 		(* TID:240630/1: Error for expected List children to be ListItem. *)
-		If[!MatchQ[listItems, {MarkdownElement["ListItem", {___}]..}],
+		If[!MatchQ[listItems, {MarkdownElement["ListItem", {___}, ___]..}],
 			Raise[
 				MarkdownError,
 				<| "MarkdownElement" -> element |>,
@@ -130,7 +133,10 @@ markdownToCells[markdown0_] := ConfirmReplace[markdown0, {
 		]
 	],
 
-	MarkdownElement["ListItem", listItemBlocks_List] :> Module[{},
+	(* Note:
+		The optional 3rd argument is the checkbox state of a task list item,
+		which is not currently shown in the notebook. *)
+	MarkdownElement["ListItem", listItemBlocks_List, _?BooleanQ...] :> Module[{},
 		(* Note:
 			Record the index of the inner block in this list item
 			we're inside of. This is used later to determine the cell style